@@ -0,0 +1,93 @@
+use crate::format::{marking_arguments, ARGUMENT_END, ARGUMENT_START};
+use crate::{BundleKind, Error, FluentErgo};
+use fluent::{FluentArgs, FluentValue};
+use std::ops::Range;
+
+/// A translation, and where the values of its arguments are in it, from
+/// `FluentErgo::tr_annotated`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedTranslation {
+    pub text: String,
+    /// Every argument value in `text`, in order
+    pub arguments: Vec<ArgumentSpan>,
+}
+
+/// Where the value of one argument is in an `AnnotatedTranslation`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArgumentSpan {
+    /// The byte range of the value in the text, with its isolation marks if it has any
+    pub range: Range<usize>,
+    /// The argument whose value this is. This is `None` when a function, such as `NUMBER`,
+    /// changed the value, so that it matches no argument.
+    pub name: Option<String>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Like `tr`, but also find where the value of each argument ended up in the translation, so
+    /// that an accessibility layer can announce the dynamic parts of a message distinctly, or a
+    /// user interface can make a user name a link.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id, String::from("unread = { $user }, you have { $count } messages"))
+    ///     .unwrap();
+    /// let mut args = fluent::FluentArgs::new();
+    /// args.set("user", "Ana");
+    /// args.set("count", 3);
+    ///
+    /// let annotated = fluent.tr_annotated("unread", Some(&args)).unwrap();
+    /// assert_eq!(annotated.text, "Ana, you have 3 messages");
+    /// let count = &annotated.arguments[1];
+    /// assert_eq!(count.name.as_deref(), Some("count"));
+    /// assert_eq!(&annotated.text[count.range.clone()], "3");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn tr_annotated(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+    ) -> Result<AnnotatedTranslation, Error> {
+        let marked = marking_arguments(|| self.tr(msgid, args))?;
+        let mut annotated = AnnotatedTranslation {
+            text: String::with_capacity(marked.len()),
+            arguments: Vec::new(),
+        };
+        let mut start = None;
+        for c in marked.chars() {
+            match c {
+                ARGUMENT_START => start = Some(annotated.text.len()),
+                ARGUMENT_END => {
+                    if let Some(start) = start.take() {
+                        let range = start..annotated.text.len();
+                        let name = argument_named(args, &annotated.text[range.clone()]);
+                        annotated.arguments.push(ArgumentSpan { range, name });
+                    }
+                }
+                _ => annotated.text.push(c),
+            }
+        }
+        Ok(annotated)
+    }
+}
+
+/// The argument whose value is `text`, leaving out any isolation marks.
+fn argument_named(args: Option<&FluentArgs>, text: &str) -> Option<String> {
+    let text = text
+        .strip_prefix('\u{2068}')
+        .and_then(|text| text.strip_suffix('\u{2069}'))
+        .unwrap_or(text);
+    args?.iter().find_map(|(name, value)| {
+        let matches = match value {
+            FluentValue::String(s) => s == text,
+            FluentValue::Number(n) => n.as_string() == text,
+            _ => false,
+        };
+        matches.then(|| String::from(name))
+    })
+}
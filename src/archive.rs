@@ -0,0 +1,203 @@
+use crate::{parse_language, parse_text, BundleKind, Error, FluentErgo, Origin};
+use flate2::read::DeflateDecoder;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Load every language in a `.zip` archive, such as a downloadable language pack. The
+    /// archive is laid out like the directory for `add_from_dir`: each top-level folder is named
+    /// for a language, and every `.ftl` file inside of it is loaded for that language. Other
+    /// files are ignored. `origin_of` reports messages as coming from `Origin::Archive`.
+    ///
+    /// ```ignore
+    /// pack.zip
+    ///     de/
+    ///         main.ftl
+    ///     fr/
+    ///         main.ftl
+    ///         dialogue/intro.ftl
+    /// ```
+    ///
+    /// Files may be stored or deflated. Encrypted and ZIP64 archives are not supported. Packs in
+    /// another format can be unpacked by the application and passed to `add_many`.
+    ///
+    /// Files are parsed and added in the same way as `add_many`.
+    ///
+    /// # Errors
+    ///
+    /// * `IOError` -- the archive cannot be read, is not a ZIP archive, or uses a feature which is
+    ///   not supported
+    /// * `InvalidLanguage` -- a folder name is not a valid language identifier
+    /// * All of the errors that `add_many` can return. The paths in them are the archive's path
+    ///   joined with the name of the file inside it.
+    ///
+    pub fn add_from_archive(&mut self, path: &Path) -> Result<(), Error> {
+        let data = fs::read(path).map_err(|err| Error::from(err).in_file(path))?;
+        self.add_archive_data(path, &data)
+    }
+
+    /// Like `add_from_archive`, but the archive is only loaded if `signature` is a valid ed25519
+    /// signature of the whole file by one of `trusted_keys`, so that a game can accept community
+    /// language packs without silently accepting ones which were tampered with. The signature is
+    /// the 64 raw bytes, usually shipped alongside the pack, and each key is the 32 raw bytes of
+    /// an ed25519 public key. Nothing is parsed until the signature has been checked.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidSignature` -- no trusted key signed the archive
+    /// * All of the errors that `add_from_archive` can return
+    ///
+    #[cfg(feature = "signed")]
+    pub fn add_from_signed_archive(
+        &mut self,
+        path: &Path,
+        signature: &[u8],
+        trusted_keys: &[[u8; 32]],
+    ) -> Result<(), Error> {
+        use ring::signature::{UnparsedPublicKey, ED25519};
+
+        let data = fs::read(path).map_err(|err| Error::from(err).in_file(path))?;
+        let trusted = trusted_keys.iter().any(|key| {
+            UnparsedPublicKey::new(&ED25519, key)
+                .verify(&data, signature)
+                .is_ok()
+        });
+        if !trusted {
+            return Err(Error::InvalidSignature {
+                path: path.to_path_buf(),
+            });
+        }
+        self.add_archive_data(path, &data)
+    }
+
+    fn add_archive_data(&mut self, path: &Path, data: &[u8]) -> Result<(), Error> {
+        let mut files = zip_entries(data).map_err(|err| Error::from(err).in_file(path))?;
+        files.retain(|file| file.name.ends_with(".ftl") && file.name.contains('/'));
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut resources = Vec::new();
+        for file in files {
+            let entry_path = path.join(&file.name);
+            let (folder, _) = file.name.split_once('/').unwrap_or_default();
+            let lang = parse_language(folder)?;
+            let bytes = file
+                .contents(data)
+                .map_err(|err| Error::from(err).in_file(&entry_path))?;
+            let mut res = String::from_utf8(bytes)
+                .map_err(Error::from)
+                .and_then(|text| parse_text(&self.options, text, None))
+                .map_err(|err| err.in_file(&entry_path).in_language(&lang))?;
+            res.origin = Origin::Archive {
+                archive: path.to_path_buf(),
+                entry: file.name,
+            };
+            resources.push((lang, res));
+        }
+        self.accept_languages(resources.iter().map(|(lang, _)| lang))?;
+        self.add_parsed(resources)
+    }
+}
+
+/// A file in a ZIP archive, from its central directory.
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+impl ZipEntry {
+    fn contents(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let header = data
+            .get(self.header_offset..)
+            .ok_or_else(|| invalid("truncated"))?;
+        if read_u32(header, 0)? != LOCAL_HEADER {
+            return Err(invalid("bad local file header"));
+        }
+        let start = 30 + usize::from(read_u16(header, 26)?) + usize::from(read_u16(header, 28)?);
+        let compressed = header
+            .get(start..start + self.compressed_size)
+            .ok_or_else(|| invalid("truncated"))?;
+        let contents = match self.method {
+            0 => compressed.to_vec(),
+            8 => {
+                let mut contents = Vec::with_capacity(self.size);
+                DeflateDecoder::new(compressed).read_to_end(&mut contents)?;
+                contents
+            }
+            _ => return Err(invalid("unsupported compression method")),
+        };
+        if contents.len() != self.size || crc32fast::hash(&contents) != self.crc {
+            return Err(invalid("checksum mismatch"));
+        }
+        Ok(contents)
+    }
+}
+
+/// Read the central directory of a ZIP archive.
+fn zip_entries(data: &[u8]) -> io::Result<Vec<ZipEntry>> {
+    // The end of directory record is 22 bytes, followed by a comment of up to 65535 bytes.
+    let end = (0..=data.len().saturating_sub(22))
+        .rev()
+        .take(65536)
+        .find(|&offset| read_u32(data, offset).ok() == Some(END_OF_DIRECTORY))
+        .ok_or_else(|| invalid("not a ZIP archive"))?;
+    let count = read_u16(data, end + 10)?;
+    let mut offset = read_u32(data, end + 16)? as usize;
+    if count == u16::MAX || offset == u32::MAX as usize {
+        return Err(invalid("ZIP64 archives are not supported"));
+    }
+
+    let mut entries = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        if read_u32(data, offset)? != DIRECTORY_ENTRY {
+            return Err(invalid("bad central directory"));
+        }
+        if read_u16(data, offset + 8)? & 1 != 0 {
+            return Err(invalid("encrypted archives are not supported"));
+        }
+        let name_len = usize::from(read_u16(data, offset + 28)?);
+        let name = data
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(|| invalid("truncated"))?;
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: read_u16(data, offset + 10)?,
+            crc: read_u32(data, offset + 16)?,
+            compressed_size: read_u32(data, offset + 20)? as usize,
+            size: read_u32(data, offset + 24)? as usize,
+            header_offset: read_u32(data, offset + 42)? as usize,
+        });
+        offset += 46
+            + name_len
+            + usize::from(read_u16(data, offset + 30)?)
+            + usize::from(read_u16(data, offset + 32)?);
+    }
+    Ok(entries)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> io::Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid("truncated"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid("truncated"))
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("ZIP archive: {}", reason),
+    )
+}
@@ -0,0 +1,125 @@
+//! Check and compare directories of Fluent translations, laid out as `FluentErgo::add_from_dir`
+//! expects.
+//!
+//! ```text
+//! fluent-ergo check <dir>
+//! fluent-ergo coverage <dir> <base-language>
+//! fluent-ergo diff <dir> <base-language> <language>
+//! fluent-ergo export <dir> <base-language> <language>
+//! fluent-ergo keys <dir> <language> <source-dir>
+//! ```
+
+use fluent_ergonomics::{canonicalize_language, extract_from_dir, Error, FluentErgo};
+use std::env;
+use std::path::Path;
+use std::process;
+use unic_langid::LanguageIdentifier;
+
+const USAGE: &str = "usage:
+    fluent-ergo check <dir>
+    fluent-ergo coverage <dir> <base-language>
+    fluent-ergo diff <dir> <base-language> <language>
+    fluent-ergo export <dir> <base-language> <language>
+    fluent-ergo keys <dir> <language> <source-dir>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["check", dir] => check(Path::new(dir)),
+        ["coverage", dir, base] => coverage(Path::new(dir), base),
+        ["diff", dir, base, lang] => diff(Path::new(dir), base, lang),
+        ["export", dir, base, lang] => export(Path::new(dir), base, lang),
+        ["keys", dir, lang, src] => keys(Path::new(dir), lang, Path::new(src)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    match result {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn load(dir: &Path) -> Result<FluentErgo, Error> {
+    let mut fluent = FluentErgo::new(&[]);
+    fluent.add_from_dir(dir)?;
+    Ok(fluent)
+}
+
+/// Parse a language tag the way the translator would look it up, so that `iw` finds `he`.
+fn language(tag: &str) -> Result<LanguageIdentifier, Error> {
+    tag.parse()
+        .map(|lang| canonicalize_language(&lang))
+        .map_err(|_| Error::InvalidLanguage(String::from(tag)))
+}
+
+/// Load every language, and list how many messages each one has.
+fn check(dir: &Path) -> Result<bool, Error> {
+    let fluent = load(dir)?;
+    for lang in fluent.loaded_languages() {
+        println!("{}: {} messages", lang, fluent.message_ids(&lang).len());
+    }
+    Ok(true)
+}
+
+/// Show how much of the base language every other language translates.
+fn coverage(dir: &Path, base: &str) -> Result<bool, Error> {
+    let fluent = load(dir)?;
+    let base = language(base)?;
+    let mut complete = true;
+    for lang in fluent.loaded_languages() {
+        let diff = fluent.diff_languages(&base, &lang);
+        complete &= diff.missing.is_empty();
+        println!(
+            "{}: {}/{} ({:.0}%)",
+            lang,
+            diff.total - diff.missing.len(),
+            diff.total,
+            diff.coverage() * 100.0
+        );
+    }
+    Ok(complete)
+}
+
+/// List the messages that one language is missing, or has left over, compared to the base.
+fn diff(dir: &Path, base: &str, lang: &str) -> Result<bool, Error> {
+    let fluent = load(dir)?;
+    let diff = fluent.diff_languages(&language(base)?, &language(lang)?);
+    for id in &diff.missing {
+        println!("- {}", id);
+    }
+    for id in &diff.extra {
+        println!("+ {}", id);
+    }
+    Ok(diff.missing.is_empty())
+}
+
+/// Write one language side by side with the base as CSV, for reviewers and spreadsheets.
+fn export(dir: &Path, base: &str, lang: &str) -> Result<bool, Error> {
+    let fluent = load(dir)?;
+    print!(
+        "{}",
+        fluent.export_review(&language(base)?, &language(lang)?)?
+    );
+    Ok(true)
+}
+
+/// List the message IDs that the code uses but the language lacks, and the messages that the code
+/// never uses.
+fn keys(dir: &Path, lang: &str, src: &Path) -> Result<bool, Error> {
+    let fluent = load(dir)?;
+    let report = fluent.check_keys(&language(lang)?, &extract_from_dir(src)?);
+    for id in &report.unknown {
+        println!("unknown: {}", id);
+    }
+    for id in &report.unused {
+        println!("unused: {}", id);
+    }
+    Ok(report.unknown.is_empty())
+}
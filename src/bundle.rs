@@ -0,0 +1,213 @@
+use crate::format::format_argument;
+use crate::functions::{builtins, Function};
+use fluent::{FluentArgs, FluentError, FluentResource};
+use fluent_syntax::ast;
+use std::fmt;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// The kind of Fluent bundle that a translator keeps its messages in. Fluent caches plural rules
+/// and other locale data in each bundle; the cache of a `Concurrent` bundle is synchronized so
+/// that the translator can be shared between threads, and the cache of a `SingleThreaded` bundle
+/// is not, which saves that overhead in applications which only ever translate on one thread.
+///
+/// ```
+/// use fluent_ergonomics::{FluentErgo, SingleThreaded};
+///
+/// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+/// let mut fluent: FluentErgo<SingleThreaded> = FluentErgo::new_with_kind(&[en_id.clone()]);
+/// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+/// assert_eq!(fluent.tr("units", None).unwrap(), "Units");
+/// ```
+///
+/// This trait is sealed, and cannot be implemented outside of this crate.
+pub trait BundleKind: sealed::Sealed + Clone + Default + 'static {
+    #[doc(hidden)]
+    type Bundle;
+
+    /// A new bundle for `lang`, with every function in `functions` registered.
+    #[doc(hidden)]
+    fn new_bundle(lang: LanguageIdentifier, functions: &[(String, Function)]) -> Self::Bundle;
+
+    #[doc(hidden)]
+    fn add_function(
+        bundle: &mut Self::Bundle,
+        name: &str,
+        function: &Function,
+    ) -> Result<(), FluentError>;
+
+    #[doc(hidden)]
+    fn add_resource(
+        bundle: &mut Self::Bundle,
+        resource: Arc<FluentResource>,
+        replace: bool,
+    ) -> Result<(), Vec<FluentError>>;
+
+    /// Write the value of a message to `out`, or return `None` if the bundle has no value for it.
+    #[doc(hidden)]
+    fn write<W: fmt::Write>(
+        bundle: &Self::Bundle,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        out: &mut W,
+        errors: &mut Vec<FluentError>,
+    ) -> Option<()>;
+
+    /// Write a pattern from the bundle's resources to `out`.
+    #[doc(hidden)]
+    fn write_pattern<W: fmt::Write>(
+        bundle: &Self::Bundle,
+        pattern: &ast::Pattern<&str>,
+        args: Option<&FluentArgs>,
+        out: &mut W,
+        errors: &mut Vec<FluentError>,
+    );
+
+    #[doc(hidden)]
+    fn format_pattern(
+        bundle: &Self::Bundle,
+        pattern: &ast::Pattern<&str>,
+        args: Option<&FluentArgs>,
+        errors: &mut Vec<FluentError>,
+    ) -> String;
+
+    /// The defaults which the message gives for its arguments in `default-<name>` attributes.
+    #[doc(hidden)]
+    fn default_args(bundle: &Self::Bundle, msgid: &str) -> Vec<(String, String)>;
+
+    /// Whether the bundle's message has a value, or `None` if it has no such message.
+    #[doc(hidden)]
+    fn has_value(bundle: &Self::Bundle, msgid: &str) -> Option<bool>;
+}
+
+/// Bundles which can be shared between threads. This is the default. Translators of this kind
+/// are always `Send + Sync`, whichever features are enabled, and have `tr_async` and the other
+/// futures for multi-threaded async runtimes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Concurrent;
+
+/// Bundles which can only be used from one thread at a time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SingleThreaded;
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Concurrent {}
+    impl Sealed for super::SingleThreaded {}
+}
+
+/// Both kinds of bundle have exactly the same methods apart from their constructors, but they are
+/// different types.
+macro_rules! bundle_kind {
+    ($kind:ty, $bundle:ty, $new:ident) => {
+        impl BundleKind for $kind {
+            type Bundle = $bundle;
+
+            fn new_bundle(
+                lang: LanguageIdentifier,
+                functions: &[(String, Function)],
+            ) -> Self::Bundle {
+                let builtins = builtins(&lang);
+                let mut bundle = <$bundle>::$new(vec![lang]);
+                bundle.set_formatter(Some(format_argument));
+                let builtins = builtins.iter().map(|(name, function)| (*name, function));
+                let functions = functions
+                    .iter()
+                    .map(|(name, function)| (name.as_str(), function));
+                for (name, function) in builtins.chain(functions) {
+                    // Names are checked for conflicts when functions are registered.
+                    let _ = Self::add_function(&mut bundle, name, function);
+                }
+                bundle
+            }
+
+            fn add_function(
+                bundle: &mut Self::Bundle,
+                name: &str,
+                function: &Function,
+            ) -> Result<(), FluentError> {
+                let function = Arc::clone(function);
+                let lang = bundle.locales.first().cloned().unwrap_or_default();
+                bundle.add_function(name, move |positional, named| {
+                    function(&lang, positional, named)
+                })
+            }
+
+            fn add_resource(
+                bundle: &mut Self::Bundle,
+                resource: Arc<FluentResource>,
+                replace: bool,
+            ) -> Result<(), Vec<FluentError>> {
+                if replace {
+                    bundle.add_resource_overriding(resource);
+                    Ok(())
+                } else {
+                    bundle.add_resource(resource)
+                }
+            }
+
+            fn write<W: fmt::Write>(
+                bundle: &Self::Bundle,
+                msgid: &str,
+                args: Option<&FluentArgs>,
+                out: &mut W,
+                errors: &mut Vec<FluentError>,
+            ) -> Option<()> {
+                let pattern = bundle.get_message(msgid)?.value()?;
+                // Writing to a `String` cannot fail.
+                let _ = bundle.write_pattern(out, pattern, args, errors);
+                Some(())
+            }
+
+            fn write_pattern<W: fmt::Write>(
+                bundle: &Self::Bundle,
+                pattern: &ast::Pattern<&str>,
+                args: Option<&FluentArgs>,
+                out: &mut W,
+                errors: &mut Vec<FluentError>,
+            ) {
+                // Only the writer can fail, and it leaves what it was given so far in place.
+                let _ = bundle.write_pattern(out, pattern, args, errors);
+            }
+
+            fn format_pattern(
+                bundle: &Self::Bundle,
+                pattern: &ast::Pattern<&str>,
+                args: Option<&FluentArgs>,
+                errors: &mut Vec<FluentError>,
+            ) -> String {
+                bundle.format_pattern(pattern, args, errors).into_owned()
+            }
+
+            fn default_args(bundle: &Self::Bundle, msgid: &str) -> Vec<(String, String)> {
+                let msg = match bundle.get_message(msgid) {
+                    Some(msg) => msg,
+                    None => return Vec::new(),
+                };
+                msg.attributes()
+                    .filter_map(|attr| {
+                        let name = attr.id().strip_prefix("default-")?;
+                        let mut errors = Vec::new();
+                        let value = bundle.format_pattern(attr.value(), None, &mut errors);
+                        Some((String::from(name), value.into_owned()))
+                    })
+                    .collect()
+            }
+
+            fn has_value(bundle: &Self::Bundle, msgid: &str) -> Option<bool> {
+                bundle.get_message(msgid).map(|msg| msg.value().is_some())
+            }
+        }
+    };
+}
+
+bundle_kind!(
+    Concurrent,
+    fluent::concurrent::FluentBundle<Arc<FluentResource>>,
+    new_concurrent
+);
+bundle_kind!(
+    SingleThreaded,
+    fluent::FluentBundle<Arc<FluentResource>>,
+    new
+);
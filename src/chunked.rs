@@ -0,0 +1,70 @@
+use crate::{canonicalize_language, parse_text, BundleKind, Error, FluentErgo, ParsedResource};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use unic_langid::LanguageIdentifier;
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Like `add_from_file`, but the file is read a line at a time, and split into resources of
+    /// about `chunk_bytes` each, so that an enormous catalog never has to be held in memory as
+    /// one string alongside its parsed form. Chunks are only ever split before a message or
+    /// term, and never between one and the comment above it.
+    ///
+    /// Each chunk is parsed and added before the next one is read. If a chunk fails, the chunks
+    /// before it stay added. Terms are validated once the whole file has been added. A language
+    /// which is unloaded by `set_unload_after` reads the file back in one piece.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `add_from_file` can return
+    ///
+    pub fn add_from_file_chunked(
+        &mut self,
+        lang: LanguageIdentifier,
+        path: &Path,
+        chunk_bytes: usize,
+    ) -> Result<(), Error> {
+        self.accept_languages(std::slice::from_ref(&lang))?;
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        let add_chunk = |chunk: Vec<u8>| -> Result<(), Error> {
+            let res = String::from_utf8(chunk)
+                .map_err(Error::from)
+                .and_then(|text| parse_text(&self.options, text, Some(path.to_path_buf())))
+                .map_err(|err| err.in_file(path).in_language(&lang))?;
+            let (_, result) = self.insert_parsed(
+                vec![(lang.clone(), ParsedResource { modified, ..res })],
+                self.options.duplicate_messages,
+            );
+            result
+        };
+
+        let mut reader =
+            BufReader::new(File::open(path).map_err(|err| Error::from(err).in_file(path))?);
+        let mut chunk = Vec::new();
+        let mut line = Vec::new();
+        let mut after_comment = false;
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|err| Error::from(err).in_file(path))?;
+            if read == 0 {
+                break;
+            }
+            let starts_entry = line[0].is_ascii_alphabetic() || line[0] == b'-' || line[0] == b'#';
+            if starts_entry
+                && !after_comment
+                && chunk.len() >= chunk_bytes
+                && !chunk.iter().all(u8::is_ascii_whitespace)
+            {
+                add_chunk(std::mem::take(&mut chunk))?;
+            }
+            after_comment = line[0] == b'#';
+            chunk.extend_from_slice(&line);
+        }
+        if !chunk.is_empty() {
+            add_chunk(chunk)?;
+        }
+        self.validate_terms(&[canonicalize_language(&lang)])
+    }
+}
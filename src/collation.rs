@@ -0,0 +1,40 @@
+use crate::{BundleKind, FluentErgo};
+use icu_collator::options::CollatorOptions;
+use icu_collator::Collator;
+use icu_locale_core::Locale;
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Sort `items`, usually translated labels for a menu or a country picker, the way the first
+    /// language of the fallback chain sorts text, instead of by code point. In Swedish, `ö` sorts
+    /// after `z`; in German, it sorts with `o`.
+    ///
+    /// ```
+    /// let sv_id = "sv".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let fluent = fluent_ergonomics::FluentErgo::new(&[sv_id]);
+    /// let mut countries = vec![
+    ///     String::from("Österrike"),
+    ///     String::from("Zambia"),
+    ///     String::from("Oman"),
+    /// ];
+    /// fluent.sort_localized(&mut countries);
+    /// assert_eq!(countries, ["Oman", "Zambia", "Österrike"]);
+    /// ```
+    ///
+    /// Languages that the collation data does not know are sorted by the root collation order,
+    /// which still handles accents and case sensibly.
+    ///
+    /// This requires the `icu` feature.
+    pub fn sort_localized(&self, items: &mut [String]) {
+        let locale = self
+            .languages
+            .first()
+            .and_then(|lang| lang.to_string().parse::<Locale>().ok())
+            .unwrap_or(Locale::UNKNOWN);
+        let collator = Collator::try_new(locale.into(), CollatorOptions::default())
+            .or_else(|_| Collator::try_new(Locale::UNKNOWN.into(), CollatorOptions::default()));
+        match collator {
+            Ok(collator) => items.sort_by(|a, b| collator.compare(a, b)),
+            Err(_) => items.sort(),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::language::parse_language;
+use crate::{read_file, Error, FluentErgo, UnparsedResource};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The manifest that `FluentErgo::from_config` reads.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    languages: Vec<String>,
+    #[serde(default)]
+    resources: BTreeMap<String, Vec<String>>,
+}
+
+impl FluentErgo {
+    /// Build a translator from a manifest file, so that a deployment can change its set of
+    /// languages without recompiling. The manifest lists the languages to search in fallback
+    /// order, and the translation files for each language:
+    ///
+    /// ```toml
+    /// languages = ["eo", "en-US"]
+    ///
+    /// [resources]
+    /// en-US = ["locales/en-US/*.ftl"]
+    /// eo = ["locales/eo/main.ftl", "locales/eo/errors.ftl"]
+    /// ```
+    ///
+    /// Files ending in `.json` are read as JSON with the same structure, and anything else is read
+    /// as TOML. Paths are relative to the directory of the manifest, and the last component of a
+    /// path may use `*` to match any number of characters. Every file is loaded in the same way as
+    /// `add_many`.
+    ///
+    /// This requires the `config` feature.
+    ///
+    /// # Errors
+    ///
+    /// * `ConfigError` -- the manifest is not valid TOML or JSON, or has unexpected fields
+    /// * `InvalidLanguage`
+    /// * All of the errors that `add_from_file` can return
+    ///
+    pub fn from_config(path: &Path) -> Result<FluentErgo, Error> {
+        let text = read_file(path)?;
+        let manifest: Manifest = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|err| config_error(path, err))?
+        } else {
+            toml::from_str(&text).map_err(|err| config_error(path, err))?
+        };
+
+        let languages = manifest
+            .languages
+            .iter()
+            .map(|tag| parse_language(tag))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut fluent = FluentErgo::new(&languages);
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut resources = Vec::new();
+        for (tag, patterns) in &manifest.resources {
+            let lang = parse_language(tag)?;
+            for pattern in patterns {
+                for file in expand(&base.join(pattern))? {
+                    resources.push((lang.clone(), UnparsedResource::File(file)));
+                }
+            }
+        }
+        fluent.add_unparsed(resources)?;
+        Ok(fluent)
+    }
+}
+
+fn config_error(path: &Path, err: impl std::fmt::Display) -> Error {
+    Error::ConfigError {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    }
+}
+
+/// Find the files that a manifest path refers to, in sorted order. Only the file name may contain
+/// wildcards.
+fn expand(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let name = match pattern.file_name().and_then(|name| name.to_str()) {
+        Some(name) if name.contains('*') => name,
+        _ => return Ok(vec![pattern.to_path_buf()]),
+    };
+    let dir = pattern.parent().unwrap_or_else(|| Path::new(""));
+    let mut files = Vec::new();
+    for entry in dir
+        .read_dir()
+        .map_err(|err| Error::from(err).in_file(dir))?
+    {
+        let file = entry.map_err(|err| Error::from(err).in_file(dir))?.path();
+        let matches = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|file_name| wildcard_match(name, file_name));
+        if matches && file.is_file() {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Match a name against a pattern in which `*` matches any number of characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,117 @@
+use crate::{BundleEntry, BundleKind, Error, FluentErgo, Origin};
+use fluent::{FluentError, FluentResource};
+use std::path::PathBuf;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// What happens when a resource defines a message or term which its language already has.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicateMessages {
+    /// Keep the first definition, and return `Error::DuplicateMessages` once the rest of the
+    /// resource has been added.
+    #[default]
+    Error,
+    /// Keep the first definition quietly.
+    FirstWins,
+    /// Replace the earlier definition. Languages which have been changed this way are never
+    /// unloaded by `set_unload_after`.
+    LastWins,
+}
+
+/// A message or term which two resources of one language both define, from
+/// `Error::DuplicateMessages`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageConflict {
+    /// The message ID, or the term ID with its leading `-`
+    pub id: String,
+    /// Where the definition that was kept came from
+    pub first: Origin,
+    /// Where the definition that was ignored came from
+    pub second: Origin,
+}
+
+impl<K: BundleKind> BundleEntry<K> {
+    /// Add a resource under the policy for duplicate messages, reporting them as conflicts
+    /// between the origins of both definitions.
+    pub(crate) fn add_checked(
+        &mut self,
+        lang: &LanguageIdentifier,
+        hash: u64,
+        resource: FluentResource,
+        path: Option<PathBuf>,
+        policy: DuplicateMessages,
+    ) -> Result<(), Error> {
+        let replace = policy == DuplicateMessages::LastWins;
+        let errors = match self.add(hash, Arc::new(resource), replace) {
+            Ok(()) => return Ok(()),
+            Err(_) if policy == DuplicateMessages::FirstWins => return Ok(()),
+            Err(errors) => errors,
+        };
+        let second = self.origins.get(&hash).cloned().unwrap_or(Origin::Text);
+        let mut conflicts = Vec::new();
+        for error in &errors {
+            let id = match error {
+                // Fluent does not export the type of `kind`, only its name.
+                FluentError::Overriding { kind, id } if kind.to_string() == "term" => {
+                    format!("-{}", id)
+                }
+                FluentError::Overriding { id, .. } => id.clone(),
+                _ => {
+                    return Err(Error::FluentError {
+                        lang: Some(lang.clone()),
+                        path,
+                        errors,
+                    })
+                }
+            };
+            let first = self.origin_of(&id).unwrap_or(Origin::Text);
+            // Environment overlays are meant to shadow the base catalog, whenever it is added.
+            if let Origin::Environment(_) = first {
+                continue;
+            }
+            conflicts.push(MessageConflict {
+                first,
+                second: second.clone(),
+                id,
+            });
+        }
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        Err(Error::DuplicateMessages {
+            lang: lang.clone(),
+            path,
+            conflicts,
+        })
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Choose what happens when a resource defines a message which its language already has.
+    /// The default, `DuplicateMessages::Error`, reports every duplicate along with where both
+    /// definitions came from:
+    ///
+    /// ```
+    /// use fluent_ergonomics::{Error, Origin};
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_labeled_text(en_id.clone(), "main", String::from("units = Units"))
+    ///     .unwrap();
+    /// match fluent.add_labeled_text(en_id, "extra", String::from("units = Measures")) {
+    ///     Err(Error::DuplicateMessages { conflicts, .. }) => {
+    ///         assert_eq!(conflicts[0].id, "units");
+    ///         assert_eq!(conflicts[0].first, Origin::Label(String::from("main")));
+    ///         assert_eq!(conflicts[0].second, Origin::Label(String::from("extra")));
+    ///     }
+    ///     _ => panic!("expected a conflict"),
+    /// }
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Units");
+    /// ```
+    ///
+    /// This does not apply to `merge`, which has its own `MergeConflicts`.
+    pub fn set_duplicate_messages(&mut self, policy: DuplicateMessages) {
+        Arc::make_mut(&mut self.options).duplicate_messages = policy;
+    }
+}
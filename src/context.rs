@@ -0,0 +1,55 @@
+use crate::{BundleKind, Error, FluentErgo, TrOptions};
+use fluent::FluentArgs;
+
+/// What goes between a message ID and its context in the ID of a message with a context. Fluent
+/// IDs may only have letters, digits, `-` and `_` in them, so gettext's `@` is not possible.
+const CONTEXT_SEPARATOR: &str = "--";
+
+/// The ID of the message `msgid` in the context `context`, as `FluentErgo::tr_ctx` looks it up:
+/// `open--verb` for `open` as a verb. Migration tools can name the messages they convert from
+/// gettext entries with a `msgctxt` with this.
+///
+/// ```
+/// assert_eq!(fluent_ergonomics::context_id("open", "verb"), "open--verb");
+/// ```
+pub fn context_id(msgid: &str, context: &str) -> String {
+    format!("{}{}{}", msgid, CONTEXT_SEPARATOR, context)
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Translate `msgid` in the context `context`, for words that need different translations
+    /// depending on what they mean, such as "Open" as a verb and as an adjective. The message
+    /// with the context is `context_id(msgid, context)`, and if no language has it, the message
+    /// without the context is used instead.
+    ///
+    /// ```
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone()]);
+    /// fluent
+    ///     .add_from_text(de_id, String::from("open = Öffnen\nopen--state = Geöffnet"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(fluent.tr_ctx("open", "state", None).unwrap(), "Geöffnet");
+    /// assert_eq!(fluent.tr_ctx("open", "verb", None).unwrap(), "Öffnen");
+    /// ```
+    ///
+    /// As with a `msgctxt` in gettext, the message with the context is searched for in every
+    /// language of the fallback chain before the message without it, so a translation which
+    /// only has the message without the context shows the next language's message with it.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return. A missing message is reported as `msgid`.
+    ///
+    pub fn tr_ctx(
+        &self,
+        msgid: &str,
+        context: &str,
+        args: Option<&FluentArgs>,
+    ) -> Result<String, Error> {
+        match self.find(&context_id(msgid, context), args, &TrOptions::default())? {
+            Some(translation) => Ok(translation),
+            None => self.tr(msgid, args),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    canonicalize_language, parse_text, BundleEntry, BundleKind, Error, FluentErgo, PoisonExt,
+};
+use fluent_syntax::{ast, serializer};
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use unic_langid::LanguageIdentifier;
+
+/// The changes to one language in a catalog update, for `FluentErgo::apply_delta`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LanguageDelta {
+    pub lang: LanguageIdentifier,
+    /// Fluent text with every message and term which was added or changed
+    pub changed: String,
+    /// The IDs of messages which were removed, and of terms with their leading `-`
+    pub removed: Vec<String>,
+}
+
+/// The ID of a message, or of a term with its leading `-`.
+fn entry_id(entry: &ast::Entry<&str>) -> Option<String> {
+    match entry {
+        ast::Entry::Message(msg) => Some(String::from(msg.id.name)),
+        ast::Entry::Term(term) => Some(format!("-{}", term.id.name)),
+        _ => None,
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Apply an update which only carries the messages that changed, so that an over-the-air
+    /// update can be a few kilobytes instead of the whole catalog. For each language, the
+    /// messages in `changed` replace any loaded messages with the same IDs, and the messages in
+    /// `removed` are dropped. Lazy resources for the languages are loaded first.
+    ///
+    /// Every language is rebuilt off to the side and swapped in under one lock, so a translation
+    /// sees either none of the update or all of it. If any part of it fails to parse, nothing
+    /// changes. Messages from `set_override` still take precedence, and languages which have had
+    /// a delta applied are never unloaded by `set_unload_after`.
+    ///
+    /// ```
+    /// use fluent_ergonomics::LanguageDelta;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("units = Units\nold = Old"))
+    ///     .unwrap();
+    ///
+    /// fluent
+    ///     .apply_delta(vec![LanguageDelta {
+    ///         lang: en_id,
+    ///         changed: String::from("units = Measures\nnew = New"),
+    ///         removed: vec![String::from("old")],
+    ///     }])
+    ///     .unwrap();
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Measures");
+    /// assert_eq!(fluent.tr("new", None).unwrap(), "New");
+    /// assert!(fluent.tr("old", None).is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * `FluentParserError` -- some `changed` text does not parse
+    /// * `FluentError`
+    /// * Any error from loading a lazy resource, as for `tr`
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    ///
+    pub fn apply_delta(&mut self, deltas: Vec<LanguageDelta>) -> Result<(), Error> {
+        trace_span!("apply_delta", languages = deltas.len());
+        self.accept_languages(deltas.iter().map(|delta| &delta.lang))?;
+        let mut parsed = Vec::new();
+        for delta in deltas {
+            let lang = canonicalize_language(&delta.lang);
+            let res = parse_text(&self.options, delta.changed, None)
+                .map_err(|err| err.in_language(&lang))?;
+            parsed.push((lang, res, delta.removed));
+        }
+        let touched: Vec<LanguageIdentifier> =
+            parsed.iter().map(|(lang, _, _)| lang.clone()).collect();
+        self.load_lazy(&touched)?;
+
+        let mut added = Vec::new();
+        let mut bundles = self.bundles.write().or_poisoned()?;
+        let functions = self.functions.read().or_poisoned()?.clone();
+        let mut entries = Vec::new();
+        for (lang, res, removed) in parsed {
+            let mut dropped: HashSet<String> = removed.into_iter().collect();
+            dropped.extend(res.resource.entries().filter_map(entry_id));
+
+            let mut entry = BundleEntry::<K>::new(lang.clone(), &functions);
+            if let Some(old) = bundles.get(&lang) {
+                let old = old.read().or_poisoned()?;
+                entry.modified = old.modified;
+                for (hash, resource) in &old.resources {
+                    let replace = old.overriding.contains(hash);
+                    let origin = old.origins.get(hash).cloned();
+                    let touches = resource
+                        .entries()
+                        .filter_map(entry_id)
+                        .any(|id| dropped.contains(&id));
+                    let (hash, resource) = if touches {
+                        let kept = ast::Resource {
+                            body: resource
+                                .entries()
+                                .filter(|e| entry_id(e).is_none_or(|id| !dropped.contains(&id)))
+                                .cloned()
+                                .collect(),
+                        };
+                        let text = serializer::serialize(&kept);
+                        let rewritten = parse_text(&self.options, text, None)
+                            .map_err(|err| err.in_language(&lang))?;
+                        (rewritten.hash, Arc::new(rewritten.resource))
+                    } else {
+                        (*hash, Arc::clone(resource))
+                    };
+                    if let Some(origin) = origin {
+                        entry.origins.insert(hash, origin);
+                    }
+                    // These messages were already added once, so any errors were already
+                    // reported.
+                    let _ = entry.add(hash, resource, replace);
+                }
+            }
+            // Everything `changed` defines was dropped above, so replacing cannot hide anything,
+            // but it keeps the language from being unloaded and read back without the delta.
+            entry
+                .add(res.hash, Arc::new(res.resource), true)
+                .map_err(|errors| Error::FluentError {
+                    lang: Some(lang.clone()),
+                    path: None,
+                    errors,
+                })?;
+            entry.origins.insert(res.hash, res.origin);
+            added.push((lang.clone(), res.hash));
+            entries.push((lang, entry));
+        }
+        for (lang, entry) in entries {
+            bundles.insert(lang, Arc::new(RwLock::new(entry)));
+        }
+        drop(bundles);
+        self.invalidate_missing();
+        self.check_staleness(&touched);
+        self.lint_added(&added);
+        self.validate_terms(&touched)
+    }
+}
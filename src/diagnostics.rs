@@ -0,0 +1,136 @@
+use crate::{BundleKind, FluentErgo, Origin, PoisonExt, UnparsedResource};
+use fluent_syntax::ast;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A snapshot of a translator's configuration and catalog, from `FluentErgo::diagnostics`. With
+/// the `serde` feature, this is `Serialize`, so that a service can return it as is from a health
+/// check endpoint. Languages are written as tags.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostics {
+    /// The languages that `tr` searches, in order
+    pub languages: Vec<String>,
+    /// Every language with resources, sorted by language tag
+    pub bundles: Vec<LanguageDiagnostics>,
+    /// The names of the catalogs added with `with_layer`, in the order they are searched
+    pub layers: Vec<String>,
+    /// When resources were last added to any language, or `None` if none have been loaded yet
+    /// or the platform has no clock, as on `wasm32-unknown-unknown`
+    pub last_reload: Option<SystemTime>,
+}
+
+/// The resources of one language, in `Diagnostics`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LanguageDiagnostics {
+    /// The language tag
+    pub lang: String,
+    /// Whether `tr` searches this language
+    pub searched: bool,
+    /// Where each resource came from, loaded resources first, in the order they were added
+    pub origins: Vec<ResourceOrigin>,
+    /// How many different messages the loaded resources define
+    pub messages: usize,
+    /// How many different terms the loaded resources define
+    pub terms: usize,
+    /// When resources were last added to this language, or `None` if none have been loaded yet
+    /// or the platform has no clock, as on `wasm32-unknown-unknown`
+    pub loaded_at: Option<SystemTime>,
+}
+
+/// Where one resource came from.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ResourceOrigin {
+    /// The file the resource was read from, or `None` if it was added as text
+    pub path: Option<PathBuf>,
+    /// Whether the resource has been parsed, rather than waiting as a lazy resource
+    pub loaded: bool,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Describe the translator's configuration and what it has loaded, for a diagnostics or
+    /// health check endpoint.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// let diagnostics = fluent.diagnostics();
+    /// assert_eq!(diagnostics.languages, vec!["en-US"]);
+    /// assert_eq!(diagnostics.bundles[0].messages, 1);
+    /// assert!(diagnostics.last_reload.is_some());
+    /// ```
+    pub fn diagnostics(&self) -> Diagnostics {
+        let mut bundles: BTreeMap<String, LanguageDiagnostics> = BTreeMap::new();
+        let lazy = self.lazy.lock().recovered();
+        let loaded = self.bundles.read().recovered();
+        for (lang, entry) in loaded.iter() {
+            let entry = entry.read().recovered();
+            let mut messages = HashSet::new();
+            let mut terms = HashSet::new();
+            for ast_entry in entry.resources.iter().flat_map(|(_, res)| res.entries()) {
+                match ast_entry {
+                    ast::Entry::Message(message) => {
+                        messages.insert(message.id.name);
+                    }
+                    ast::Entry::Term(term) => {
+                        terms.insert(term.id.name);
+                    }
+                    _ => (),
+                }
+            }
+            bundles.insert(
+                lang.to_string(),
+                LanguageDiagnostics {
+                    lang: lang.to_string(),
+                    searched: self.languages.contains(lang),
+                    origins: entry
+                        .resources
+                        .iter()
+                        .map(|(hash, _)| ResourceOrigin {
+                            path: match entry.origins.get(hash) {
+                                Some(Origin::File(path)) => Some(path.clone()),
+                                _ => None,
+                            },
+                            loaded: true,
+                        })
+                        .collect(),
+                    messages: messages.len(),
+                    terms: terms.len(),
+                    loaded_at: entry.loaded_at,
+                },
+            );
+        }
+        for (lang, resources) in lazy.iter() {
+            let diagnostics =
+                bundles
+                    .entry(lang.to_string())
+                    .or_insert_with(|| LanguageDiagnostics {
+                        lang: lang.to_string(),
+                        searched: self.languages.contains(lang),
+                        ..LanguageDiagnostics::default()
+                    });
+            diagnostics
+                .origins
+                .extend(resources.iter().map(|resource| ResourceOrigin {
+                    path: match resource {
+                        UnparsedResource::Text(_) => None,
+                        #[cfg(feature = "fs")]
+                        UnparsedResource::File(path) => Some(path.clone()),
+                    },
+                    loaded: false,
+                }));
+        }
+        let bundles: Vec<LanguageDiagnostics> = bundles.into_values().collect();
+        Diagnostics {
+            languages: self.languages.iter().map(|lang| lang.to_string()).collect(),
+            last_reload: bundles.iter().filter_map(|lang| lang.loaded_at).max(),
+            bundles,
+            layers: self.layer_names().into_iter().map(String::from).collect(),
+        }
+    }
+}
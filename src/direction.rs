@@ -0,0 +1,45 @@
+use crate::{canonicalize_language, BundleKind, FluentErgo};
+use unic_langid::{CharacterDirection, LanguageIdentifier};
+
+/// Which way text in a language runs, so that a user interface can mirror its layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// Whether text in `lang` runs from right to left. This goes by the script the language is
+/// written in, as the CLDR gives it when `lang` does not name one, so `ar` and `ur` are right to
+/// left, `az-Arab` is too, but `az` is not.
+///
+/// ```
+/// use fluent_ergonomics::is_rtl;
+///
+/// assert!(is_rtl(&"he".parse().unwrap()));
+/// assert!(is_rtl(&"pa-Arab".parse().unwrap()));
+/// assert!(!is_rtl(&"pa".parse().unwrap()));
+/// assert!(!is_rtl(&"en-US".parse().unwrap()));
+/// ```
+pub fn is_rtl(lang: &LanguageIdentifier) -> bool {
+    let mut lang = canonicalize_language(lang);
+    lang.maximize();
+    lang.character_direction() == CharacterDirection::RTL
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Which way text in the first language of the fallback chain runs. Scripts which are
+    /// written top to bottom, such as traditional Mongolian, count as left to right.
+    ///
+    /// ```
+    /// use fluent_ergonomics::TextDirection;
+    ///
+    /// let fluent = fluent_ergonomics::FluentErgo::from_tags(&["ar", "en-US"]).unwrap();
+    /// assert_eq!(fluent.text_direction_of_current(), TextDirection::RightToLeft);
+    /// ```
+    pub fn text_direction_of_current(&self) -> TextDirection {
+        match self.languages.first() {
+            Some(lang) if is_rtl(lang) => TextDirection::RightToLeft,
+            _ => TextDirection::LeftToRight,
+        }
+    }
+}
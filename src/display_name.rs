@@ -0,0 +1,46 @@
+use icu_experimental::displaynames::multi::LocaleDisplayNamesFormatter;
+use icu_experimental::displaynames::DisplayNamesOptions;
+use icu_locale_core::Locale;
+use unic_langid::LanguageIdentifier;
+
+/// The name of `lang` in the language `in_locale`, from the CLDR, for language pickers. Pass
+/// the same language twice to get the name that its speakers use, such as "Deutsch" or "日本語".
+///
+/// ```
+/// use fluent_ergonomics::language_display_name;
+/// use unic_langid::LanguageIdentifier;
+///
+/// let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+/// let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+/// let ja_id = "ja".parse::<LanguageIdentifier>().unwrap();
+/// assert_eq!(language_display_name(&de_id, &de_id), "Deutsch");
+/// assert_eq!(language_display_name(&ja_id, &ja_id), "日本語");
+/// assert_eq!(language_display_name(&de_id, &en_id), "German");
+/// ```
+///
+/// Regions and scripts are named too, as in "English (Mexico)", and a language that the CLDR
+/// does not name comes back as its tag.
+///
+/// This requires the `icu` feature.
+pub fn language_display_name(lang: &LanguageIdentifier, in_locale: &LanguageIdentifier) -> String {
+    let lang = match lang.to_string().parse::<Locale>() {
+        Ok(lang) => lang,
+        Err(_) => return lang.to_string(),
+    };
+    let in_locale = in_locale
+        .to_string()
+        .parse::<Locale>()
+        .unwrap_or(Locale::UNKNOWN);
+    let formatter =
+        LocaleDisplayNamesFormatter::try_new((&in_locale).into(), DisplayNamesOptions::default())
+            .or_else(|_| {
+                LocaleDisplayNamesFormatter::try_new(
+                    (&Locale::UNKNOWN).into(),
+                    DisplayNamesOptions::default(),
+                )
+            });
+    match formatter {
+        Ok(formatter) => formatter.of(&lang).into_owned(),
+        Err(_) => lang.to_string(),
+    }
+}
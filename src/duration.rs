@@ -0,0 +1,60 @@
+use crate::{BundleKind, Error, FluentErgo};
+use fluent::{FluentArgs, FluentValue};
+use std::time::Duration;
+
+/// The units `tr_duration` chooses between, largest first, with their lengths in seconds.
+const UNITS: [(&str, u64); 4] = [
+    ("days", 24 * 60 * 60),
+    ("hours", 60 * 60),
+    ("minutes", 60),
+    ("seconds", 1),
+];
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Translate a duration such as "3 hours" or "1 minute". The duration is described in the
+    /// largest unit that it contains at least one of, rounded down, using the message
+    /// `<msgid_prefix>-days`, `-hours`, `-minutes`, or `-seconds`. Durations under a second use
+    /// `-seconds` with a count of zero.
+    ///
+    /// The count is passed to the message as the number `$count`, so the message can select the
+    /// right plural form for each language:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from(
+    ///             "
+    /// ago-hours = { $count ->
+    ///     [one] an hour ago
+    ///    *[other] { $count } hours ago
+    /// }
+    /// ",
+    ///         ),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     fluent.tr_duration("ago", Duration::from_secs(3 * 60 * 60 + 5)).unwrap(),
+    ///     "3 hours ago"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn tr_duration(&self, msgid_prefix: &str, duration: Duration) -> Result<String, Error> {
+        let seconds = duration.as_secs();
+        let (unit, length) = UNITS
+            .iter()
+            .find(|(_, length)| seconds >= *length)
+            .unwrap_or(&UNITS[UNITS.len() - 1]);
+        let mut args = FluentArgs::new();
+        args.set("count", FluentValue::from(seconds / length));
+        self.tr(&format!("{}-{}", msgid_prefix, unit), Some(&args))
+    }
+}
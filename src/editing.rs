@@ -0,0 +1,147 @@
+use crate::{canonicalize_language, BundleKind, Concurrent, Error, FluentErgo, PoisonExt};
+use fluent_syntax::{ast, serializer};
+use std::collections::BTreeMap;
+use unic_langid::LanguageIdentifier;
+
+/// Changes to the messages of one language which are shown by `tr` while they are being made,
+/// for tools which let translators work inside the running application. Created with
+/// `FluentErgo::edit`.
+///
+/// Edits are overrides, as if they had been made with `FluentErgo::set_override`, so every clone
+/// of the translator shows them straight away. They last until they are reverted or discarded,
+/// even after the session is dropped.
+pub struct EditingSession<K: BundleKind = Concurrent> {
+    fluent: FluentErgo<K>,
+    lang: LanguageIdentifier,
+    edits: BTreeMap<String, String>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Start editing the messages of `lang`.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("units = Units\nquit = Quit"))
+    ///     .unwrap();
+    ///
+    /// let mut session = fluent.edit(&en_id);
+    /// session.set("units", "Measures").unwrap();
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Measures");
+    /// assert_eq!(session.export().unwrap(), "units = Measures\nquit = Quit\n");
+    /// ```
+    pub fn edit(&self, lang: &LanguageIdentifier) -> EditingSession<K> {
+        EditingSession {
+            fluent: self.clone(),
+            lang: canonicalize_language(lang),
+            edits: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: BundleKind> EditingSession<K> {
+    /// The language being edited.
+    pub fn lang(&self) -> &LanguageIdentifier {
+        &self.lang
+    }
+
+    /// Every message changed in this session, and its new Fluent source.
+    pub fn edits(&self) -> &BTreeMap<String, String> {
+        &self.edits
+    }
+
+    /// Change the value of `msgid` to `text`, which is Fluent source.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `FluentErgo::set_override` can return. The message keeps its
+    ///   previous text.
+    ///
+    pub fn set(&mut self, msgid: &str, text: &str) -> Result<(), Error> {
+        self.fluent.set_override(msgid, &self.lang, text)?;
+        self.edits.insert(String::from(msgid), String::from(text));
+        Ok(())
+    }
+
+    /// Undo the changes to `msgid`, so that `tr` shows the loaded text again.
+    ///
+    /// # Errors
+    ///
+    /// * `Poisoned`
+    ///
+    pub fn revert(&mut self, msgid: &str) -> Result<(), Error> {
+        if self.edits.remove(msgid).is_none() {
+            return Ok(());
+        }
+        let mut sources = self.fluent.override_sources(&self.lang)?;
+        sources.remove(msgid);
+        self.fluent.install_overrides(self.lang.clone(), sources)
+    }
+
+    /// Undo every change in this session.
+    ///
+    /// # Errors
+    ///
+    /// * `Poisoned`
+    ///
+    pub fn discard(mut self) -> Result<(), Error> {
+        let mut sources = self.fluent.override_sources(&self.lang)?;
+        for msgid in std::mem::take(&mut self.edits).keys() {
+            sources.remove(msgid);
+        }
+        self.fluent.install_overrides(self.lang.clone(), sources)
+    }
+
+    /// The whole catalog of the language with the edits made, as Fluent source which can be
+    /// saved over the original files. Every loaded resource of the language is included, in the
+    /// order they were added, and edited messages keep their attributes. Messages which were
+    /// only added in this session come last. Comments are not kept.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from loading a lazy resource of the language, as for `tr`
+    ///
+    pub fn export(&self) -> Result<String, Error> {
+        self.fluent.load_lazy(std::slice::from_ref(&self.lang))?;
+        let bundles = self.fluent.bundles.read().or_poisoned()?;
+        let overrides = self.fluent.overrides.read().or_poisoned()?;
+        let edited = |msgid: &str| match overrides.get(&self.lang)?.entry.definition(msgid)?.1 {
+            ast::Entry::Message(msg) => Some(msg),
+            _ => None,
+        };
+
+        let entry = bundles
+            .get(&self.lang)
+            .map(|entry| entry.read().or_poisoned())
+            .transpose()?;
+
+        let mut body = Vec::new();
+        let mut exported = Vec::new();
+        if let Some(entry) = &entry {
+            for (_, resource) in &entry.resources {
+                for definition in resource.entries() {
+                    body.push(match definition {
+                        ast::Entry::Message(msg) if self.edits.contains_key(msg.id.name) => {
+                            exported.push(String::from(msg.id.name));
+                            match edited(msg.id.name) {
+                                Some(edit) => ast::Entry::Message(ast::Message {
+                                    value: edit.value.clone(),
+                                    ..msg.clone()
+                                }),
+                                None => definition.clone(),
+                            }
+                        }
+                        _ => definition.clone(),
+                    });
+                }
+            }
+        }
+        for msgid in self.edits.keys() {
+            if let Some(edit) = edited(msgid).filter(|_| !exported.contains(msgid)) {
+                body.push(ast::Entry::Message(edit.clone()));
+            }
+        }
+        Ok(serializer::serialize(&ast::Resource { body }))
+    }
+}
@@ -0,0 +1,560 @@
+use crate::{Limit, MessageConflict};
+use fluent::{FluentError, FluentResource};
+use fluent_syntax::parser::ParserError;
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// Everything that can go wrong while loading or translating.
+///
+/// Variants carry as much context as the translator had when the error happened: the language,
+/// the file, the message, and the source positions of parse errors. Context fields are `None` when
+/// the error did not come from a particular language or file, for instance when it was converted
+/// from a bare `io::Error`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A manifest for `FluentErgo::from_config` could not be understood
+    ConfigError { path: PathBuf, message: String },
+    /// A catalog could not be downloaded by an `OtaClient`
+    DownloadError { url: String, message: String },
+    /// A resource defines messages or terms which its language already has. The rest of the
+    /// resource was added, and the first definitions were kept. See
+    /// `FluentErgo::set_duplicate_messages`.
+    DuplicateMessages {
+        lang: LanguageIdentifier,
+        path: Option<PathBuf>,
+        conflicts: Vec<MessageConflict>,
+    },
+    /// All files must be UTF-8 encoded.
+    FileEncodingError {
+        path: Option<PathBuf>,
+        error: FromUtf8Error,
+    },
+    /// Fluent encountered an underlying error while adding translation strings to a bundle
+    FluentError {
+        lang: Option<LanguageIdentifier>,
+        path: Option<PathBuf>,
+        errors: Vec<FluentError>,
+    },
+    /// Fluent encountered an underlying error while parsing the translation strings. `text` is
+    /// the source that failed to parse.
+    FluentParserError {
+        lang: Option<LanguageIdentifier>,
+        path: Option<PathBuf>,
+        errors: Vec<ParserError>,
+        text: Option<Arc<str>>,
+    },
+    /// Fluent could not format a message, and the `TrOptions` for the call asked for an error
+    FormatError {
+        id: String,
+        lang: LanguageIdentifier,
+        errors: Vec<FluentError>,
+    },
+    /// There was an underlying IO error
+    IOError {
+        path: Option<PathBuf>,
+        error: io::Error,
+    },
+    /// A language identifier could not be parsed from the given name
+    InvalidLanguage(String),
+    /// `FluentErgo::try_new` was given a list of languages which cannot be searched as written
+    InvalidLanguageList(LanguageListProblem),
+    /// A language pack given to `FluentErgo::add_from_signed_archive` was not signed by any of
+    /// the trusted keys
+    InvalidSignature { path: PathBuf },
+    /// `FluentErgo::parse_number` could not read the text as a number in the language
+    InvalidNumber {
+        text: String,
+        lang: LanguageIdentifier,
+    },
+    /// Formatting a message would have gone over one of the limits set with
+    /// `FluentErgo::set_evaluation_limits`
+    LimitExceeded {
+        id: String,
+        lang: LanguageIdentifier,
+        limit: Limit,
+    },
+    /// No message could be found matching the specified message ID
+    NoMatchingMessage(MissingMessage),
+    /// Another thread panicked while it was changing the translator, such as in a
+    /// `ResourceProvider` or a custom function, so its state may be incomplete, or while it was
+    /// parsing resources for `FluentErgo::add_many`
+    Poisoned,
+    /// The validator from `FluentErgo::set_reload_validator` refused a reload, for this reason
+    ReloadVetoed { reason: String },
+    /// Messages refer to terms which their language does not define. See
+    /// `FluentErgo::set_term_validation`.
+    UnknownTerms {
+        lang: LanguageIdentifier,
+        terms: Vec<String>,
+    },
+    /// Resources were added for a language that the translator was not constructed with
+    UnregisteredLanguage(LanguageIdentifier),
+}
+
+impl Error {
+    /// The language that the error happened in, if it happened in a particular language.
+    pub fn lang(&self) -> Option<&LanguageIdentifier> {
+        match self {
+            Error::FluentError { lang, .. } | Error::FluentParserError { lang, .. } => {
+                lang.as_ref()
+            }
+            Error::DuplicateMessages { lang, .. }
+            | Error::FormatError { lang, .. }
+            | Error::InvalidNumber { lang, .. }
+            | Error::LimitExceeded { lang, .. }
+            | Error::UnknownTerms { lang, .. }
+            | Error::UnregisteredLanguage(lang) => Some(lang),
+            Error::InvalidLanguageList(
+                LanguageListProblem::Duplicate(lang) | LanguageListProblem::NotCanonical(lang),
+            ) => Some(lang),
+            _ => None,
+        }
+    }
+
+    /// The file that the error happened in, if it came from a file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::DuplicateMessages { path, .. }
+            | Error::FileEncodingError { path, .. }
+            | Error::FluentError { path, .. }
+            | Error::FluentParserError { path, .. }
+            | Error::IOError { path, .. } => path.as_deref(),
+            Error::ConfigError { path, .. } | Error::InvalidSignature { path } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The message identifier that the error is about, if it is about exactly one message.
+    pub fn message_id(&self) -> Option<&str> {
+        match self {
+            Error::NoMatchingMessage(missing) => Some(&missing.id),
+            Error::FormatError { id, .. } | Error::LimitExceeded { id, .. } => Some(id),
+            Error::DuplicateMessages { conflicts, .. } if conflicts.len() == 1 => {
+                Some(&conflicts[0].id)
+            }
+            Error::FluentError { errors, .. } if errors.len() == 1 => match &errors[0] {
+                FluentError::Overriding { id, .. } => Some(id),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Byte ranges in the translation source which the error refers to. These are only available
+    /// for parser errors.
+    pub fn spans(&self) -> Vec<(usize, usize)> {
+        match self {
+            Error::FluentParserError { errors, .. } => errors
+                .iter()
+                .map(|err| (err.pos.start, err.pos.end))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Fill in the language for errors which don't already know it.
+    pub(crate) fn in_language(mut self, language: &LanguageIdentifier) -> Error {
+        if let Error::FluentError { lang, .. } | Error::FluentParserError { lang, .. } = &mut self {
+            lang.get_or_insert_with(|| language.clone());
+        }
+        self
+    }
+
+    /// Fill in the file for errors which don't already know it.
+    pub(crate) fn in_file(mut self, file: &Path) -> Error {
+        match &mut self {
+            Error::DuplicateMessages { path, .. }
+            | Error::FileEncodingError { path, .. }
+            | Error::FluentError { path, .. }
+            | Error::FluentParserError { path, .. }
+            | Error::IOError { path, .. } => {
+                path.get_or_insert_with(|| file.to_path_buf());
+            }
+            _ => (),
+        }
+        self
+    }
+
+    #[cfg(any(feature = "serde", feature = "miette"))]
+    fn kind(&self) -> &'static str {
+        match self {
+            Error::ConfigError { .. } => "ConfigError",
+            Error::DownloadError { .. } => "DownloadError",
+            Error::DuplicateMessages { .. } => "DuplicateMessages",
+            Error::FileEncodingError { .. } => "FileEncodingError",
+            Error::FluentError { .. } => "FluentError",
+            Error::FluentParserError { .. } => "FluentParserError",
+            Error::FormatError { .. } => "FormatError",
+            Error::IOError { .. } => "IOError",
+            Error::InvalidLanguage(_) => "InvalidLanguage",
+            Error::InvalidLanguageList(_) => "InvalidLanguageList",
+            Error::InvalidNumber { .. } => "InvalidNumber",
+            Error::InvalidSignature { .. } => "InvalidSignature",
+            Error::LimitExceeded { .. } => "LimitExceeded",
+            Error::NoMatchingMessage(_) => "NoMatchingMessage",
+            Error::Poisoned => "Poisoned",
+            Error::ReloadVetoed { .. } => "ReloadVetoed",
+            Error::UnknownTerms { .. } => "UnknownTerms",
+            Error::UnregisteredLanguage(_) => "UnregisteredLanguage",
+        }
+    }
+}
+
+/// Everything the translator knew about a message it could not find, so that a "no matching
+/// message" error in a log says why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MissingMessage {
+    /// The message identifier which was requested
+    pub id: String,
+    /// Every language that was searched, in fallback order
+    pub languages: Vec<LanguageIdentifier>,
+    /// The searched languages which had any resources loaded at all
+    pub loaded: Vec<LanguageIdentifier>,
+    /// The searched languages in which the message exists but only has attributes, and so has no
+    /// value that can be translated
+    pub without_value: Vec<LanguageIdentifier>,
+}
+
+impl MissingMessage {
+    /// Describe a missing message. `lookup` is called for each language and returns `None` if the
+    /// language has no bundle, or whether the bundle has the message without a value.
+    pub(crate) fn new<F>(id: &str, languages: &[LanguageIdentifier], lookup: F) -> MissingMessage
+    where
+        F: Fn(&LanguageIdentifier) -> Option<bool>,
+    {
+        let mut loaded = Vec::new();
+        let mut without_value = Vec::new();
+        for lang in languages {
+            if let Some(valueless) = lookup(lang) {
+                loaded.push(lang.clone());
+                if valueless {
+                    without_value.push(lang.clone());
+                }
+            }
+        }
+        MissingMessage {
+            id: String::from(id),
+            languages: Vec::from(languages),
+            loaded,
+            without_value,
+        }
+    }
+}
+
+/// What is wrong with a list of languages given to `FluentErgo::try_new`.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LanguageListProblem {
+    /// There are no languages, so no message could ever be found
+    Empty,
+    /// The language appears more than once. Only the first would ever be searched.
+    Duplicate(LanguageIdentifier),
+    /// The language uses a deprecated code, and would be searched as its replacement from
+    /// `canonicalize_language`, which may already be in the list
+    NotCanonical(LanguageIdentifier),
+}
+
+impl fmt::Display for LanguageListProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LanguageListProblem::Empty => write!(f, "no languages were given"),
+            LanguageListProblem::Duplicate(lang) => write!(f, "{} is listed more than once", lang),
+            LanguageListProblem::NotCanonical(lang) => write!(
+                f,
+                "{} is deprecated; use {}",
+                lang,
+                crate::canonicalize_language(lang)
+            ),
+        }
+    }
+}
+
+fn list(langs: &[LanguageIdentifier]) -> String {
+    if langs.is_empty() {
+        String::from("none")
+    } else {
+        langs
+            .iter()
+            .map(|lang| lang.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for MissingMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (searched: {}; loaded: {}",
+            self.id,
+            list(&self.languages),
+            list(&self.loaded)
+        )?;
+        if !self.without_value.is_empty() {
+            write!(
+                f,
+                "; message has no value in: {}",
+                list(&self.without_value)
+            )?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::ConfigError { .. } => None,
+            Error::DownloadError { .. } => None,
+            Error::DuplicateMessages { .. } => None,
+            Error::FileEncodingError { error, .. } => Some(error),
+            Error::InvalidLanguage(_) => None,
+            Error::InvalidLanguageList(_) => None,
+            Error::InvalidNumber { .. } => None,
+            Error::InvalidSignature { .. } => None,
+            Error::LimitExceeded { .. } => None,
+            Error::NoMatchingMessage(_) => None,
+            Error::Poisoned => None,
+            Error::FluentParserError { .. } => None,
+            Error::FluentError { .. } => None,
+            Error::FormatError { .. } => None,
+            Error::IOError { error, .. } => Some(error),
+            Error::ReloadVetoed { .. } => None,
+            Error::UnknownTerms { .. } => None,
+            Error::UnregisteredLanguage(_) => None,
+        }
+    }
+}
+
+/// Describe where an error happened, e.g. ` in en-US (locales/en-US/main.ftl)`.
+struct Location<'a>(Option<&'a LanguageIdentifier>, Option<&'a Path>);
+
+impl<'a> fmt::Display for Location<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(lang) = self.0 {
+            write!(f, " in {}", lang)?;
+        }
+        if let Some(path) = self.1 {
+            write!(f, " ({})", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = Location(self.lang(), self.path());
+        match self {
+            Error::ConfigError { message, .. } => {
+                write!(f, "Invalid configuration{}: {}", location, message)
+            }
+            Error::DownloadError { url, message } => {
+                write!(f, "Could not download {}: {}", url, message)
+            }
+            Error::DuplicateMessages { conflicts, .. } => {
+                write!(f, "Defined twice{}: ", location)?;
+                for (i, conflict) in conflicts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(
+                        f,
+                        "{} (kept {}, ignored {})",
+                        conflict.id, conflict.first, conflict.second
+                    )?;
+                }
+                Ok(())
+            }
+            Error::FileEncodingError { error, .. } => write!(
+                f,
+                "Translation file has an encoding problem{}: {}",
+                location, error
+            ),
+            Error::FluentError { errors, .. } => {
+                write!(f, "Fluent Error{}: {:?}", location, errors)
+            }
+            Error::FluentParserError { errors, .. } => {
+                write!(f, "Fluent Parser Error{}: {:?}", location, errors)
+            }
+            Error::FormatError { id, errors, .. } => {
+                write!(f, "Could not format {}{}: {:?}", id, location, errors)
+            }
+            Error::IOError { error, .. } => write!(f, "IO Error{}: {}", location, error),
+            Error::InvalidLanguage(name) => write!(f, "Invalid language identifier: {}", name),
+            Error::InvalidLanguageList(problem) => write!(f, "Invalid language list: {}", problem),
+            Error::InvalidNumber { text, .. } => {
+                write!(f, "Not a number{}: {:?}", location, text)
+            }
+            Error::InvalidSignature { path } => write!(
+                f,
+                "Language pack is not signed by a trusted key: {}",
+                path.display()
+            ),
+            Error::LimitExceeded { id, limit, .. } => {
+                write!(f, "Message {}{} is over the {} limit", id, location, limit)
+            }
+            Error::NoMatchingMessage(missing) => write!(f, "No matching message for {}", missing),
+            Error::Poisoned => write!(f, "A thread panicked while it was changing the translator"),
+            Error::ReloadVetoed { reason } => write!(f, "Reload was vetoed: {}", reason),
+            Error::UnknownTerms { terms, .. } => write!(
+                f,
+                "Messages{} refer to undefined terms: {}",
+                location,
+                terms.join(", ")
+            ),
+            Error::UnregisteredLanguage(lang) => write!(
+                f,
+                "Resources were added for {}, which the translator does not search",
+                lang
+            ),
+        }
+    }
+}
+
+impl From<(FluentResource, Vec<ParserError>)> for Error {
+    fn from(inp: (FluentResource, Vec<ParserError>)) -> Self {
+        let (resource, errors) = inp;
+        Error::FluentParserError {
+            lang: None,
+            path: None,
+            errors,
+            text: Some(Arc::from(resource.source())),
+        }
+    }
+}
+
+impl From<Vec<ParserError>> for Error {
+    fn from(errors: Vec<ParserError>) -> Self {
+        Error::FluentParserError {
+            lang: None,
+            path: None,
+            errors,
+            text: None,
+        }
+    }
+}
+
+impl From<Vec<FluentError>> for Error {
+    fn from(errors: Vec<FluentError>) -> Self {
+        Error::FluentError {
+            lang: None,
+            path: None,
+            errors,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::IOError { path: None, error }
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(error: FromUtf8Error) -> Self {
+        Error::FileEncodingError { path: None, error }
+    }
+}
+
+/// Errors serialize as a flat record, suitable for machine-readable error reports:
+///
+/// ```ignore
+/// {
+///   "kind": "FluentParserError",
+///   "message": "Fluent Parser Error in en-US (locales/en-US/main.ftl): [...]",
+///   "lang": "en-US",
+///   "path": "locales/en-US/main.ftl",
+///   "message_id": null,
+///   "spans": [[10, 11]]
+/// }
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Error", 6)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("lang", &self.lang().map(|lang| lang.to_string()))?;
+        state.serialize_field("path", &self.path())?;
+        state.serialize_field("message_id", &self.message_id())?;
+        state.serialize_field("spans", &self.spans())?;
+        state.end()
+    }
+}
+
+/// With the `miette` feature, errors render as diagnostics. Parser errors label each problem in
+/// the translation source.
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(format!("fluent_ergonomics::{}", self.kind())))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self {
+            Error::FileEncodingError { .. } => "translation files must be saved as UTF-8",
+            Error::DuplicateMessages { .. } | Error::FluentError { .. } => {
+                "each message may only be defined once per language"
+            }
+            Error::InvalidLanguage(_) => {
+                "language identifiers look like `en`, `en-US`, or `sr-Latn`"
+            }
+            Error::InvalidLanguageList(LanguageListProblem::NotCanonical(_)) => {
+                "pass the language through `canonicalize_language`"
+            }
+            Error::InvalidSignature { .. } => {
+                "the pack was changed after it was signed, or signed by a key that is not trusted"
+            }
+            Error::LimitExceeded { .. } => {
+                "shorten or simplify the translation, or raise the limit in `EvaluationLimits`"
+            }
+            Error::NoMatchingMessage(missing) if !missing.without_value.is_empty() => {
+                "the message only has attributes; give it a value or translate an attribute"
+            }
+            Error::NoMatchingMessage(missing) if missing.loaded.is_empty() => {
+                "none of the searched languages have any translations loaded"
+            }
+            Error::Poisoned => "create a new translator; this one may be missing resources",
+            Error::UnknownTerms { .. } => {
+                "define the terms in this language, or load the files that define them"
+            }
+            Error::UnregisteredLanguage(_) => {
+                "add the language to `FluentErgo::new`, or use `UnknownLanguages::Append`"
+            }
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        match self {
+            Error::FluentParserError {
+                text: Some(text), ..
+            } => Some(text as &dyn miette::SourceCode),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        match self {
+            Error::FluentParserError {
+                errors,
+                text: Some(text),
+                ..
+            } => Some(Box::new(errors.iter().map(move |err| {
+                let start = err.pos.start.min(text.len());
+                let end = err.pos.end.clamp(start, text.len());
+                miette::LabeledSpan::new(Some(format!("{:?}", err.kind)), start, end - start)
+            }))),
+            _ => None,
+        }
+    }
+}
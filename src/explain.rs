@@ -0,0 +1,305 @@
+use crate::format::write_message;
+use crate::functions::Function;
+use crate::lint::pattern_variables;
+use crate::suggest::pattern_source;
+use crate::{
+    BundleEntry, BundleKind, Error, FluentErgo, Isolation, Options, Origin, PoisonExt, TrOptions,
+};
+use fluent::{FluentArgs, FluentResource};
+use fluent_syntax::{ast, serializer};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// How `FluentErgo::explain` found and formatted a message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    /// The languages that were searched, in order, ending with the one which had the message
+    pub tried: Vec<LanguageIdentifier>,
+    /// The language which had the message, if any did
+    pub lang: Option<LanguageIdentifier>,
+    /// Where that language's message came from
+    pub origin: Option<Origin>,
+    /// The select expressions that were reached, in the order Fluent formatted them
+    pub selections: Vec<Selection>,
+    /// Every variable that the message uses, whichever variants it takes
+    pub variables: Vec<String>,
+    /// The variables which neither the arguments nor any defaults give
+    pub missing: Vec<String>,
+    /// The translation, as `tr` would return it without a `MissingMessageResolver`
+    pub translation: Option<String>,
+}
+
+/// One select expression reached while formatting a message, from `FluentErgo::explain`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    /// The selector, as Fluent source, such as `$count` or `NUMBER($count, type: "ordinal")`
+    pub selector: String,
+    /// The key of the variant that was taken
+    pub variant: String,
+    /// Whether the variant taken is the default one, marked with `*`
+    pub default: bool,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Show how `tr` would translate `msgid`: which languages it searched, which one had the
+    /// message and where it came from, which variant of each select expression was taken, and
+    /// which variables the message needs that `args` does not give. This answers questions such
+    /// as why German showed the singular form.
+    ///
+    /// ```
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         de_id.clone(),
+    ///         String::from("files = { $count ->\n    [one] Eine Datei\n   *[other] { $count } Dateien\n}"),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let mut args = fluent::FluentArgs::new();
+    /// args.set("count", 1);
+    /// let explanation = fluent.explain("files", Some(&args)).unwrap();
+    /// assert_eq!(explanation.lang, Some(de_id));
+    /// assert_eq!(explanation.selections[0].selector, "$count");
+    /// assert_eq!(explanation.selections[0].variant, "one");
+    /// assert_eq!(explanation.translation.as_deref(), Some("Eine Datei"));
+    /// ```
+    ///
+    /// Only the message itself is explained, not the messages and terms it refers to. Nothing
+    /// is cached, so this is slower than `tr`; it is meant for debugging.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from loading a lazy resource, as for `tr`
+    /// * `Poisoned`
+    ///
+    pub fn explain(&self, msgid: &str, args: Option<&FluentArgs>) -> Result<Explanation, Error> {
+        self.load_lazy(&self.languages)?;
+        let mut explanation = Explanation {
+            tried: Vec::new(),
+            lang: None,
+            origin: None,
+            selections: Vec::new(),
+            variables: Vec::new(),
+            missing: Vec::new(),
+            translation: None,
+        };
+
+        let bundles = self.bundles.read().or_poisoned()?;
+        let overrides = self.overrides.read().or_poisoned()?;
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.bundles.read().or_poisoned())
+            .collect::<Result<Vec<_>, _>>()?;
+        let functions = self.functions.read().or_poisoned()?;
+        for lang in &self.languages {
+            explanation.tried.push(lang.clone());
+            if let Some(overridden) = overrides.get(lang) {
+                if K::has_value(&overridden.entry.bundle, msgid) == Some(true) {
+                    explanation.origin = Some(Origin::Override);
+                    self.explain_entry(
+                        &overridden.entry,
+                        lang,
+                        msgid,
+                        args,
+                        &functions,
+                        &mut explanation,
+                    );
+                    return Ok(explanation);
+                }
+            }
+            let entries = std::iter::once(&*bundles)
+                .chain(layers.iter().map(|layer| &**layer))
+                .filter_map(|bundles| bundles.get(lang));
+            for entry in entries {
+                let entry = entry.read().or_poisoned()?;
+                if K::has_value(&entry.bundle, msgid) == Some(true) {
+                    explanation.origin = entry.origin_of(msgid);
+                    self.explain_entry(&entry, lang, msgid, args, &functions, &mut explanation);
+                    return Ok(explanation);
+                }
+            }
+        }
+        Ok(explanation)
+    }
+
+    fn explain_entry(
+        &self,
+        entry: &BundleEntry<K>,
+        lang: &LanguageIdentifier,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        functions: &[(String, Function)],
+        explanation: &mut Explanation,
+    ) {
+        explanation.lang = Some(lang.clone());
+        let opts = TrOptions {
+            isolation: Some(Isolation::Strip),
+            ..TrOptions::default()
+        };
+        let mut translation = String::new();
+        if let Ok(true) = self.write_entry(entry, lang, msgid, args, &opts, &mut translation) {
+            explanation.translation = Some(translation);
+        }
+
+        let msg = match entry.definition(msgid) {
+            Some((_, ast::Entry::Message(msg))) => msg,
+            _ => return,
+        };
+        let pattern = match &msg.value {
+            Some(pattern) => pattern,
+            None => return,
+        };
+        let mut variables = BTreeSet::new();
+        pattern_variables(pattern, &mut variables);
+        let defaults = K::default_args(&entry.bundle, msgid);
+        let configured = self.options.default_args.get(msgid);
+        let given = |name: &str| {
+            args.is_some_and(|args| args.get(name).is_some())
+                || defaults
+                    .iter()
+                    .chain(configured.into_iter().flatten())
+                    .any(|(default, _)| default == name)
+        };
+        explanation.missing = variables
+            .iter()
+            .filter(|name| !given(name))
+            .map(|name| String::from(*name))
+            .collect();
+        explanation.variables = variables.into_iter().map(String::from).collect();
+
+        let probe = Probe {
+            entry,
+            lang,
+            msg,
+            args,
+            functions,
+            options: &self.options,
+            opts: &opts,
+        };
+        probe.pattern(pattern, &mut explanation.selections);
+    }
+}
+
+/// Finds which variant of each select expression Fluent takes, by formatting a copy of the
+/// message in which each select expression has been replaced by one that gives the index of the
+/// variant it takes. That way Fluent's own rules for plurals and selectors are followed.
+struct Probe<'a, K: BundleKind> {
+    entry: &'a BundleEntry<K>,
+    lang: &'a LanguageIdentifier,
+    msg: &'a ast::Message<&'a str>,
+    args: Option<&'a FluentArgs<'a>>,
+    functions: &'a [(String, Function)],
+    options: &'a Options,
+    opts: &'a TrOptions,
+}
+
+impl<K: BundleKind> Probe<'_, K> {
+    fn pattern(&self, pattern: &ast::Pattern<&str>, selections: &mut Vec<Selection>) {
+        for element in &pattern.elements {
+            if let ast::PatternElement::Placeable { expression } = element {
+                self.expression(expression, selections);
+            }
+        }
+    }
+
+    fn expression(&self, expression: &ast::Expression<&str>, selections: &mut Vec<Selection>) {
+        match expression {
+            ast::Expression::Select { selector, variants } => {
+                let taken = match self.taken(selector, variants) {
+                    Some(taken) => taken,
+                    None => return,
+                };
+                let variant = &variants[taken];
+                let selector = pattern_source(
+                    "selector",
+                    &ast::Pattern {
+                        elements: vec![ast::PatternElement::Placeable {
+                            expression: ast::Expression::Inline(selector.clone()),
+                        }],
+                    },
+                );
+                selections.push(Selection {
+                    selector: String::from(
+                        selector
+                            .trim_start_matches('{')
+                            .trim_end_matches('}')
+                            .trim(),
+                    ),
+                    variant: match &variant.key {
+                        ast::VariantKey::Identifier { name } => String::from(*name),
+                        ast::VariantKey::NumberLiteral { value } => String::from(*value),
+                    },
+                    default: variant.default,
+                });
+                self.pattern(&variant.value, selections);
+            }
+            ast::Expression::Inline(ast::InlineExpression::Placeable { expression }) => {
+                self.expression(expression, selections)
+            }
+            ast::Expression::Inline(_) => {}
+        }
+    }
+
+    /// The index of the variant that Fluent takes.
+    fn taken(
+        &self,
+        selector: &ast::InlineExpression<&str>,
+        variants: &[ast::Variant<&str>],
+    ) -> Option<usize> {
+        let indices: Vec<String> = (0..variants.len()).map(|i| i.to_string()).collect();
+        let probe = ast::Message {
+            value: Some(ast::Pattern {
+                elements: vec![ast::PatternElement::Placeable {
+                    expression: ast::Expression::Select {
+                        selector: selector.clone(),
+                        variants: variants
+                            .iter()
+                            .zip(&indices)
+                            .map(|(variant, index)| ast::Variant {
+                                key: variant.key.clone(),
+                                value: ast::Pattern {
+                                    elements: vec![ast::PatternElement::TextElement {
+                                        value: index.as_str(),
+                                    }],
+                                },
+                                default: variant.default,
+                            })
+                            .collect(),
+                    },
+                }],
+            }),
+            // The message keeps its attributes and its ID, so that it gets the same default
+            // arguments as the real one.
+            ..self.msg.clone()
+        };
+        let text = serializer::serialize(&ast::Resource {
+            body: vec![ast::Entry::Message(probe)],
+        });
+        let resource = FluentResource::try_new(text).ok()?;
+
+        let mut bundle = K::new_bundle(self.lang.clone(), self.functions);
+        for (hash, resource) in &self.entry.resources {
+            let replace = self.entry.overriding.contains(hash);
+            let _ = K::add_resource(&mut bundle, Arc::clone(resource), replace);
+        }
+        let _ = K::add_resource(&mut bundle, Arc::new(resource), true);
+        let mut out = String::new();
+        let id = self.msg.id.name;
+        match write_message::<K>(
+            self.options,
+            self.opts,
+            self.lang,
+            &bundle,
+            id,
+            self.args,
+            &mut out,
+        ) {
+            Ok(true) => out.parse().ok(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(feature = "fs")]
+use crate::{read_file, Error};
+use crate::{BundleKind, FluentErgo};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use unic_langid::LanguageIdentifier;
+
+/// The translation methods that `extract_message_ids` recognizes, after the `tr` prefix.
+const CALL_SUFFIXES: &[&str] = &[
+    "", "_opt", "_opts", "_or", "_or_else", "_ctx", "g", "g_case",
+];
+
+/// Find the message IDs in Rust source which are passed as string literals to `tr` or one of its
+/// variants, as a method (`fluent.tr("id", ...)`) or as a macro (`tr!("id", ...)`). IDs are
+/// returned in the order they appear, and may repeat.
+///
+/// This is a textual scan rather than a parse, so calls which build the ID at run time are not
+/// found, and calls inside comments are.
+///
+/// ```
+/// let source = r#"println!("{}", fluent.tr("greeting", None)?); tr!("farewell");"#;
+/// assert_eq!(
+///     fluent_ergonomics::extract_message_ids(source),
+///     vec!["greeting", "farewell"]
+/// );
+/// ```
+pub fn extract_message_ids(source: &str) -> Vec<String> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut ids = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = source[pos..].find("tr") {
+        let start = pos + offset;
+        pos = start + 2;
+        if source[..start].chars().next_back().is_some_and(is_ident) {
+            continue;
+        }
+        let rest = &source[pos..];
+        let name_end = rest.find(|c| !is_ident(c)).unwrap_or(rest.len());
+        if !CALL_SUFFIXES.contains(&&rest[..name_end]) {
+            continue;
+        }
+        let rest = &rest[name_end..];
+        let rest = rest.strip_prefix('!').unwrap_or(rest);
+        let literal = rest
+            .strip_prefix('(')
+            .and_then(|rest| rest.trim_start().strip_prefix('"'));
+        if let Some(literal) = literal {
+            if let Some(end) = literal.find('"') {
+                ids.push(String::from(&literal[..end]));
+            }
+        }
+    }
+    ids
+}
+
+/// Run `extract_message_ids` over every `.rs` file under `path`, and return the sorted list of
+/// distinct IDs.
+///
+/// # Errors
+///
+/// * `IOError`
+/// * `FileEncodingError`
+///
+#[cfg(feature = "fs")]
+pub fn extract_from_dir(path: &Path) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in dir
+            .read_dir()
+            .map_err(|err| Error::from(err).in_file(&dir))?
+        {
+            let entry = entry.map_err(|err| Error::from(err).in_file(&dir))?.path();
+            if entry.is_dir() {
+                dirs.push(entry);
+            } else if entry.extension().is_some_and(|ext| ext == "rs") {
+                ids.extend(extract_message_ids(&read_file(&entry)?));
+            }
+        }
+    }
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+/// The result of `FluentErgo::check_keys`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyReport {
+    /// IDs which the code uses, but the language has no message for
+    pub unknown: Vec<String>,
+    /// Messages in the language which the code never uses
+    pub unused: Vec<String>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Cross-reference the message IDs that code uses, usually from `extract_from_dir`, with the
+    /// messages loaded for `lang`.
+    pub fn check_keys(&self, lang: &LanguageIdentifier, used: &[String]) -> KeyReport {
+        let ids = self.message_ids(lang);
+        let mut unknown: Vec<String> = used
+            .iter()
+            .filter(|id| ids.binary_search(id).is_err())
+            .cloned()
+            .collect();
+        unknown.sort();
+        unknown.dedup();
+        KeyReport {
+            unknown,
+            unused: ids.into_iter().filter(|id| !used.contains(id)).collect(),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{
+    canonicalize_language, BundleEntry, BundleKind, DuplicateMessages, Error, FluentErgo, Origin,
+    PoisonExt, UnparsedResource,
+};
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use unic_langid::LanguageIdentifier;
+
+/// The application features and environment overlays of a translator.
+#[derive(Default)]
+pub(crate) struct Features {
+    flags: HashMap<String, Feature>,
+    environments: HashMap<String, Feature>,
+    /// The environment from `set_environment`, whose overlays are loaded
+    environment: Option<String>,
+}
+
+/// The resources of one application feature, from `FluentErgo::add_feature_text`, or of one
+/// environment, from `FluentErgo::add_environment_text`.
+#[derive(Default)]
+struct Feature {
+    enabled: bool,
+    resources: Vec<(LanguageIdentifier, UnparsedResource)>,
+    /// The hashes of the resources which enabling the feature added to bundles, so that
+    /// disabling it takes out exactly those.
+    loaded: Vec<(LanguageIdentifier, u64)>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Add translations which belong to the application feature `feature`, such as an
+    /// experiment that most users cannot see. They are not parsed, and `tr` does not find them,
+    /// until the feature is turned on with `set_feature_enabled`.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("save = Save"))
+    ///     .unwrap();
+    /// fluent
+    ///     .add_feature_text("sharing", en_id, String::from("share = Share with a friend"))
+    ///     .unwrap();
+    ///
+    /// assert!(fluent.tr("share", None).is_err());
+    /// fluent.set_feature_enabled("sharing", true).unwrap();
+    /// assert_eq!(fluent.tr("share", None).unwrap(), "Share with a friend");
+    /// fluent.set_feature_enabled("sharing", false).unwrap();
+    /// assert!(fluent.tr("share", None).is_err());
+    /// ```
+    ///
+    /// If the feature is already on, the text is parsed and added straight away. `origin_of`
+    /// reports its messages as coming from a label with the name of the feature. Features are
+    /// shared by every clone of this translator.
+    ///
+    /// # Errors
+    ///
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    /// * All of the errors that `add_from_text` can return, if the feature is on. The text is
+    ///   then taken out again, and is not kept for the feature.
+    ///
+    pub fn add_feature_text(
+        &mut self,
+        feature: &str,
+        lang: LanguageIdentifier,
+        text: String,
+    ) -> Result<(), Error> {
+        self.add_feature_resource(feature, false, lang, UnparsedResource::Text(text))
+    }
+
+    /// Like `add_feature_text`, but the translations are read from a file when the feature is
+    /// turned on.
+    ///
+    /// # Errors
+    ///
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    /// * All of the errors that `add_from_file` can return, if the feature is on
+    ///
+    #[cfg(feature = "fs")]
+    pub fn add_feature_file(
+        &mut self,
+        feature: &str,
+        lang: LanguageIdentifier,
+        path: &Path,
+    ) -> Result<(), Error> {
+        let resource = UnparsedResource::File(path.to_path_buf());
+        self.add_feature_resource(feature, false, lang, resource)
+    }
+
+    fn add_feature_resource(
+        &mut self,
+        feature: &str,
+        overlay: bool,
+        lang: LanguageIdentifier,
+        resource: UnparsedResource,
+    ) -> Result<(), Error> {
+        self.accept_languages(std::slice::from_ref(&lang))?;
+        let lang = canonicalize_language(&lang);
+        let mut features = self.features.lock().or_poisoned()?;
+        let features = if overlay {
+            &mut features.environments
+        } else {
+            &mut features.flags
+        };
+        let entry = features.entry(String::from(feature)).or_default();
+        if entry.enabled {
+            let resources = vec![(lang.clone(), resource.clone())];
+            let loaded = self.load_feature(feature, overlay, resources, &[])?;
+            entry.loaded.extend(loaded);
+        }
+        entry.resources.push((lang, resource));
+        Ok(())
+    }
+
+    /// Turn the translations of `feature` on or off, as the application's feature flag for it
+    /// changes. Turning a feature on parses its resources and adds them to their languages;
+    /// turning it off takes them out again, so that its messages are no longer found. A feature
+    /// which has no resources yet can still be turned on, and gets them as they are added.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `add_from_text` and `add_from_file` can return, when the feature
+    ///   is turned on. If any resource fails, none of them are added, and the feature stays off.
+    /// * `Poisoned`
+    ///
+    pub fn set_feature_enabled(&mut self, feature: &str, enabled: bool) -> Result<(), Error> {
+        let mut features = self.features.lock().or_poisoned()?;
+        let entry = features.flags.entry(String::from(feature)).or_default();
+        if entry.enabled == enabled {
+            return Ok(());
+        }
+        if enabled {
+            entry.loaded = self.load_feature(feature, false, entry.resources.clone(), &[])?;
+        } else {
+            self.unload_feature(std::mem::take(&mut entry.loaded))?;
+        }
+        entry.enabled = enabled;
+        Ok(())
+    }
+
+    /// The features which are turned on, sorted by name.
+    pub fn enabled_features(&self) -> Vec<String> {
+        let features = self.features.lock().recovered();
+        let mut enabled: Vec<String> = features
+            .flags
+            .iter()
+            .filter(|(_, feature)| feature.enabled)
+            .map(|(name, _)| name.clone())
+            .collect();
+        enabled.sort();
+        enabled
+    }
+
+    /// Add translations which replace messages while the application runs in `environment`, such
+    /// as `staging` or `dev`, so that those builds can show annotated strings without a catalog
+    /// of their own. Only the messages which the overlay defines are replaced, and they can still
+    /// refer to the terms and messages of the base catalog. Nothing is loaded until the
+    /// environment is chosen with `set_environment`.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("-brand = Acme\ntitle = { -brand }"))
+    ///     .unwrap();
+    /// fluent
+    ///     .add_environment_text("staging", en_id, String::from("title = { -brand } (staging)"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(fluent.tr("title", None).unwrap(), "Acme");
+    /// fluent.set_environment(Some("staging")).unwrap();
+    /// assert_eq!(fluent.tr("title", None).unwrap(), "Acme (staging)");
+    /// fluent.set_environment(None).unwrap();
+    /// assert_eq!(fluent.tr("title", None).unwrap(), "Acme");
+    /// ```
+    ///
+    /// `origin_of` reports the messages of an overlay as coming from its environment. Resources
+    /// which are added to a language after the overlay do not replace its messages, and do not
+    /// count as duplicates of them. Environments are shared by every clone of this translator.
+    ///
+    /// # Errors
+    ///
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    /// * All of the errors that `add_from_text` can return, if the environment is chosen
+    ///
+    pub fn add_environment_text(
+        &mut self,
+        environment: &str,
+        lang: LanguageIdentifier,
+        text: String,
+    ) -> Result<(), Error> {
+        self.add_feature_resource(environment, true, lang, UnparsedResource::Text(text))
+    }
+
+    /// Like `add_environment_text`, but the translations are read from a file when the
+    /// environment is chosen.
+    ///
+    /// # Errors
+    ///
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    /// * All of the errors that `add_from_file` can return, if the environment is chosen
+    ///
+    #[cfg(feature = "fs")]
+    pub fn add_environment_file(
+        &mut self,
+        environment: &str,
+        lang: LanguageIdentifier,
+        path: &Path,
+    ) -> Result<(), Error> {
+        let resource = UnparsedResource::File(path.to_path_buf());
+        self.add_feature_resource(environment, true, lang, resource)
+    }
+
+    /// Choose the environment which the application runs in, usually from its configuration at
+    /// startup, and load that environment's overlays in place of the previous one's. `None`
+    /// takes every overlay out, which is how production builds should run.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `add_from_text` and `add_from_file` can return. If any overlay
+    ///   fails, none of them are added, and the previous environment stays.
+    /// * `Poisoned`
+    ///
+    pub fn set_environment(&mut self, environment: Option<&str>) -> Result<(), Error> {
+        let mut features = self.features.lock().or_poisoned()?;
+        if features.environment.as_deref() == environment {
+            return Ok(());
+        }
+        let previous = features
+            .environment
+            .as_ref()
+            .and_then(|previous| features.environments.get(previous))
+            .map(|previous| previous.loaded.clone())
+            .unwrap_or_default();
+
+        // The new overlays go in before the old ones come out, so that a failure changes nothing.
+        let mut loaded = Vec::new();
+        if let Some(environment) = environment {
+            let entry = features
+                .environments
+                .entry(String::from(environment))
+                .or_default();
+            entry.loaded =
+                self.load_feature(environment, true, entry.resources.clone(), &previous)?;
+            entry.enabled = true;
+            loaded = entry.loaded.clone();
+        }
+        if let Some(previous) = features.environment.take() {
+            if let Some(entry) = features.environments.get_mut(&previous) {
+                entry.enabled = false;
+                entry.loaded.clear();
+            }
+        }
+        let unloaded = previous
+            .into_iter()
+            .filter(|resource| !loaded.contains(resource))
+            .collect();
+        self.unload_feature(unloaded)?;
+        features.environment = environment.map(String::from);
+        Ok(())
+    }
+
+    /// The environment from `set_environment`, if one has been chosen.
+    pub fn environment(&self) -> Option<String> {
+        self.features.lock().recovered().environment.clone()
+    }
+
+    /// Parse resources of `feature` and add them to their bundles, returning the hashes of the
+    /// ones that the bundles did not already have, or which are among `owned`. Overlays replace
+    /// the messages they define.
+    fn load_feature(
+        &self,
+        feature: &str,
+        overlay: bool,
+        resources: Vec<(LanguageIdentifier, UnparsedResource)>,
+        owned: &[(LanguageIdentifier, u64)],
+    ) -> Result<Vec<(LanguageIdentifier, u64)>, Error> {
+        let mut parsed = Vec::new();
+        for (lang, resource) in resources {
+            let is_text = matches!(resource, UnparsedResource::Text(_));
+            let mut res = resource.parse(&self.options, &lang)?;
+            if overlay {
+                res.origin = Origin::Environment(String::from(feature));
+            } else if is_text {
+                res.origin = Origin::Label(String::from(feature));
+            }
+            parsed.push((lang, res));
+        }
+
+        let mut loaded = Vec::new();
+        {
+            let bundles = self.bundles.read().or_poisoned()?;
+            for (lang, res) in &parsed {
+                let resource = (lang.clone(), res.hash);
+                let present = match bundles.get(lang) {
+                    Some(entry) => entry.read().or_poisoned()?.hashes.contains(&res.hash),
+                    None => false,
+                };
+                if (!present || owned.contains(&resource)) && !loaded.contains(&resource) {
+                    loaded.push(resource);
+                }
+            }
+        }
+        // Replacing messages is what overlays are for.
+        let added = if overlay {
+            self.add_parsed_under(parsed, DuplicateMessages::LastWins)
+        } else {
+            self.add_parsed(parsed)
+        };
+        // Duplicate messages and unknown terms are only reported once the resources are in, so
+        // they are taken out again.
+        if let Err(err) = added {
+            self.unload_feature(loaded)?;
+            return Err(err);
+        }
+        Ok(loaded)
+    }
+
+    /// Take resources out of their bundles, by rebuilding each bundle without them.
+    fn unload_feature(&self, loaded: Vec<(LanguageIdentifier, u64)>) -> Result<(), Error> {
+        let mut removed: HashMap<LanguageIdentifier, HashSet<u64>> = HashMap::new();
+        for (lang, hash) in loaded {
+            removed.entry(lang).or_default().insert(hash);
+        }
+        let mut bundles = self.bundles.write().or_poisoned()?;
+        let functions = self.functions.read().or_poisoned()?.clone();
+        for (lang, hashes) in removed {
+            let old = match bundles.get(&lang) {
+                Some(old) => Arc::clone(old),
+                None => continue,
+            };
+            let old = old.read().or_poisoned()?;
+            let mut entry = BundleEntry::<K>::new(lang.clone(), &functions);
+            entry.modified = old.modified;
+            for (hash, resource) in &old.resources {
+                if hashes.contains(hash) {
+                    continue;
+                }
+                if let Some(origin) = old.origins.get(hash) {
+                    entry.origins.insert(*hash, origin.clone());
+                }
+                // These resources were already added once, so any errors were already reported.
+                let _ = entry.add(*hash, Arc::clone(resource), old.overriding.contains(hash));
+            }
+            drop(old);
+            bundles.insert(lang, Arc::new(RwLock::new(entry)));
+        }
+        drop(bundles);
+        self.invalidate_missing();
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{canonicalize_language, BundleEntry, BundleKind, FluentErgo, PoisonExt};
+use fluent_syntax::{ast, serializer};
+use std::collections::HashSet;
+use unic_langid::LanguageIdentifier;
+
+/// FNV-1a, which unlike `DefaultHasher` gives the same result in every build.
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, text: &str) {
+        for byte in text.bytes().chain(std::iter::once(0)) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// A hash of the text that `lang` uses for `msgid`, for cache keys of rendered pages and
+    /// HTTP responses, so that they change exactly when the copy does. The hash covers the
+    /// message's value and attributes, and every message and term it refers to, but not comments
+    /// or formatting. It is the same in every build and on every platform, so it can be stored.
+    ///
+    /// `None` means that `lang` has no such message. Lazy resources only count once they have
+    /// been loaded.
+    ///
+    /// ```
+    /// use fluent_ergonomics::LanguageDelta;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("-brand = Aurora\nabout = About { -brand }"))
+    ///     .unwrap();
+    /// let before = fluent.message_fingerprint("about", &en_id).unwrap();
+    ///
+    /// fluent
+    ///     .apply_delta(vec![LanguageDelta {
+    ///         lang: en_id.clone(),
+    ///         changed: String::from("-brand = Aurora Browser"),
+    ///         removed: vec![],
+    ///     }])
+    ///     .unwrap();
+    /// assert_ne!(fluent.message_fingerprint("about", &en_id), Some(before));
+    /// ```
+    pub fn message_fingerprint(&self, msgid: &str, lang: &LanguageIdentifier) -> Option<u64> {
+        let lang = canonicalize_language(lang);
+        let bundles = self.bundles.read().recovered();
+        let overrides = self.overrides.read().recovered();
+        if let Some(overridden) = overrides.get(&lang) {
+            if let Some(fingerprint) = entry_fingerprint(&overridden.entry, msgid) {
+                return Some(fingerprint);
+            }
+        }
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.bundles.read().recovered())
+            .collect::<Vec<_>>();
+        std::iter::once(&*bundles)
+            .chain(layers.iter().map(|layer| &**layer))
+            .filter_map(|bundles| bundles.get(&lang))
+            .find_map(|entry| entry_fingerprint(&entry.read().recovered(), msgid))
+    }
+}
+
+/// Hash a message and everything it refers to. Fluent resolves references in the bundle which
+/// has the message, so they are looked up in the same entry.
+fn entry_fingerprint<K: BundleKind>(entry: &BundleEntry<K>, msgid: &str) -> Option<u64> {
+    let lookup = |id: &str| definition_text(entry.definition(id)?.1);
+    let (text, mut pending) = lookup(msgid)?;
+    let mut hash = Fnv::new();
+    hash.write(msgid);
+    hash.write(&text);
+    let mut seen: HashSet<String> = HashSet::new();
+    seen.insert(String::from(msgid));
+    pending.reverse();
+    while let Some(id) = pending.pop() {
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+        hash.write(&id);
+        match lookup(&id) {
+            Some((text, references)) => {
+                hash.write(&text);
+                pending.extend(references.into_iter().rev());
+            }
+            None => hash.write(""),
+        }
+    }
+    Some(hash.0)
+}
+
+fn definition_text(entry: &ast::Entry<&str>) -> Option<(String, Vec<String>)> {
+    let mut references = Vec::new();
+    let entry = match entry {
+        ast::Entry::Message(msg) => {
+            for pattern in msg
+                .value
+                .iter()
+                .chain(msg.attributes.iter().map(|a| &a.value))
+            {
+                pattern_references(pattern, &mut references);
+            }
+            ast::Entry::Message(ast::Message {
+                comment: None,
+                ..msg.clone()
+            })
+        }
+        ast::Entry::Term(term) => {
+            for pattern in
+                std::iter::once(&term.value).chain(term.attributes.iter().map(|a| &a.value))
+            {
+                pattern_references(pattern, &mut references);
+            }
+            ast::Entry::Term(ast::Term {
+                comment: None,
+                ..term.clone()
+            })
+        }
+        _ => return None,
+    };
+    let text = serializer::serialize(&ast::Resource { body: vec![entry] });
+    Some((text, references))
+}
+
+/// Collect the IDs of the messages and terms that a pattern refers to, in order.
+fn pattern_references(pattern: &ast::Pattern<&str>, references: &mut Vec<String>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_references(expression, references);
+        }
+    }
+}
+
+fn expression_references(expression: &ast::Expression<&str>, references: &mut Vec<String>) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            inline_references(selector, references);
+            for variant in variants {
+                pattern_references(&variant.value, references);
+            }
+        }
+        ast::Expression::Inline(inline) => inline_references(inline, references),
+    }
+}
+
+fn inline_references(inline: &ast::InlineExpression<&str>, references: &mut Vec<String>) {
+    let arguments = match inline {
+        ast::InlineExpression::MessageReference { id, .. } => {
+            references.push(String::from(id.name));
+            None
+        }
+        ast::InlineExpression::TermReference { id, arguments, .. } => {
+            references.push(format!("-{}", id.name));
+            arguments.as_ref()
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => Some(arguments),
+        ast::InlineExpression::Placeable { expression } => {
+            expression_references(expression, references);
+            None
+        }
+        _ => None,
+    };
+    if let Some(arguments) = arguments {
+        for argument in &arguments.positional {
+            inline_references(argument, references);
+        }
+        for argument in &arguments.named {
+            inline_references(&argument.value, references);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{BundleKind, Case, Error, Limit, Options};
+use fluent::memoizer::MemoizerKind;
+use fluent::resolver::errors::ReferenceKind;
+use fluent::resolver::ResolverError;
+use fluent::{FluentArgs, FluentError, FluentValue};
+use fluent_syntax::ast;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt;
+use unic_langid::LanguageIdentifier;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// What to do when Fluent finds a problem while formatting a message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OnFormatError {
+    /// Return whatever Fluent managed to produce. Fluent replaces the broken parts of the message
+    /// with markers such as `{$name}`. With the `tracing` feature, the errors are recorded on
+    /// the `tr` span.
+    #[default]
+    BestEffort,
+    /// Return a `FormatError` instead of a translation.
+    Fail,
+    /// Return this string instead of the translation.
+    Fallback(String),
+}
+
+/// How a translator renders an argument that a message uses but the caller did not give, after
+/// any defaults have been applied. See `FluentErgo::set_missing_arguments`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum MissingArguments {
+    /// Show the argument's name, as in `Hello, {$name}`. This is what Fluent does.
+    #[default]
+    Placeholder,
+    /// Leave the argument out, as in `Hello, `.
+    Empty,
+    /// Show this string in place of the argument.
+    Marker(String),
+    /// Return a `FormatError` instead of a translation, whatever the `TrOptions` say.
+    Fail,
+}
+
+/// Which parts of a translation are wrapped in Unicode isolation marks, U+2068 and U+2069, so that
+/// text written in one direction does not scramble the text around it: an Arabic user name in an
+/// English sentence, or an English product name in a Hebrew one. See `FluentErgo::set_isolation`.
+///
+/// | Mode         | Arguments | Other placeables | Marks written by the translator |
+/// |--------------|-----------|------------------|---------------------------------|
+/// | `Strip`      | -         | -                | Removed                         |
+/// | `Arguments`  | Wrapped   | -                | Kept                            |
+/// | `Placeables` | Wrapped   | Wrapped          | Kept                            |
+///
+/// Arguments include what functions make of them. In `Placeables` mode, which is
+/// what Fluent itself does, selectors and number literals are wrapped too, but message and term
+/// references and string literals are not, and neither is a message that is only one placeable.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Isolation {
+    /// Remove every isolation mark, so that translations can be compared with plain strings and
+    /// shown by toolkits which draw the marks as boxes.
+    #[default]
+    Strip,
+    /// Wrap only the values of arguments, which are the parts that the translator did not write.
+    Arguments,
+    /// Wrap placeables the way Fluent does.
+    Placeables,
+}
+
+/// Put around the value of each argument by `FluentErgo::tr_annotated`, so that it can find
+/// them in the translation. These are noncharacters, which Unicode keeps for uses like this.
+pub(crate) const ARGUMENT_START: char = '\u{fdd0}';
+pub(crate) const ARGUMENT_END: char = '\u{fdd1}';
+
+thread_local! {
+    /// Whether the bundle formatter wraps the values it writes, while a message is formatted in
+    /// `Isolation::Arguments` mode on this thread.
+    static ISOLATE_ARGUMENTS: Cell<bool> = const { Cell::new(false) };
+    /// Whether it marks them for `tr_annotated`.
+    static MARK_ARGUMENTS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets a flag for the current thread until it is dropped, so that a function which formats
+/// another message in the middle of this one does not turn it off.
+struct Flag(&'static std::thread::LocalKey<Cell<bool>>, bool);
+
+impl Flag {
+    fn set(key: &'static std::thread::LocalKey<Cell<bool>>, on: bool) -> Flag {
+        Flag(key, key.with(|flag| flag.replace(on)))
+    }
+}
+
+impl Drop for Flag {
+    fn drop(&mut self) {
+        let previous = self.1;
+        self.0.with(|flag| flag.set(previous));
+    }
+}
+
+/// Run `f` with the value of every argument that is formatted on this thread between
+/// `ARGUMENT_START` and `ARGUMENT_END`.
+pub(crate) fn marking_arguments<T>(f: impl FnOnce() -> T) -> T {
+    let _marking = Flag::set(&MARK_ARGUMENTS, true);
+    f()
+}
+
+/// The formatter of every bundle. It writes values as Fluent would, except in
+/// `Isolation::Arguments` mode, where it wraps them in isolation marks, and for `tr_annotated`,
+/// where it marks them.
+pub(crate) fn format_argument<M: MemoizerKind>(value: &FluentValue, intls: &M) -> Option<String> {
+    let isolate = ISOLATE_ARGUMENTS.with(Cell::get);
+    let mark = MARK_ARGUMENTS.with(Cell::get);
+    if !isolate && !mark {
+        return None;
+    }
+    let text = match value {
+        FluentValue::String(s) => Cow::Borrowed(s.as_ref()),
+        FluentValue::Number(n) => n.as_string(),
+        FluentValue::Custom(custom) => intls.stringify_value(&**custom),
+        FluentValue::Error | FluentValue::None => return None,
+    };
+    let mut out = String::with_capacity(text.len() + 12);
+    if mark {
+        out.push(ARGUMENT_START);
+    }
+    if isolate {
+        out.push('\u{2068}');
+    }
+    out.push_str(&text);
+    if isolate {
+        out.push('\u{2069}');
+    }
+    if mark {
+        out.push(ARGUMENT_END);
+    }
+    Some(out)
+}
+
+/// Per-call settings for `tr_opts`. Different surfaces need different strictness: a log line can
+/// tolerate a broken placeholder, but a UI label might rather show a fixed fallback, and a test
+/// suite usually wants to fail outright.
+///
+/// ```
+/// use fluent_ergonomics::{OnFormatError, TrOptions};
+///
+/// let opts = TrOptions {
+///     on_unresolved_reference: OnFormatError::Fallback(String::from("—")),
+///     ..TrOptions::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrOptions {
+    /// A variable, message, or term that the message refers to does not exist
+    pub on_unresolved_reference: OnFormatError,
+    /// A select expression has no default variant that matches
+    pub on_bad_selector: OnFormatError,
+    /// The message refers to itself, or expands into far too many placeables
+    pub on_cyclic_reference: OnFormatError,
+    /// How to change the capitalization of the translation. This is not applied to fallbacks.
+    pub case: Case,
+    /// Which parts of the translation to wrap in isolation marks, such as `Isolation::Strip` for
+    /// a log file and `Isolation::Arguments` for the user interface. `None` uses the translator's
+    /// setting, from `FluentErgo::set_isolation`.
+    pub isolation: Option<Isolation>,
+}
+
+impl TrOptions {
+    /// Options which apply the same policy to every kind of formatting error.
+    pub fn on_any_error(policy: OnFormatError) -> TrOptions {
+        TrOptions {
+            on_unresolved_reference: policy.clone(),
+            on_bad_selector: policy.clone(),
+            on_cyclic_reference: policy,
+            case: Case::AsWritten,
+            isolation: None,
+        }
+    }
+
+    fn policy_for(&self, error: &FluentError) -> &OnFormatError {
+        match error {
+            FluentError::ResolverError(ResolverError::Reference(_))
+            | FluentError::ResolverError(ResolverError::NoValue(_)) => {
+                &self.on_unresolved_reference
+            }
+            FluentError::ResolverError(ResolverError::MissingDefault) => &self.on_bad_selector,
+            FluentError::ResolverError(ResolverError::Cyclic)
+            | FluentError::ResolverError(ResolverError::TooManyPlaceables) => {
+                &self.on_cyclic_reference
+            }
+            _ => &OnFormatError::BestEffort,
+        }
+    }
+}
+
+/// Format a message from a single bundle. `Ok(None)` means that the bundle has no value for the
+/// message, and the caller should try the next language.
+pub(crate) fn format_message<K: BundleKind>(
+    options: &Options,
+    tr_options: &TrOptions,
+    lang: &LanguageIdentifier,
+    bundle: &K::Bundle,
+    msgid: &str,
+    args: Option<&FluentArgs>,
+) -> Result<Option<String>, Error> {
+    let mut out = String::new();
+    let found = write_message::<K>(options, tr_options, lang, bundle, msgid, args, &mut out)?;
+    Ok(if found { Some(out) } else { None })
+}
+
+/// Like `format_message`, but appends the translation to `out`, which is left as it was if there
+/// is no value or an error. Returns whether the bundle had a value for the message.
+pub(crate) fn write_message<K: BundleKind>(
+    options: &Options,
+    tr_options: &TrOptions,
+    lang: &LanguageIdentifier,
+    bundle: &K::Bundle,
+    msgid: &str,
+    args: Option<&FluentArgs>,
+    out: &mut String,
+) -> Result<bool, Error> {
+    let normalized_args = match args {
+        Some(args) if options.normalize_nfc => Some(nfc_args(args)),
+        _ => None,
+    };
+    let args = normalized_args.as_ref().or(args);
+
+    // Arguments the caller left out come from the message's own defaults first, and then from
+    // the defaults configured on the translator.
+    let defaults = K::default_args(bundle, msgid);
+    let configured = options.default_args.get(msgid);
+    let with_defaults;
+    let args = if defaults.is_empty() && configured.is_none() {
+        args
+    } else {
+        let mut merged: FluentArgs = args
+            .map(|args| {
+                args.iter()
+                    .map(|(name, value)| (name, value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for (name, value) in defaults.iter().chain(configured.into_iter().flatten()) {
+            if merged.get(name.as_str()).is_none() {
+                merged.set(name.as_str(), FluentValue::try_number(value));
+            }
+        }
+        with_defaults = merged;
+        Some(&with_defaults)
+    };
+
+    let start = out.len();
+    // Fluent insists on somewhere to put errors, but an empty `Vec` does not allocate, so a call
+    // without errors pays nothing for it. Everything below that inspects the errors is skipped
+    // when there are none.
+    let mut errors = Vec::new();
+    let isolation = tr_options.isolation.unwrap_or(options.isolation);
+    let _isolating = Flag::set(&ISOLATE_ARGUMENTS, isolation == Isolation::Arguments);
+    let mut writer = Isolating::new(out, options, isolation);
+    if K::write(bundle, msgid, args, &mut writer, &mut errors).is_none() {
+        return Ok(false);
+    }
+    let mut truncated = writer.truncated;
+    if options.missing_arguments != MissingArguments::Placeholder {
+        let missing: Vec<String> = errors
+            .iter()
+            .filter_map(|err| match err {
+                FluentError::ResolverError(ResolverError::Reference(ReferenceKind::Variable {
+                    id,
+                })) => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        let replacement = match &options.missing_arguments {
+            _ if missing.is_empty() => None,
+            MissingArguments::Fail => {
+                out.truncate(start);
+                return Err(Error::FormatError {
+                    id: String::from(msgid),
+                    lang: lang.clone(),
+                    errors,
+                });
+            }
+            MissingArguments::Marker(marker) => Some(marker.as_str()),
+            _ => Some(""),
+        };
+        if let Some(replacement) = replacement {
+            // Format again with the missing arguments filled in, so that Fluent puts the
+            // replacement exactly where the argument goes.
+            let mut filled: FluentArgs = args
+                .map(|args| {
+                    args.iter()
+                        .map(|(name, value)| (name, value.clone()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            for name in missing {
+                filled.set(name, FluentValue::from(replacement));
+            }
+            out.truncate(start);
+            errors.clear();
+            let mut writer = Isolating::new(out, options, isolation);
+            K::write(bundle, msgid, Some(&filled), &mut writer, &mut errors);
+            truncated = writer.truncated;
+        }
+    }
+    if truncated {
+        out.truncate(start);
+        return Err(Error::LimitExceeded {
+            id: String::from(msgid),
+            lang: lang.clone(),
+            limit: Limit::Length,
+        });
+    }
+    if !errors.is_empty() {
+        let policies: Vec<&OnFormatError> = errors
+            .iter()
+            .map(|err| tr_options.policy_for(err))
+            .collect();
+        if policies.contains(&&OnFormatError::Fail) {
+            out.truncate(start);
+            return Err(Error::FormatError {
+                id: String::from(msgid),
+                lang: lang.clone(),
+                errors,
+            });
+        }
+        let fallback = policies.iter().find_map(|policy| match policy {
+            OnFormatError::Fallback(fallback) => Some(fallback.clone()),
+            _ => None,
+        });
+        if let Some(fallback) = fallback {
+            out.truncate(start);
+            out.push_str(&fallback);
+            return Ok(true);
+        }
+        trace_record!("errors", tracing::field::debug(&errors));
+    }
+
+    if tr_options.case != Case::AsWritten {
+        let translation = tr_options.case.apply(out.split_off(start), lang);
+        options.limits.check_len(msgid, lang, translation.len())?;
+        out.push_str(&translation);
+    }
+    Ok(true)
+}
+
+/// Format a pattern of `id`, such as the value of a term, in the translator's `Isolation` mode
+/// and within its length limit, as `write_message` formats the value of a message.
+pub(crate) fn format_pattern<K: BundleKind>(
+    options: &Options,
+    lang: &LanguageIdentifier,
+    id: &str,
+    bundle: &K::Bundle,
+    pattern: &ast::Pattern<&str>,
+    args: Option<&FluentArgs>,
+    errors: &mut Vec<FluentError>,
+) -> Result<String, Error> {
+    let mut out = String::new();
+    let _isolating = Flag::set(
+        &ISOLATE_ARGUMENTS,
+        options.isolation == Isolation::Arguments,
+    );
+    let mut writer = Isolating::new(&mut out, options, options.isolation);
+    K::write_pattern(bundle, pattern, args, &mut writer, errors);
+    if writer.truncated {
+        return Err(Error::LimitExceeded {
+            id: String::from(id),
+            lang: lang.clone(),
+            limit: Limit::Length,
+        });
+    }
+    Ok(out)
+}
+
+/// Drops the Unicode isolation marks that the `Isolation` mode does not want as they are written,
+/// so that nothing has to be removed from the output afterwards. It stops Fluent as soon as the
+/// translation would go over the length limit, so that the rest of it is never built.
+struct Isolating<'a> {
+    out: &'a mut String,
+    isolation: Isolation,
+    end: usize,
+    truncated: bool,
+}
+
+impl<'a> Isolating<'a> {
+    fn new(out: &'a mut String, options: &Options, isolation: Isolation) -> Isolating<'a> {
+        let end = match options.limits.max_len {
+            Some(max_len) => out.len().saturating_add(max_len),
+            None => usize::MAX,
+        };
+        Isolating {
+            out,
+            isolation,
+            end,
+            truncated: false,
+        }
+    }
+}
+
+impl fmt::Write for Isolating<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let marks: &[char] = match self.isolation {
+            Isolation::Strip => &['\u{2068}', '\u{2069}'],
+            // Fluent writes the marks around a placeable on their own; the formatter has already
+            // put the ones that are wanted around the value.
+            Isolation::Arguments if s == "\u{2068}" || s == "\u{2069}" => return Ok(()),
+            _ => &[],
+        };
+        for part in s.split(marks) {
+            if self.out.len().saturating_add(part.len()) > self.end {
+                self.truncated = true;
+                return Err(fmt::Error);
+            }
+            self.out.push_str(part);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn nfc(text: String) -> String {
+    if is_nfc(&text) {
+        text
+    } else {
+        text.nfc().collect()
+    }
+}
+
+fn nfc_args<'a>(args: &'a FluentArgs) -> FluentArgs<'a> {
+    args.iter()
+        .map(|(key, value)| match value {
+            FluentValue::String(s) if !is_nfc(s) => {
+                (key, FluentValue::from(s.nfc().collect::<String>()))
+            }
+            _ => (key, value.clone()),
+        })
+        .collect()
+}
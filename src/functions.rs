@@ -0,0 +1,211 @@
+use crate::number::{percent_function, range_function, range_separator, PercentStyle};
+use crate::text::{quotation_marks, quote_function, truncate_function};
+use crate::{BundleKind, Error, FluentErgo, PoisonExt};
+use fluent::{FluentArgs, FluentValue};
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// A function which messages can call, such as `{ ROLE($user) }`. It is given the language of
+/// the bundle which has the message as well as the arguments of the call.
+pub(crate) type Function = Arc<
+    dyn for<'a> Fn(&LanguageIdentifier, &[FluentValue<'a>], &FluentArgs) -> FluentValue<'a>
+        + Send
+        + Sync,
+>;
+
+/// Formats one kind of value, such as phone numbers or postal codes, in the way of a language
+/// and its region. Register it with `FluentErgo::add_formatter`, and messages can call it like
+/// any other function.
+pub trait DomainFormatter: Send + Sync + 'static {
+    /// Format `value` for `lang`, with the named arguments of the call as `options`. `None`
+    /// means that the value is not one the formatter understands, and it is shown as it was
+    /// given.
+    fn format(
+        &self,
+        value: &str,
+        lang: &LanguageIdentifier,
+        options: &FluentArgs,
+    ) -> Option<String>;
+}
+
+/// The names of the functions which every bundle has, before any from `add_function`.
+pub(crate) const BUILTINS: &[&str] = &["TRUNCATE", "QUOTE", "RANGE", "PERCENT", "PERMILLE"];
+
+/// The functions which every bundle in `lang` has, in the order of `BUILTINS`.
+pub(crate) fn builtins(lang: &LanguageIdentifier) -> Vec<(&'static str, Function)> {
+    let (open, close) = quotation_marks(lang);
+    let separator = range_separator(lang);
+    let style = PercentStyle::for_language(lang);
+    vec![
+        (
+            "TRUNCATE",
+            Arc::new(|_, positional, named| truncate_function(positional, named)),
+        ),
+        (
+            "QUOTE",
+            Arc::new(move |_, positional, _| quote_function(positional, open, close)),
+        ),
+        (
+            "RANGE",
+            Arc::new(move |_, positional, _| range_function(positional, separator)),
+        ),
+        (
+            "PERCENT",
+            Arc::new(move |_, positional, named| {
+                percent_function(positional, named, style, '%', 100.0)
+            }),
+        ),
+        (
+            "PERMILLE",
+            Arc::new(move |_, positional, named| {
+                percent_function(positional, named, style, '‰', 1000.0)
+            }),
+        ),
+    ]
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Make a function available to the messages of every language, including languages which
+    /// are loaded later. The function receives the positional and named arguments of the call,
+    /// and may return any value, including a custom one from `IntoFluentValue`:
+    ///
+    /// ```
+    /// use fluent::{FluentArgs, FluentValue};
+    /// use fluent_ergonomics::FluentArgsExt;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_function("SHOUT", |positional, _| match positional.first() {
+    ///         Some(FluentValue::String(text)) => FluentValue::from(text.to_uppercase()),
+    ///         _ => FluentValue::Error,
+    ///     })
+    ///     .unwrap();
+    /// fluent
+    ///     .add_from_text(en_id, String::from("warning = { SHOUT($text) }!"))
+    ///     .unwrap();
+    ///
+    /// let mut args = FluentArgs::new();
+    /// args.set_value("text", "careful");
+    /// assert_eq!(fluent.tr("warning", Some(&args)).unwrap(), "CAREFUL!");
+    /// ```
+    ///
+    /// Clones of this translator share their bundles, and so they share their functions as well.
+    ///
+    /// Every translator also has these functions, which cannot be replaced:
+    ///
+    /// * `TRUNCATE($text, 40)` -- the text, cut down to at most 40 graphemes if it is longer, so
+    ///   that a translator can bound user content such as `{ TRUNCATE($title, 40) }…`. With
+    ///   `ellipsis: "…"`, the ellipsis is only added when the text is cut, and counts towards
+    ///   the 40.
+    /// * `QUOTE($text)` -- the text in the quotation marks of the bundle's language, such as
+    ///   “text” in English, „text“ in German, «text» in French, and 「text」 in Japanese
+    /// * `RANGE($min, $max)` -- the two numbers as a range in the bundle's language, such as
+    ///   3–5 in English, 3-5 in Spanish, and 3～5 in Japanese, or just one number if they are
+    ///   the same. The numbers may come from `NUMBER`, to set their digits.
+    /// * `PERCENT($ratio)` -- the fraction as a percentage in the bundle's language, such as
+    ///   25% in English, 25 % in German and %25 in Turkish for 0.25. It rounds to a whole
+    ///   percent unless `maximumFractionDigits` or `minimumFractionDigits` say otherwise.
+    ///   `PERMILLE($ratio)` does the same in per mille. `Percent` and `Permille` arguments
+    ///   format the same way without a function.
+    ///
+    /// # Errors
+    ///
+    /// * FluentError -- a function with this name has already been added, or is built in
+    ///
+    pub fn add_function<F>(&mut self, name: &str, function: F) -> Result<(), Error>
+    where
+        F: for<'a> Fn(&[FluentValue<'a>], &FluentArgs) -> FluentValue<'a> + Send + Sync + 'static,
+    {
+        self.register_function(
+            name,
+            Arc::new(move |_, positional, named| function(positional, named)),
+        )
+    }
+
+    /// Make a `DomainFormatter` available to the messages of every language as the function
+    /// `name`, so that a value such as a phone number can be passed to a message as it is
+    /// stored, and be formatted in the message's language:
+    ///
+    /// ```
+    /// use fluent::FluentArgs;
+    /// use fluent_ergonomics::DomainFormatter;
+    /// use unic_langid::LanguageIdentifier;
+    ///
+    /// struct Phone;
+    ///
+    /// impl DomainFormatter for Phone {
+    ///     fn format(&self, value: &str, lang: &LanguageIdentifier, _: &FluentArgs) -> Option<String> {
+    ///         let digits = value.strip_prefix("+1")?;
+    ///         match lang.region.as_ref().map(|region| region.as_str()) {
+    ///             Some("US") => Some(format!("({}) {}-{}", &digits[..3], &digits[3..6], &digits[6..])),
+    ///             _ => Some(format!("+1 {} {} {}", &digits[..3], &digits[3..6], &digits[6..])),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_formatter("PHONE", Phone).unwrap();
+    /// fluent
+    ///     .add_from_text(en_id, String::from("call = Call { PHONE($number) }"))
+    ///     .unwrap();
+    ///
+    /// let mut args = FluentArgs::new();
+    /// args.set("number", "+12025550123");
+    /// assert_eq!(fluent.tr("call", Some(&args)).unwrap(), "Call (202) 555-0123");
+    /// ```
+    ///
+    /// The formatter is given the value as text, whether it was passed as a string or a number.
+    /// A call with anything other than one string or number fails, and Fluent shows it as the
+    /// name of the function, such as `PHONE()`.
+    ///
+    /// # Errors
+    ///
+    /// * FluentError -- a function with this name has already been added, or is built in
+    ///
+    pub fn add_formatter<D: DomainFormatter>(
+        &mut self,
+        name: &str,
+        formatter: D,
+    ) -> Result<(), Error> {
+        self.register_function(
+            name,
+            Arc::new(move |lang, positional, named| {
+                let text = match positional {
+                    [FluentValue::String(text)] => text.clone(),
+                    [FluentValue::Number(number)] => number.as_string(),
+                    _ => return FluentValue::Error,
+                };
+                formatter
+                    .format(&text, lang, named)
+                    .map_or_else(|| positional[0].clone(), FluentValue::from)
+            }),
+        )
+    }
+
+    fn register_function(&mut self, name: &str, function: Function) -> Result<(), Error> {
+        let bundles = self.bundles.read().recovered();
+        let mut overrides = self.overrides.write().recovered();
+        let mut synthetic = self.synthetic.write().recovered();
+        let mut functions = self.functions.write().recovered();
+        if BUILTINS.contains(&name) || functions.iter().any(|(existing, _)| existing == name) {
+            // Let Fluent describe the conflict, from a bundle that already has the function.
+            let mut bundle = K::new_bundle(LanguageIdentifier::default(), &functions);
+            return K::add_function(&mut bundle, name, &function)
+                .map_err(|err| Error::from(vec![err]));
+        }
+
+        for entry in bundles.values() {
+            let _ = K::add_function(&mut entry.write().recovered().bundle, name, &function);
+        }
+        for overridden in overrides.values_mut() {
+            let _ = K::add_function(&mut overridden.entry.bundle, name, &function);
+        }
+        for entry in synthetic.values_mut() {
+            let _ = K::add_function(&mut entry.bundle, name, &function);
+        }
+        functions.push((String::from(name), function));
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{Concurrent, Error, FluentErgo};
+use fluent::FluentArgs;
+use std::future::Future;
+use unic_langid::LanguageIdentifier;
+
+/// Futures for async applications. Each future owns a clone of the translator and all of its
+/// arguments, so it is `Send + 'static` and can be handed to `tokio::spawn` or any other
+/// multi-threaded executor. The work itself does not wait on anything, but it may read lazy
+/// resource files or ask a `ResourceProvider`, which is why an application may prefer to run it
+/// on a blocking pool.
+///
+/// Only `Concurrent` translators have these methods, since a `SingleThreaded` translator cannot
+/// leave the thread it was created on.
+impl FluentErgo<Concurrent> {
+    /// `tr`, as a future.
+    ///
+    /// ```
+    /// use fluent::FluentArgs;
+    ///
+    /// fn assert_send<T: Send + 'static>(_: &T) {}
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id, String::from("greeting = Hello, { $name }"))
+    ///     .unwrap();
+    ///
+    /// let mut args = FluentArgs::new();
+    /// args.set("name", String::from("Ana"));
+    /// let greeting = fluent.tr_async("greeting", Some(args));
+    /// assert_send(&greeting);
+    /// ```
+    pub fn tr_async(
+        &self,
+        msgid: &str,
+        args: Option<FluentArgs<'static>>,
+    ) -> impl Future<Output = Result<String, Error>> + Send + 'static {
+        let fluent = self.clone();
+        let msgid = String::from(msgid);
+        async move { fluent.tr(&msgid, args.as_ref()) }
+    }
+
+    /// `warm_up`, as a future.
+    pub fn warm_up_async(
+        &self,
+        lang: &LanguageIdentifier,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'static {
+        let fluent = self.clone();
+        let lang = lang.clone();
+        async move { fluent.warm_up(&lang) }
+    }
+
+    /// `load_lazy_resources`, as a future.
+    pub fn load_lazy_resources_async(
+        &self,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'static {
+        let fluent = self.clone();
+        async move { fluent.load_lazy_resources() }
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{canonicalize_language, BundleKind, Error, FluentErgo, PoisonExt};
+use fluent::FluentArgs;
+use fluent_syntax::ast;
+use std::fmt;
+use unic_langid::LanguageIdentifier;
+
+/// The argument that `FluentErgo::trg` gives the gender in.
+const GENDER_ARG: &str = "gender";
+
+/// The argument that `FluentErgo::trg_case` gives the grammatical case in.
+const CASE_ARG: &str = "case";
+
+/// The grammatical gender of whoever or whatever a message is about, for `FluentErgo::trg`.
+/// Messages see it as `$gender`, with the CLDR name of the gender, such as `feminine`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+    /// The gender that Danish, Dutch and Swedish have in place of masculine and feminine
+    Common,
+    /// No gender, or one that is not known. Messages should handle it in their default variant.
+    Other,
+}
+
+impl Gender {
+    /// The variant key that messages select on for this gender.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Gender::Masculine => "masculine",
+            Gender::Feminine => "feminine",
+            Gender::Neuter => "neuter",
+            Gender::Common => "common",
+            Gender::Other => "other",
+        }
+    }
+
+    /// The genders which the grammar of `lang` distinguishes, from the CLDR grammatical
+    /// features. Languages which are not listed, such as English, have none.
+    pub fn of_language(lang: &LanguageIdentifier) -> &'static [Gender] {
+        use Gender::*;
+
+        match lang.language.as_str() {
+            "be" | "bg" | "cs" | "de" | "el" | "hr" | "is" | "nb" | "pl" | "ro" | "ru" | "sk"
+            | "sl" | "sr" | "uk" => &[Masculine, Feminine, Neuter],
+            "ar" | "ca" | "es" | "fr" | "he" | "hi" | "it" | "lt" | "lv" | "pt" | "ur" => {
+                &[Masculine, Feminine]
+            }
+            "da" | "nl" | "sv" => &[Common, Neuter],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Gender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A message which selects on `$gender` without a variant for every gender of its language,
+/// from `FluentErgo::gender_gaps`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenderGap {
+    pub lang: LanguageIdentifier,
+    pub id: String,
+    /// The genders of the language which fall through to the default variant
+    pub missing: Vec<Gender>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Translate a message about someone or something of the grammatical gender `gender`, which
+    /// the message receives as `$gender` along with `args`. This is the convention for agreeing
+    /// with a gender: select on `$gender`, with a variant for each gender that the language
+    /// has, as `Gender::of_language` lists them, and the default variant for `other`.
+    ///
+    /// ```
+    /// use fluent_ergonomics::Gender;
+    ///
+    /// let fr_id = "fr".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[fr_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         fr_id,
+    ///         String::from(
+    ///             "
+    /// invited = { $gender ->
+    ///     [masculine] Il est invité
+    ///     [feminine] Elle est invitée
+    ///    *[other] Vous êtes invité·e
+    /// }
+    /// ",
+    ///         ),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(fluent.trg("invited", Gender::Feminine, None).unwrap(), "Elle est invitée");
+    /// assert_eq!(fluent.trg("invited", Gender::Other, None).unwrap(), "Vous êtes invité·e");
+    /// ```
+    ///
+    /// A `$gender` in `args` is replaced.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn trg(
+        &self,
+        msgid: &str,
+        gender: Gender,
+        args: Option<&FluentArgs>,
+    ) -> Result<String, Error> {
+        self.tr(msgid, Some(&inflected(args, gender, None)))
+    }
+
+    /// Like `trg`, but also give the message the grammatical case it should be in as `$case`,
+    /// for languages which decline nouns. Cases are named as in CLDR, such as `nominative`,
+    /// `accusative` or `genitive`, and messages should select on them with a default variant.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn trg_case(
+        &self,
+        msgid: &str,
+        gender: Gender,
+        case: &str,
+        args: Option<&FluentArgs>,
+    ) -> Result<String, Error> {
+        self.tr(msgid, Some(&inflected(args, gender, Some(case))))
+    }
+
+    /// Find messages which select on `$gender`, but which do not have a variant for every gender
+    /// of their language, so that some genders get the default text. Every loaded language is
+    /// checked, sorted by language and then by message ID, along with the attributes of each
+    /// message. Lazy resources only count once they have been loaded.
+    ///
+    /// ```
+    /// use fluent_ergonomics::Gender;
+    ///
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         de_id,
+    ///         String::from(
+    ///             "
+    /// welcome = { $gender ->
+    ///     [masculine] Willkommen, lieber Kunde
+    ///    *[other] Willkommen
+    /// }
+    /// ",
+    ///         ),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let gaps = fluent.gender_gaps();
+    /// assert_eq!(gaps[0].id, "welcome");
+    /// assert_eq!(gaps[0].missing, vec![Gender::Feminine, Gender::Neuter]);
+    /// ```
+    pub fn gender_gaps(&self) -> Vec<GenderGap> {
+        let bundles = self.bundles.read().recovered();
+        let mut languages: Vec<&LanguageIdentifier> = bundles.keys().collect();
+        languages.sort_by_key(|lang| lang.to_string());
+
+        let mut gaps = Vec::new();
+        for lang in languages {
+            let genders = Gender::of_language(&canonicalize_language(lang));
+            if genders.is_empty() {
+                continue;
+            }
+            let entry = bundles[lang].read().recovered();
+            for id in entry.message_ids() {
+                let msg = match entry.definition(id) {
+                    Some((_, ast::Entry::Message(msg))) => msg,
+                    _ => continue,
+                };
+                let mut missing = Vec::new();
+                for pattern in msg
+                    .value
+                    .iter()
+                    .chain(msg.attributes.iter().map(|attr| &attr.value))
+                {
+                    missing_genders(pattern, genders, &mut missing);
+                }
+                missing.sort_by_key(|gender| genders.iter().position(|g| g == gender));
+                if !missing.is_empty() {
+                    gaps.push(GenderGap {
+                        lang: lang.clone(),
+                        id: String::from(id),
+                        missing,
+                    });
+                }
+            }
+        }
+        gaps
+    }
+}
+
+/// `args`, with the gender and the case set.
+fn inflected<'a>(
+    args: Option<&'a FluentArgs>,
+    gender: Gender,
+    case: Option<&'a str>,
+) -> FluentArgs<'a> {
+    let mut inflected = FluentArgs::new();
+    for (name, value) in args.into_iter().flat_map(|args| args.iter()) {
+        if name != GENDER_ARG && (case.is_none() || name != CASE_ARG) {
+            inflected.set(name, value.clone());
+        }
+    }
+    inflected.set(GENDER_ARG, gender.as_str());
+    if let Some(case) = case {
+        inflected.set(CASE_ARG, case);
+    }
+    inflected
+}
+
+/// Add the genders which some select expression on `$gender` in a pattern has no variant for to
+/// `missing`.
+fn missing_genders(pattern: &ast::Pattern<&str>, genders: &[Gender], missing: &mut Vec<Gender>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_missing_genders(expression, genders, missing);
+        }
+    }
+}
+
+fn expression_missing_genders(
+    expression: &ast::Expression<&str>,
+    genders: &[Gender],
+    missing: &mut Vec<Gender>,
+) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            if matches!(
+                selector,
+                ast::InlineExpression::VariableReference { id } if id.name == GENDER_ARG
+            ) {
+                for gender in genders {
+                    let found = variants.iter().any(|variant| {
+                        matches!(
+                            variant.key,
+                            ast::VariantKey::Identifier { name } if name == gender.as_str()
+                        )
+                    });
+                    if !found && !missing.contains(gender) {
+                        missing.push(*gender);
+                    }
+                }
+            }
+            for variant in variants {
+                missing_genders(&variant.value, genders, missing);
+            }
+        }
+        ast::Expression::Inline(ast::InlineExpression::Placeable { expression }) => {
+            expression_missing_genders(expression, genders, missing)
+        }
+        ast::Expression::Inline(_) => {}
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{BundleKind, BundleMap, Error, FluentErgo, Function, PoisonExt};
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+/// The catalogs which reloads replaced, newest last, for `FluentErgo::rollback`.
+pub(crate) struct History<K: BundleKind> {
+    limit: usize,
+    snapshots: VecDeque<BundleMap<K>>,
+}
+
+impl<K: BundleKind> Default for History<K> {
+    fn default() -> Self {
+        History {
+            limit: 0,
+            snapshots: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: BundleKind> History<K> {
+    /// Remember the catalog that a reload is about to replace. Each bundle is copied, since a
+    /// reload which only replaces some languages leaves the others in use, and resources added
+    /// to them afterwards must not show up in the snapshot.
+    pub(crate) fn push(
+        &mut self,
+        bundles: &BundleMap<K>,
+        functions: &[(String, Function)],
+    ) -> Result<(), Error> {
+        if self.limit == 0 {
+            return Ok(());
+        }
+        let mut snapshot = BundleMap::with_capacity(bundles.len());
+        for (lang, entry) in bundles {
+            let entry = entry
+                .read()
+                .or_poisoned()?
+                .snapshot(lang.clone(), functions);
+            snapshot.insert(lang.clone(), Arc::new(RwLock::new(entry)));
+        }
+        if self.snapshots.len() == self.limit {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+        Ok(())
+    }
+
+    /// Forget every snapshot.
+    pub(crate) fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Keep the catalogs which the last `limit` reloads replaced, so that `rollback` can put
+    /// them back, for instance after an over-the-air update pushes bad translations to
+    /// production. Reloads are `replace_all` and the updates of an `OtaClient`. The default is
+    /// to keep none.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.set_catalog_history(2);
+    /// fluent.replace_all(vec![(en_id.clone(), String::from("units = Units"))]).unwrap();
+    /// fluent.replace_all(vec![(en_id, String::from("units = Unitz"))]).unwrap();
+    ///
+    /// assert!(fluent.rollback().unwrap());
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Units");
+    /// ```
+    ///
+    /// Snapshots share the parsed resources of the catalog they were taken of, so each one only
+    /// costs memory for its own bundles and for the resources that later reloads replaced, and
+    /// taking one builds the bundles again without parsing anything. Lowering the limit forgets
+    /// the oldest snapshots. The history is shared by every clone of this translator, and is
+    /// dropped by `freeze`.
+    pub fn set_catalog_history(&mut self, limit: usize) {
+        let mut history = self.history.lock().recovered();
+        history.limit = limit;
+        while history.snapshots.len() > limit {
+            history.snapshots.pop_front();
+        }
+    }
+
+    /// Put back the catalog from before the latest reload which has not been rolled back yet,
+    /// returning whether there was one. Each call steps back one more reload, as far as
+    /// `set_catalog_history` allows.
+    ///
+    /// Everything which was added since that reload is dropped along with the catalog it was
+    /// added to, and lazy resources which the reload dropped do not come back. Rolling back is
+    /// not a reload itself, so the reload validator is not asked.
+    ///
+    /// # Errors
+    ///
+    /// * `Poisoned`
+    ///
+    pub fn rollback(&mut self) -> Result<bool, Error> {
+        let mut bundles = self.bundles.write().or_poisoned()?;
+        let snapshot = match self.history.lock().or_poisoned()?.snapshots.pop_back() {
+            Some(snapshot) => snapshot,
+            None => return Ok(false),
+        };
+        *bundles = snapshot;
+        drop(bundles);
+        self.invalidate_missing();
+        Ok(true)
+    }
+
+    /// How many reloads `rollback` can still undo.
+    pub fn catalog_history_len(&self) -> usize {
+        self.history.lock().recovered().snapshots.len()
+    }
+}
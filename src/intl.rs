@@ -0,0 +1,199 @@
+use crate::unload::IdleTracker;
+use crate::{canonicalize_language, BundleKind, Error, FluentErgo, PoisonExt};
+use fluent::{FluentArgs, FluentResource};
+use fluent_syntax::ast;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LockResult, Mutex, MutexGuard};
+use unic_langid::LanguageIdentifier;
+
+/// A message whose only purpose is to make a bundle build its plural rules.
+const PLURAL_WARM_UP: &str = "warm-up = { $count -> [one] one *[other] other }";
+
+/// Which languages have formatted a message most recently, so that the plural rules and other
+/// locale data which Fluent caches in their bundles can be dropped for the rest.
+#[derive(Debug, Default)]
+pub(crate) struct IntlCache {
+    limit: Option<usize>,
+    /// Most recently used last.
+    pub(crate) recent: VecDeque<LanguageIdentifier>,
+    pub(crate) idle: Option<IdleTracker>,
+}
+
+/// The `IntlCache`, behind a flag which lets `touch_intl` skip the lock when there is neither a
+/// limit nor an idle policy to keep up.
+#[derive(Debug, Default)]
+pub(crate) struct Intl {
+    active: AtomicBool,
+    cache: Mutex<IntlCache>,
+}
+
+impl Intl {
+    pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, IntlCache>> {
+        self.cache.lock()
+    }
+
+    /// Bring the flag up to date after `cache` has had its limit or idle policy changed.
+    pub(crate) fn update(&self, cache: &IntlCache) {
+        let active = cache.limit.is_some() || cache.idle.is_some();
+        self.active.store(active, Ordering::Release);
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Keep cached locale data, such as plural rules, for at most `limit` languages at a time.
+    /// Fluent builds this data the first time a bundle needs it, and keeps it for as long as the
+    /// bundle exists, so a long-running process that formats messages in many languages would
+    /// otherwise hold on to the data of every one of them. When a message is formatted in a
+    /// language beyond the limit, the cache of the language that was used least recently is
+    /// cleared. `None`, the default, never clears anything.
+    ///
+    /// Clearing the cache of a language rebuilds its bundle from the resources which it has
+    /// already parsed, which costs about as much as adding them again. A limit below the number
+    /// of languages which are in use at the same time therefore rebuilds a bundle on nearly
+    /// every call when they take turns, so it should leave room for all of them.
+    ///
+    /// Fluent gives every bundle a cache of its own, and has no way to make bundles share one,
+    /// so it is the limit that is shared rather than the cache: it counts the languages of every
+    /// bundle of this translator together. Clones of this translator share their bundles, and so
+    /// they share this limit as well.
+    pub fn set_intl_cache_limit(&mut self, limit: Option<usize>) {
+        let evicted = {
+            let mut cache = self.intl.lock().recovered();
+            cache.limit = limit;
+            self.intl.update(&cache);
+            cache.evict()
+        };
+        self.rebuild_bundles(&evicted);
+    }
+
+    /// Throw away the locale data that Fluent has cached for every language, including the
+    /// messages produced by a `MissingMessageResolver`. The data is built again the next time it
+    /// is needed, and no resources are parsed again.
+    pub fn clear_intl_cache(&self) {
+        self.intl.lock().recovered().recent.clear();
+        let languages: Vec<LanguageIdentifier> =
+            self.bundles.read().recovered().keys().cloned().collect();
+        self.rebuild_bundles(&languages);
+        let mut synthetic = self.synthetic.write().recovered();
+        let functions = self.functions.read().recovered();
+        for (lang, entry) in synthetic.iter_mut() {
+            entry.rebuild(lang.clone(), &functions);
+        }
+    }
+
+    /// Do the work that the first `tr` call in `lang` would otherwise do on top of formatting:
+    /// load any lazy or provided resources, build the plural rules, and format one message from
+    /// each resource. Call this during startup, or on a background thread, to keep that cost out
+    /// of the first frame of a UI.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_lazy_from_text(en_id.clone(), String::from("hello = Hello"))
+    ///     .unwrap();
+    /// fluent.warm_up(&en_id).unwrap();
+    /// ```
+    ///
+    /// A language with no resources is not an error; there is simply nothing to warm up. Layers
+    /// beneath this translator are warmed up as well.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from loading a lazy resource, as for `tr`
+    ///
+    pub fn warm_up(&self, lang: &LanguageIdentifier) -> Result<(), Error> {
+        let lang = canonicalize_language(lang);
+        self.load_lazy(std::slice::from_ref(&lang))?;
+        let plural = FluentResource::try_new(String::from(PLURAL_WARM_UP))
+            .unwrap_or_else(|(resource, _)| resource);
+        let plural = plural.entries().find_map(entry_value);
+        let mut args = FluentArgs::new();
+        args.set("count", 1);
+
+        let warmed = {
+            let bundles = self.bundles.read().or_poisoned()?;
+            let layers = self
+                .layers
+                .iter()
+                .map(|layer| layer.bundles.read().or_poisoned())
+                .collect::<Result<Vec<_>, _>>()?;
+            let entries = std::iter::once(&*bundles)
+                .chain(layers.iter().map(|layer| &**layer))
+                .filter_map(|bundles| bundles.get(&lang));
+            let mut warmed = false;
+            for entry in entries {
+                let entry = entry.read().or_poisoned()?;
+                let mut errors = Vec::new();
+                let representative = entry
+                    .resources
+                    .iter()
+                    .filter_map(|(_, resource)| resource.entries().find_map(entry_value));
+                for pattern in plural.into_iter().chain(representative) {
+                    K::format_pattern(&entry.bundle, pattern, Some(&args), &mut errors);
+                }
+                warmed = true;
+            }
+            warmed
+        };
+        if warmed {
+            self.touch_intl(&lang);
+        }
+        Ok(())
+    }
+
+    /// Record that a message was just formatted in `lang`, clear the caches of any languages
+    /// which that pushes over the limit, and unload idle languages. This must not be called with
+    /// any bundle locked.
+    pub(crate) fn touch_intl(&self, lang: &LanguageIdentifier) {
+        if !self.intl.active.load(Ordering::Acquire) {
+            return;
+        }
+        let (evicted, sweep) = {
+            let mut cache = self.intl.lock().recovered();
+            let sweep = cache.idle.as_mut().is_some_and(|idle| idle.touch(lang));
+            if cache.limit.is_none() || cache.recent.back() == Some(lang) {
+                (Vec::new(), sweep)
+            } else {
+                cache.recent.retain(|recent| recent != lang);
+                cache.recent.push_back(lang.clone());
+                (cache.evict(), sweep)
+            }
+        };
+        self.rebuild_bundles(&evicted);
+        if sweep {
+            self.unload_idle_languages();
+        }
+    }
+
+    fn rebuild_bundles(&self, languages: &[LanguageIdentifier]) {
+        let bundles = self.bundles.read().recovered();
+        for lang in languages {
+            if let Some(entry) = bundles.get(lang) {
+                let mut entry = entry.write().recovered();
+                entry.rebuild(lang.clone(), &self.functions.read().recovered());
+            }
+        }
+    }
+}
+
+impl IntlCache {
+    /// Forget the least recently used languages beyond the limit, and return them.
+    fn evict(&mut self) -> Vec<LanguageIdentifier> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Vec::new(),
+        };
+        let excess = self.recent.len().saturating_sub(limit);
+        self.recent.drain(..excess).collect()
+    }
+}
+
+/// The value of an entry, if it is a message which has one.
+fn entry_value<'r>(entry: &'r ast::Entry<&'r str>) -> Option<&'r ast::Pattern<&'r str>> {
+    match entry {
+        ast::Entry::Message(msg) => msg.value.as_ref(),
+        _ => None,
+    }
+}
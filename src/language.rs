@@ -0,0 +1,107 @@
+use crate::{Error, LanguageListProblem};
+use unic_langid::LanguageIdentifier;
+
+/// Deprecated language subtags and their replacements, from the CLDR language aliases. The
+/// replacement may add a script.
+const LANGUAGE_ALIASES: &[(&str, &str, Option<&str>)] = &[
+    ("in", "id", None),
+    ("iw", "he", None),
+    ("ji", "yi", None),
+    ("jw", "jv", None),
+    ("mo", "ro", None),
+    ("no", "nb", None),
+    ("sh", "sr", Some("Latn")),
+];
+
+/// Put a language identifier into the form the translator uses for every lookup. Parsing a
+/// `LanguageIdentifier` already normalizes case and accepts `_` as a separator, so `en_us` and
+/// `en-US` are the same identifier; this also replaces deprecated codes, so that a bundle
+/// registered as `iw` is found when asking for `he`.
+///
+/// ```
+/// use fluent_ergonomics::canonicalize_language;
+///
+/// let iw = "iw-IL".parse().unwrap();
+/// assert_eq!(canonicalize_language(&iw).to_string(), "he-IL");
+/// ```
+pub fn canonicalize_language(lang: &LanguageIdentifier) -> LanguageIdentifier {
+    let mut lang = lang.clone();
+    if let Some((_, replacement, script)) = LANGUAGE_ALIASES
+        .iter()
+        .find(|(deprecated, _, _)| *deprecated == lang.language.as_str())
+    {
+        lang.language = replacement
+            .parse()
+            .expect("alias replacements are valid language subtags");
+        if let (Some(script), None) = (script, lang.script) {
+            lang.script = Some(
+                script
+                    .parse()
+                    .expect("alias replacements are valid script subtags"),
+            );
+        }
+    }
+    lang
+}
+
+/// `languages`, with the more general forms of each language after it: `zh-Hant-TW` is followed by
+/// `zh-Hant` and `zh`. A language which is already in the list earlier keeps its place.
+pub(crate) fn with_parents(languages: &[LanguageIdentifier]) -> Vec<LanguageIdentifier> {
+    let mut chain: Vec<LanguageIdentifier> = Vec::new();
+    for lang in languages {
+        let mut lang = lang.clone();
+        loop {
+            if !chain.contains(&lang) {
+                chain.push(lang.clone());
+            }
+            if lang.variants().len() > 0 {
+                lang.clear_variants();
+            } else if lang.region.is_some() {
+                lang.region = None;
+            } else if lang.script.is_some() {
+                lang.script = None;
+            } else {
+                break;
+            }
+        }
+    }
+    chain
+}
+
+/// Parse a language tag into a canonical identifier.
+pub(crate) fn parse_language(tag: &str) -> Result<LanguageIdentifier, Error> {
+    tag.parse::<LanguageIdentifier>()
+        .map(|lang| canonicalize_language(&lang))
+        .map_err(|_| Error::InvalidLanguage(String::from(tag)))
+}
+
+/// Check a fallback list for `FluentErgo::try_new`. With `deduplicate`, deprecated codes are
+/// replaced and repeated languages are dropped, keeping the first of each, instead of being
+/// rejected.
+pub(crate) fn check_languages(
+    languages: &[LanguageIdentifier],
+    deduplicate: bool,
+) -> Result<Vec<LanguageIdentifier>, Error> {
+    if languages.is_empty() {
+        return Err(Error::InvalidLanguageList(LanguageListProblem::Empty));
+    }
+    let mut checked: Vec<LanguageIdentifier> = Vec::with_capacity(languages.len());
+    for lang in languages {
+        let canonical = canonicalize_language(lang);
+        if !deduplicate && canonical != *lang {
+            return Err(Error::InvalidLanguageList(
+                LanguageListProblem::NotCanonical(lang.clone()),
+            ));
+        }
+        if checked.contains(&canonical) {
+            if deduplicate {
+                continue;
+            }
+            return Err(Error::InvalidLanguageList(LanguageListProblem::Duplicate(
+                canonical,
+            )));
+        }
+        checked.push(canonical);
+    }
+    Ok(checked)
+}
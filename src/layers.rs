@@ -0,0 +1,85 @@
+use crate::{BundleKind, BundleMap, FluentErgo, MissingCache};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Another translator's bundles, searched after this translator's own.
+#[derive(Clone)]
+pub(crate) struct Layer<K: BundleKind> {
+    name: String,
+    pub(crate) bundles: Arc<RwLock<BundleMap<K>>>,
+    generation: Arc<AtomicU64>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Create a translator which searches this translator's resources first, and then those of
+    /// `catalog`. This stacks catalogs such as a base product, a white-label brand, and one
+    /// tenant's customizations, so that each tenant gets a view without copying the catalogs
+    /// beneath it:
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut product = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// product
+    ///     .add_from_text(en_id.clone(), String::from("title = Acme\nsave = Save"))
+    ///     .unwrap();
+    ///
+    /// let mut tenant = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// tenant
+    ///     .add_from_text(en_id, String::from("title = Globex"))
+    ///     .unwrap();
+    /// let tenant = tenant.with_layer("product", &product);
+    ///
+    /// assert_eq!(tenant.tr("title", None).unwrap(), "Globex");
+    /// assert_eq!(tenant.tr("save", None).unwrap(), "Save");
+    /// assert_eq!(tenant.layer_names(), vec!["product"]);
+    /// ```
+    ///
+    /// The layers share their bundles with the translators they came from, so resources added to
+    /// `catalog` later are seen by every view on top of it. Any layers of `catalog` itself come
+    /// along behind it. Precedence applies within each language of the fallback chain: a tenant's
+    /// English customization does not hide the product's German translation from German users.
+    ///
+    /// The catalogs' own fallback chains, overrides, and settings are ignored; the view uses this
+    /// translator's. A catalog which is already part of the view is not added again. A
+    /// translator with layers cannot be frozen.
+    pub fn with_layer(&self, name: &str, catalog: &FluentErgo<K>) -> FluentErgo<K> {
+        let mut view = FluentErgo {
+            missing: Arc::new(RwLock::new(MissingCache::default())),
+            ..self.clone()
+        };
+        let layer = Layer {
+            name: String::from(name),
+            bundles: Arc::clone(&catalog.bundles),
+            generation: Arc::clone(&catalog.generation),
+        };
+        for layer in std::iter::once(layer).chain(catalog.layers.iter().cloned()) {
+            let present = Arc::ptr_eq(&layer.bundles, &view.bundles)
+                || view
+                    .layers
+                    .iter()
+                    .any(|existing| Arc::ptr_eq(&existing.bundles, &layer.bundles));
+            if !present {
+                Arc::make_mut(&mut view.layers).push(layer);
+            }
+        }
+        view
+    }
+
+    /// The names of the layers beneath this translator, in the order they are searched.
+    pub fn layer_names(&self) -> Vec<&str> {
+        self.layers
+            .iter()
+            .map(|layer| layer.name.as_str())
+            .collect()
+    }
+
+    /// The generation of this translator's resources together with those of its layers, so that
+    /// adding a resource to any layer invalidates the missing message cache.
+    pub(crate) fn current_generation(&self) -> u64 {
+        self.layers
+            .iter()
+            .fold(self.generation.load(Ordering::SeqCst), |sum, layer| {
+                sum.wrapping_add(layer.generation.load(Ordering::SeqCst))
+            })
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{BundleKind, FluentErgo};
+use fluent_syntax::{ast, parser};
+use std::sync::Arc;
+
+/// The parameter that a term's old-style variant list selects on, once it has been rewritten.
+const VARIANT_PARAMETER: &str = "variant";
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Rewrite syntax from Fluent 0.x as resources are loaded, so that old catalogs can be used
+    /// without editing them by hand. Only the parts of a resource which do not parse are
+    /// touched. This understands:
+    ///
+    /// * Section headers, `[[ name ]]`, which become group comments, `## name`
+    /// * Terms with a variant list, `-brand = { *[nominative] … }`, which become a select on a
+    ///   `$variant` parameter
+    /// * References to a term's variant, `{ -brand[genitive] }`, which become
+    ///   `{ -brand(variant: "genitive") }`
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.set_legacy_syntax(true);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from(
+    ///             "[[ Branding ]]\n\
+    ///              -brand = {\n    *[nominative] Firefox\n     [genitive] Firefox's\n}\n\
+    ///              about = About { -brand[genitive] } features",
+    ///         ),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(fluent.tr("about", None).unwrap(), "About Firefox's features");
+    /// ```
+    ///
+    /// This is off by default. It only applies to text added after it has been enabled.
+    pub fn set_legacy_syntax(&mut self, enabled: bool) {
+        Arc::make_mut(&mut self.options).legacy_syntax = enabled;
+    }
+}
+
+/// Rewrite the old syntax in every junk entry of `text`.
+pub(crate) fn upgrade_legacy_syntax(text: String) -> String {
+    let junk: Vec<(usize, usize)> = match parser::parse_runtime(text.as_str()) {
+        Ok(_) => return text,
+        Err((resource, _)) => resource
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::Entry::Junk { content } => {
+                    let start = content.as_ptr() as usize - text.as_ptr() as usize;
+                    Some((start, start + content.len()))
+                }
+                _ => None,
+            })
+            .collect(),
+    };
+    let mut upgraded = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, end) in junk {
+        upgraded.push_str(&text[copied..start]);
+        upgrade_junk(&text[start..end], &mut upgraded);
+        copied = end;
+    }
+    upgraded.push_str(&text[copied..]);
+    upgraded
+}
+
+fn upgrade_junk(junk: &str, out: &mut String) {
+    let mut rest = junk;
+    while !rest.is_empty() {
+        let line_len = rest.find('\n').map_or(rest.len(), |end| end + 1);
+        let line = &rest[..line_len];
+        if let Some(name) = section_name(line) {
+            out.push_str("## ");
+            out.push_str(name);
+            out.push_str(&line[line.trim_end().len()..]);
+            rest = &rest[line_len..];
+            continue;
+        }
+        let value_start = variant_list_start(rest).unwrap_or(0);
+        out.push_str(&rewrite_variant_references(&rest[..value_start]));
+        if value_start > 0 {
+            out.push_str(" $");
+            out.push_str(VARIANT_PARAMETER);
+            out.push_str(" ->");
+        }
+        let rest_of_line = rest[value_start..]
+            .find('\n')
+            .map_or(rest.len(), |end| value_start + end + 1);
+        out.push_str(&rewrite_variant_references(
+            &rest[value_start..rest_of_line],
+        ));
+        rest = &rest[rest_of_line..];
+    }
+}
+
+/// The name in a `[[ name ]]` line.
+fn section_name(line: &str) -> Option<&str> {
+    let name = line
+        .trim_end()
+        .strip_prefix("[[")?
+        .strip_suffix("]]")?
+        .trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// If `text` starts with a term whose value is an old-style variant list, the offset just past
+/// its opening brace.
+fn variant_list_start(text: &str) -> Option<usize> {
+    let after_id = text
+        .strip_prefix('-')?
+        .trim_start_matches(is_identifier_char);
+    let value = after_id
+        .trim_start_matches([' ', '\t'])
+        .strip_prefix('=')?
+        .trim_start();
+    let after_brace = value.strip_prefix('{')?;
+    let first = after_brace.trim_start();
+    if first.starts_with('[') || first.starts_with("*[") {
+        Some(text.len() - after_brace.len())
+    } else {
+        None
+    }
+}
+
+/// Rewrite every `{ -term[key]` in `text` as `{ -term(variant: "key")`.
+fn rewrite_variant_references(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('-') {
+        let (before, reference) = rest.split_at(index);
+        out.push_str(before);
+        let after_brace = out.trim_end().ends_with('{');
+        match variant_reference(reference) {
+            Some((id, key, len)) if after_brace => {
+                out.push('-');
+                out.push_str(id);
+                out.push_str(&format!("({}: \"{}\")", VARIANT_PARAMETER, key));
+                rest = &reference[len..];
+            }
+            _ => {
+                out.push('-');
+                rest = &reference[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split `-term[key]` at the start of `text` into the term ID, the key, and its whole length.
+fn variant_reference(text: &str) -> Option<(&str, &str, usize)> {
+    let after_dash = text.strip_prefix('-')?;
+    if !after_dash.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let id_len = after_dash.len() - after_dash.trim_start_matches(is_identifier_char).len();
+    let (id, after_id) = after_dash.split_at(id_len);
+    let inside = after_id.strip_prefix('[')?;
+    let end = inside.find(']')?;
+    let key = inside[..end].trim();
+    if key.is_empty() || !key.chars().all(|c| is_identifier_char(c) || c == '.') {
+        return None;
+    }
+    Some((id, key, 1 + id_len + 1 + end + 1))
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
@@ -0,0 +1,125 @@
+use crate::{canonicalize_language, BundleEntry, BundleKind, FluentErgo, PoisonExt, TrOptions};
+use fluent::FluentArgs;
+use fluent_syntax::ast;
+use std::collections::{BTreeMap, BTreeSet};
+use unic_langid::LanguageIdentifier;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How long one translation is compared to the reference language, from
+/// `FluentErgo::length_report`. Lengths are counted in user-perceived characters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageLength {
+    pub lang: LanguageIdentifier,
+    pub id: String,
+    /// The length of the message in the reference language
+    pub reference_len: usize,
+    /// The length of the message in this language
+    pub len: usize,
+    /// `len` divided by `reference_len`
+    pub ratio: f64,
+    /// Whether `ratio` is more than the limit given to `length_report`
+    pub too_long: bool,
+}
+
+impl<K: BundleKind> BundleEntry<K> {
+    /// The IDs of every message with a value.
+    pub(crate) fn message_ids(&self) -> BTreeSet<&str> {
+        self.resources
+            .iter()
+            .flat_map(|(_, resource)| resource.entries())
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(msg) if msg.value.is_some() => Some(msg.id.name),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Format every message of `reference` and of each other loaded language with the same
+    /// `samples` arguments, and compare their lengths, so that strings which will overflow a
+    /// button in German or Finnish can be found before anyone looks at the screen. Translations
+    /// which are more than `max_ratio` times as long as the reference are marked `too_long`; a
+    /// common limit for short labels is `1.5`.
+    ///
+    /// The result is sorted by language and then by message ID. Messages which fail to format,
+    /// or which are empty in the reference language, are left out. Lazy resources only count once
+    /// they have been loaded.
+    ///
+    /// ```
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("save = Save settings"))
+    ///     .unwrap();
+    /// fluent
+    ///     .add_from_text(de_id, String::from("save = Einstellungen speichern"))
+    ///     .unwrap();
+    ///
+    /// let report = fluent.length_report(&en_id, None, 1.5);
+    /// assert_eq!((report[0].reference_len, report[0].len), (13, 23));
+    /// assert!(report[0].too_long);
+    /// ```
+    pub fn length_report(
+        &self,
+        reference: &LanguageIdentifier,
+        samples: Option<&FluentArgs>,
+        max_ratio: f64,
+    ) -> Vec<MessageLength> {
+        let reference = canonicalize_language(reference);
+        let bundles = self.bundles.read().recovered();
+        let reference_entry = match bundles.get(&reference) {
+            Some(entry) => entry.read().recovered(),
+            None => return Vec::new(),
+        };
+        let mut languages: Vec<&LanguageIdentifier> =
+            bundles.keys().filter(|lang| **lang != reference).collect();
+        languages.sort_by_key(|lang| lang.to_string());
+
+        let opts = TrOptions::default();
+        let length_of = |entry: &BundleEntry<K>, lang: &LanguageIdentifier, msgid: &str| {
+            let mut out = String::new();
+            match self.write_entry(entry, lang, msgid, samples, &opts, &mut out) {
+                Ok(true) => {
+                    out.retain(|c| c != '\u{2068}' && c != '\u{2069}');
+                    Some(out.graphemes(true).count())
+                }
+                _ => None,
+            }
+        };
+
+        let reference_lens: BTreeMap<&str, usize> = reference_entry
+            .message_ids()
+            .into_iter()
+            .filter_map(|id| match length_of(&reference_entry, &reference, id) {
+                Some(len) if len > 0 => Some((id, len)),
+                _ => None,
+            })
+            .collect();
+        let mut report = Vec::new();
+        for lang in languages {
+            let entry = bundles[lang].read().recovered();
+            for id in entry.message_ids() {
+                let reference_len = match reference_lens.get(id) {
+                    Some(len) => *len,
+                    None => continue,
+                };
+                let len = match length_of(&entry, lang, id) {
+                    Some(len) => len,
+                    None => continue,
+                };
+                let ratio = len as f64 / reference_len as f64;
+                report.push(MessageLength {
+                    lang: lang.clone(),
+                    id: String::from(id),
+                    reference_len,
+                    len,
+                    ratio,
+                    too_long: ratio > max_ratio,
+                });
+            }
+        }
+        report
+    }
+}
@@ -3,107 +3,438 @@
 //! The Fluent class makes it easier to load translation bundles with language fallbacks and to go
 //! through the most common steps of translating a message.
 //!
-use fluent::concurrent::FluentBundle;
+//! Resources are parsed when they are added, or when their language is first needed. Fluent can
+//! only build a resource by parsing its source text, so there is no faster form that a parsed
+//! catalog could be saved in and loaded back from on the next launch. An application which cares
+//! about startup time should register its resources with `add_lazy_from_file` or a
+//! `ResourceProvider`, so that only the languages it uses are parsed, and can do that work on a
+//! background thread with `warm_up`.
+//!
 use fluent::{FluentArgs, FluentError, FluentResource};
-use fluent_syntax::parser::ParserError;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
-use std::error;
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+use fluent_syntax::ast;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::fs::File;
-use std::io;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+#[cfg(all(feature = "fs", not(feature = "mmap")))]
 use std::io::Read;
+#[cfg(feature = "fs")]
 use std::path::Path;
-use std::string::FromUtf8Error;
-use std::sync::{Arc, RwLock};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::SystemTime;
 use unic_langid::LanguageIdentifier;
 
-#[derive(Debug)]
-pub enum Error {
-    /// All files must be UTF-8 encoded.
-    FileEncodingError(FromUtf8Error),
-    /// Fluent encountered an underlying error
-    FluentError(Vec<FluentError>),
-    /// Fluent encountered an underlying error while parsing the translation strings
-    FluentParserError(Vec<ParserError>),
-    /// There was an underlying IO error
-    IOError(io::Error),
-    /// No message could be found matching the specified message ID
-    NoMatchingMessage(String),
-}
-
-impl error::Error for Error {
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match self {
-            Error::FileEncodingError(error) => Some(error),
-            Error::NoMatchingMessage(_) => None,
-            Error::FluentParserError(_) => None,
-            Error::FluentError(_) => None,
-            Error::IOError(error) => Some(error),
+// The tracing macros have to be defined before the modules which use them.
+#[macro_use]
+mod trace;
+
+mod annotate;
+#[cfg(feature = "archive")]
+mod archive;
+mod bundle;
+#[cfg(feature = "fs")]
+mod chunked;
+#[cfg(feature = "icu")]
+mod collation;
+#[cfg(feature = "config")]
+mod config;
+mod conflicts;
+mod context;
+mod delta;
+mod diagnostics;
+mod direction;
+#[cfg(feature = "icu")]
+mod display_name;
+mod duration;
+mod editing;
+mod error;
+mod explain;
+mod extract;
+mod features;
+mod fingerprint;
+mod format;
+mod functions;
+mod futures;
+mod gender;
+mod history;
+mod intl;
+mod language;
+mod layers;
+mod legacy;
+mod length;
+mod limits;
+mod lint;
+mod literal;
+mod memory;
+mod number;
+mod observer;
+mod origin;
+#[cfg(feature = "http")]
+mod ota;
+mod overrides;
+mod placeholders;
+mod poison;
+pub mod prelude;
+mod provider;
+mod reload;
+mod resolver;
+mod review;
+mod stats;
+mod subset;
+mod suggest;
+mod terms;
+mod text;
+mod unload;
+mod value;
+mod version;
+mod weighted;
+pub use annotate::{AnnotatedTranslation, ArgumentSpan};
+pub use bundle::{BundleKind, Concurrent, SingleThreaded};
+pub use conflicts::{DuplicateMessages, MessageConflict};
+pub use context::context_id;
+pub use delta::LanguageDelta;
+pub use diagnostics::{Diagnostics, LanguageDiagnostics, ResourceOrigin};
+pub use direction::{is_rtl, TextDirection};
+#[cfg(feature = "icu")]
+pub use display_name::language_display_name;
+pub use editing::EditingSession;
+pub use error::{Error, LanguageListProblem, MissingMessage};
+pub use explain::{Explanation, Selection};
+#[cfg(feature = "fs")]
+pub use extract::extract_from_dir;
+pub use extract::{extract_message_ids, KeyReport};
+use features::Features;
+use format::{nfc, write_message};
+pub use format::{Isolation, MissingArguments, OnFormatError, TrOptions};
+pub use functions::DomainFormatter;
+use functions::Function;
+pub use gender::{Gender, GenderGap};
+use history::History;
+use intl::Intl;
+pub use language::canonicalize_language;
+use language::{check_languages, parse_language, with_parents};
+use layers::Layer;
+use legacy::upgrade_legacy_syntax;
+pub use length::MessageLength;
+use limits::OverLimit;
+pub use limits::{EvaluationLimits, Limit};
+use lint::LintCheck;
+pub use lint::{Lint, LintKind};
+use literal::{write_literal, Literals};
+pub use memory::LanguageMemory;
+pub use number::{Percent, Permille};
+use observer::Observer;
+pub use origin::Origin;
+#[cfg(feature = "http")]
+pub use ota::{OtaClient, OtaHandle};
+use overrides::OverrideEntry;
+pub use placeholders::PlaceholderMismatch;
+use poison::PoisonExt;
+use provider::Provider;
+pub use provider::ResourceProvider;
+pub use reload::CatalogDiff;
+use reload::ReloadValidator;
+pub use resolver::{MissingMessageResolver, Resolution};
+pub use stats::{BundleStats, Stats};
+#[cfg(feature = "fs")]
+pub use subset::subset_dir;
+pub use subset::subset_resource;
+pub use suggest::Suggestion;
+pub use text::{to_sentence_case, to_title_case, Case};
+pub use value::{FluentArgsExt, IntoFluentValue, LocalizedValue};
+use version::StalenessCheck;
+pub use version::{CatalogVersion, StaleCatalog};
+pub use weighted::WeightedTranslation;
+
+/// A language's bundle, along with what the translator knows about the resources in it.
+/// Resources are reference counted so that `merge` can add them to another translator's bundles
+/// without parsing them again.
+struct BundleEntry<K: BundleKind> {
+    bundle: K::Bundle,
+    /// Content hashes of every resource added to the bundle, so that adding the same text twice
+    /// is harmless.
+    hashes: HashSet<u64>,
+    /// Every resource in the bundle, in the order it was added, and its hash.
+    resources: Vec<(u64, Arc<FluentResource>)>,
+    /// Where the definition that the bundle uses for each message and term is, as the positions
+    /// of its resource and of the entry in that resource, so that finding it does not search
+    /// every resource.
+    definitions: HashMap<String, (usize, usize)>,
+    /// Hashes of the resources whose messages replaced existing ones, so that the bundle can be
+    /// rebuilt exactly.
+    overriding: HashSet<u64>,
+    /// The newest modification time of the files in the bundle.
+    modified: Option<SystemTime>,
+    /// When a resource was last added to the bundle, where the platform has a clock.
+    loaded_at: Option<SystemTime>,
+    literals: Literals,
+    /// Where each resource came from, by hash, so that an unloaded language can read its files
+    /// again.
+    origins: HashMap<u64, Origin>,
+}
+
+impl<K: BundleKind> BundleEntry<K> {
+    fn new(lang: LanguageIdentifier, functions: &[(String, Function)]) -> BundleEntry<K> {
+        BundleEntry {
+            bundle: K::new_bundle(lang, functions),
+            hashes: HashSet::new(),
+            resources: Vec::new(),
+            definitions: HashMap::new(),
+            overriding: HashSet::new(),
+            modified: None,
+            loaded_at: now(),
+            literals: Literals::new(),
+            origins: HashMap::new(),
+        }
+    }
+
+    /// Add a resource unless the bundle already has it. With `replace`, messages in the resource
+    /// replace existing messages with the same ID instead of being reported as errors.
+    fn add(
+        &mut self,
+        hash: u64,
+        resource: Arc<FluentResource>,
+        replace: bool,
+    ) -> Result<(), Vec<FluentError>> {
+        if !self.hashes.insert(hash) {
+            return Ok(());
+        }
+        self.resources.push((hash, Arc::clone(&resource)));
+        self.loaded_at = now();
+        if replace {
+            self.overriding.insert(hash);
+        }
+        self.record_literals(&resource, replace);
+        self.index_definitions(&resource, replace);
+        K::add_resource(&mut self.bundle, resource, replace)
+    }
+
+    /// Rebuild the bundle from its resources, which throws away everything Fluent has cached in
+    /// it. The resources themselves are shared, not parsed again.
+    fn rebuild(&mut self, lang: LanguageIdentifier, functions: &[(String, Function)]) {
+        self.bundle = self.build_bundle(lang, functions);
+    }
+
+    /// A copy of this entry which shares its resources but has a bundle of its own, so that
+    /// adding resources to either one leaves the other as it was.
+    fn snapshot(&self, lang: LanguageIdentifier, functions: &[(String, Function)]) -> Self {
+        BundleEntry {
+            bundle: self.build_bundle(lang, functions),
+            hashes: self.hashes.clone(),
+            resources: self.resources.clone(),
+            definitions: self.definitions.clone(),
+            overriding: self.overriding.clone(),
+            modified: self.modified,
+            loaded_at: self.loaded_at,
+            literals: self.literals.clone(),
+            origins: self.origins.clone(),
+        }
+    }
+
+    fn build_bundle(
+        &self,
+        lang: LanguageIdentifier,
+        functions: &[(String, Function)],
+    ) -> K::Bundle {
+        let mut bundle = K::new_bundle(lang, functions);
+        for (hash, resource) in &self.resources {
+            let replace = self.overriding.contains(hash);
+            // These resources were already added once, so any errors were already reported.
+            let _ = K::add_resource(&mut bundle, Arc::clone(resource), replace);
         }
+        bundle
     }
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Error::FileEncodingError(error) => {
-                write!(f, "Translation file has an encoding problem: {}", error)
+/// The current time.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> Option<SystemTime> {
+    Some(SystemTime::now())
+}
+
+/// `SystemTime::now` panics on `wasm32-unknown-unknown`, which has no clock.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn now() -> Option<SystemTime> {
+    None
+}
+
+/// Each language's bundle, behind its own lock.
+type BundleMap<K> = HashMap<LanguageIdentifier, Arc<RwLock<BundleEntry<K>>>>;
+
+/// A resource which has been parsed, and the hash of the text it was parsed from.
+struct ParsedResource {
+    hash: u64,
+    resource: FluentResource,
+    path: Option<PathBuf>,
+    /// When the file was last modified, for resources which came from files.
+    modified: Option<SystemTime>,
+    origin: Origin,
+}
+
+/// Message IDs which are known to be missing from every bundle in one fallback chain. Every
+/// resource change bumps the shared generation counter, and the cache only answers for the
+/// generation its contents were recorded in. A lookup records its result against the generation
+/// it started in, so a result that raced with a resource change is simply never used.
+///
+/// The description of the missing message is only built, and then cached, the first time a
+/// caller asks for an error about it. `tr_opt` never needs one.
+#[derive(Default)]
+struct MissingCache {
+    generation: u64,
+    ids: HashMap<String, Option<MissingMessage>>,
+}
+
+impl MissingCache {
+    fn get(&self, generation: u64, msgid: &str) -> Option<&Option<MissingMessage>> {
+        if self.generation == generation {
+            self.ids.get(msgid)
+        } else {
+            None
+        }
+    }
+
+    fn record(&mut self, generation: u64, msgid: &str, description: Option<MissingMessage>) {
+        if generation > self.generation {
+            self.generation = generation;
+            self.ids.clear();
+        }
+        if generation == self.generation {
+            let entry = self.ids.entry(String::from(msgid)).or_insert(None);
+            if description.is_some() {
+                *entry = description;
             }
-            Error::FluentError(errs) => write!(f, "Fluent Error: {:?}", errs),
-            Error::FluentParserError(errs) => write!(f, "Fluent Parser Error: {:?}", errs),
-            Error::IOError(error) => write!(f, "IO Error: {}", error),
-            Error::NoMatchingMessage(id) => write!(f, "No matching message for {}", id),
         }
     }
 }
 
-impl From<(FluentResource, Vec<ParserError>)> for Error {
-    fn from(inp: (FluentResource, Vec<ParserError>)) -> Self {
-        let (_, error) = inp;
-        Error::FluentParserError(error)
-    }
+/// A resource which has been registered but not yet parsed.
+#[derive(Clone, Debug)]
+enum UnparsedResource {
+    Text(String),
+    #[cfg(feature = "fs")]
+    File(PathBuf),
 }
 
-impl From<Vec<ParserError>> for Error {
-    fn from(error: Vec<ParserError>) -> Self {
-        Error::FluentParserError(error)
+impl UnparsedResource {
+    fn parse(self, options: &Options, lang: &LanguageIdentifier) -> Result<ParsedResource, Error> {
+        match self {
+            UnparsedResource::Text(text) => parse_text(options, text, None),
+            #[cfg(feature = "fs")]
+            UnparsedResource::File(path) => {
+                let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                read_file(&path)
+                    .and_then(|text| parse_text(options, text, Some(path)))
+                    .map(|res| ParsedResource { modified, ..res })
+            }
+        }
+        .map_err(|err| err.in_language(lang))
     }
 }
 
-impl From<Vec<FluentError>> for Error {
-    fn from(error: Vec<FluentError>) -> Self {
-        Error::FluentError(error)
-    }
+type LazyMap = HashMap<LanguageIdentifier, Vec<UnparsedResource>>;
+
+/// The resources which are parsed the first time their language is needed, behind a flag which
+/// lets `tr` skip the lock once there are none left.
+#[derive(Default)]
+struct Lazy {
+    pending: AtomicBool,
+    resources: Mutex<LazyMap>,
 }
 
-impl From<io::Error> for Error {
-    fn from(error: io::Error) -> Self {
-        Error::IOError(error)
+impl Lazy {
+    fn lock(&self) -> LockResult<MutexGuard<'_, LazyMap>> {
+        self.resources.lock()
     }
-}
 
-impl From<FromUtf8Error> for Error {
-    fn from(error: FromUtf8Error) -> Self {
-        Error::FileEncodingError(error)
+    /// Bring the flag up to date after `lazy` has had resources added or removed.
+    fn update(&self, lazy: &LazyMap) {
+        self.pending.store(!lazy.is_empty(), Ordering::Release);
     }
 }
 
-#[derive(Clone, Default)]
-pub struct FluentErgo {
-    languages: Vec<LanguageIdentifier>,
-    bundles: Arc<RwLock<HashMap<LanguageIdentifier, FluentBundle<FluentResource>>>>,
+/// What to do when resources are added for a language which is not in the translator's language
+/// list.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnknownLanguages {
+    /// Load the resources anyway. `tr` will not search them, but translators created with
+    /// `with_languages` can, and `resolve` will consider them.
+    #[default]
+    Load,
+    /// Load the resources, and add the language to the end of the fallback chain.
+    Append,
+    /// Refuse to load the resources, and return `UnregisteredLanguage`.
+    Reject,
 }
 
-impl fmt::Debug for FluentErgo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "FluentErgo")
-        //write!(
-        //f,
-        //"FluentErgo {{ language: {:?}, units: {} }}",
-        //self.language, "whatever, for the moment"
-        //)
-    }
+/// What `merge` does when both translators define a message with the same ID in the same
+/// language.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergeConflicts {
+    /// Keep this translator's message.
+    Keep,
+    /// Use the merged translator's message.
+    Replace,
+}
+
+/// Settings which control how resources are loaded and messages are formatted. These are copied,
+/// not shared, between clones of the translator, although the copy is only made when a clone
+/// changes a setting.
+#[derive(Clone, Debug, Default)]
+struct Options {
+    normalize_nfc: bool,
+    unknown_languages: UnknownLanguages,
+    /// Values for arguments that callers leave out, by message and argument name.
+    default_args: HashMap<String, Vec<(String, String)>>,
+    missing_arguments: MissingArguments,
+    validate_terms: bool,
+    duplicate_messages: DuplicateMessages,
+    legacy_syntax: bool,
+    observer: Option<Arc<Observer>>,
+    limits: EvaluationLimits,
+    regional_fallback: bool,
+    isolation: Isolation,
+    reload_validator: Option<Arc<ReloadValidator>>,
+}
+
+#[derive(Clone, Default)]
+pub struct FluentErgo<K: BundleKind = Concurrent> {
+    languages: Vec<LanguageIdentifier>,
+    /// Each bundle has its own lock, so adding a resource to one language does not block
+    /// translations in any other language. The outer lock is only taken for writing when a
+    /// language gets its first resource.
+    bundles: Arc<RwLock<BundleMap<K>>>,
+    /// Bumped whenever any resource is added. Shared by every translator using these bundles.
+    generation: Arc<AtomicU64>,
+    /// Clones with the same fallback chain share this cache; `with_languages` starts a new one.
+    missing: Arc<RwLock<MissingCache>>,
+    lazy: Arc<Lazy>,
+    /// Resources of application features and environments, from `add_feature_text` and
+    /// `add_environment_text`. Locked before the bundles.
+    features: Arc<Mutex<Features>>,
+    /// The catalogs that reloads replaced, for `rollback`. Locked after the bundles.
+    history: Arc<Mutex<History<K>>>,
+    resolver: Option<Arc<dyn MissingMessageResolver>>,
+    /// Locked before the lazy resources.
+    provider: Option<Arc<Provider>>,
+    staleness: Option<Arc<StalenessCheck>>,
+    lints: Option<Arc<LintCheck>>,
+    /// Messages from the resolver, kept apart from the real resources.
+    synthetic: Arc<RwLock<HashMap<LanguageIdentifier, BundleEntry<K>>>>,
+    intl: Arc<Intl>,
+    /// Messages replaced at runtime with `set_override`. Locked after the bundles.
+    overrides: Arc<RwLock<HashMap<LanguageIdentifier, OverrideEntry<K>>>>,
+    /// Functions registered with `add_function`, which every new bundle gets. Locked after the
+    /// bundles, the overrides, and the synthetic bundles.
+    functions: Arc<RwLock<Vec<(String, Function)>>>,
+    /// Other translators' bundles from `with_layer`, locked after the overrides in the order they
+    /// are searched.
+    layers: Arc<Vec<Layer<K>>>,
+    options: Arc<Options>,
 }
 
 /// An Ergonomic class wrapping the Fluent library
@@ -126,243 +457,5305 @@ impl FluentErgo {
     ///
     /// Note that no language resources are loaded during construction. You must call
     /// `add_from_text` or `add_from_file` to load language packs.
+    ///
+    /// Every language identifier given to the translator, here or when adding resources, is
+    /// passed through `canonicalize_language`, so deprecated codes such as `iw` will still find
+    /// bundles registered under their modern replacements.
     pub fn new(languages: &[LanguageIdentifier]) -> FluentErgo {
-        FluentErgo {
-            languages: Vec::from(languages),
-            bundles: Arc::new(RwLock::new(HashMap::new())),
-        }
+        FluentErgo::new_with_kind(languages)
     }
 
-    /// Add a list of translation strings from a string, which can be a constant hard-coded in the
-    /// application, loaded from a file, loaded from the internet, or wherever you like. `lang`
-    /// specifies which language the translation strings being provided.
+    /// Like `new`, but reject a list of languages which would not be searched as written: an
+    /// empty list, a language listed twice, or a deprecated code, which `new` would quietly
+    /// replace.
     ///
-    /// You should not specify a language that you did not include in the constructor. You can, but
-    /// the translation function will only check those languages specified when this object was
-    /// constructed.
+    /// ```
+    /// use fluent_ergonomics::{Error, FluentErgo, LanguageListProblem};
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// match FluentErgo::try_new(&[en_id.clone(), en_id]) {
+    ///     Err(Error::InvalidLanguageList(LanguageListProblem::Duplicate(lang))) => {
+    ///         assert_eq!(lang.to_string(), "en-US")
+    ///     }
+    ///     _ => panic!("expected a duplicate language"),
+    /// }
+    /// ```
     ///
     /// # Errors
     ///
-    /// * `FluentError`
-    /// * `FluentParserError`
+    /// * `Error::InvalidLanguageList` with the first problem in the list
     ///
-    pub fn add_from_text(&mut self, lang: LanguageIdentifier, text: String) -> Result<(), Error> {
-        let res = FluentResource::try_new(text)?;
-        let mut bundles = self.bundles.write().unwrap();
-        let entry = bundles.entry(lang.clone());
-        match entry {
-            Entry::Occupied(mut e) => {
-                let bundle = e.get_mut();
-                bundle.add_resource(res).map_err(|err| Error::from(err))
-            }
-            Entry::Vacant(e) => {
-                let mut bundle = FluentBundle::new(&[lang]);
-                bundle.add_resource(res).map_err(|err| Error::from(err))?;
-                e.insert(bundle);
-                Ok(())
-            }
-        }?;
-        Ok(())
+    pub fn try_new(languages: &[LanguageIdentifier]) -> Result<FluentErgo, Error> {
+        check_languages(languages, false).map(|languages| FluentErgo::new(&languages))
     }
 
-    /// Like `add_from_text`, but this will load the translation strings from a file.
-    ///
-    /// Note that this will load the entire file into memory before passing it to Fluent. While I
-    /// think it is unlikely, it is possible that a translation file may be so big as to run the
-    /// computer out of memory.
+    /// Like `try_new`, but replace deprecated codes and drop repeated languages, keeping the
+    /// first of each so that the fallback order is preserved. Only an empty list is an error.
     ///
     /// # Errors
     ///
-    /// * `FluentError`
-    /// * `FluentParserError`
-    /// * `FileEncodingError` -- all files must be encoded in UTF-8. Most files saved from text
-    /// editors already do proper UTF-8 encoding, so this should rarely be a problem.
+    /// * `Error::InvalidLanguageList` if there are no languages
     ///
-    pub fn add_from_file(&mut self, lang: LanguageIdentifier, path: &Path) -> Result<(), Error> {
-        let mut v = Vec::new();
-        let mut f = File::open(path)?;
-        f.read_to_end(&mut v)?;
-        String::from_utf8(v)
-            .map_err(Error::FileEncodingError)
-            .and_then(|s| self.add_from_text(lang, s))
+    pub fn try_new_deduplicated(languages: &[LanguageIdentifier]) -> Result<FluentErgo, Error> {
+        check_languages(languages, true).map(|languages| FluentErgo::new(&languages))
     }
 
-    /// Run a translation.
-    ///
-    /// `msgid` is the translation identifier as specified in the translation strings. `args` is a
-    /// set of Fluent arguments to be interpolated into the strings.
+    /// Like `new`, but parse the languages from their tags.
     ///
-    /// This function will search language bundles in the order that they were specified in the
-    /// constructor. NoMatchingMessage will be returned only if the message identifier cannot be
-    /// found in any bundle.
-    ///
-    /// ```ignore
-    /// length-without-label = {$value}
-    /// swimming = Swimming
-    /// units = Units
+    /// ```
+    /// let fluent = fluent_ergonomics::FluentErgo::from_tags(&["eo", "en-US"]).unwrap();
+    /// assert!(fluent_ergonomics::FluentErgo::from_tags(&["en-US", "not a tag"]).is_err());
     /// ```
     ///
-    /// With this set of translation strings, `length-without-label`, `swimming`, and `units` are
-    /// all valid translation identifiers. See the documentation for `FluentBundle.get_message` for
-    /// more information.
+    /// # Errors
     ///
-    /// A typical call with arguments would look like this:
+    /// * `Error::InvalidLanguage` with the first tag which is not a language identifier
+    ///
+    pub fn from_tags(tags: &[&str]) -> Result<FluentErgo, Error> {
+        let languages = tags
+            .iter()
+            .map(|tag| parse_language(tag))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(FluentErgo::new(&languages))
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Like `new`, but the translator keeps its messages in the given kind of bundle. See
+    /// `BundleKind`.
+    pub fn new_with_kind(languages: &[LanguageIdentifier]) -> FluentErgo<K> {
+        FluentErgo {
+            languages: languages.iter().map(canonicalize_language).collect(),
+            bundles: Arc::new(RwLock::new(HashMap::new())),
+            generation: Arc::new(AtomicU64::new(0)),
+            missing: Arc::new(RwLock::new(MissingCache::default())),
+            lazy: Arc::new(Lazy::default()),
+            features: Arc::new(Mutex::new(Features::default())),
+            history: Arc::new(Mutex::new(History::default())),
+            resolver: None,
+            provider: None,
+            staleness: None,
+            lints: None,
+            synthetic: Arc::new(RwLock::new(HashMap::new())),
+            intl: Arc::new(Intl::default()),
+            overrides: Arc::new(RwLock::new(HashMap::new())),
+            functions: Arc::new(RwLock::new(Vec::new())),
+            layers: Arc::new(Vec::new()),
+            options: Arc::new(Options::default()),
+        }
+    }
+
+    /// Build a translator from pairs of a language and the Fluent source of one of its
+    /// resources, such as an embedded map or the rows of a database query. The languages are
+    /// searched in the order that they first appear, and the resources are added as `add_many`
+    /// would add them.
     ///
     /// ```
-    /// use fluent::{FluentArgs, FluentValue};
+    /// use fluent_ergonomics::FluentErgo;
     ///
     /// let eo_id = "eo".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let fluent: FluentErgo = FluentErgo::try_from_iter(vec![
+    ///     (eo_id, String::from("hello = Saluton")),
+    ///     (en_id, String::from("hello = Hello\nbye = Goodbye")),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(fluent.tr("hello", None).unwrap(), "Saluton");
+    /// assert_eq!(fluent.tr("bye", None).unwrap(), "Goodbye");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `add_many` can return
+    ///
+    pub fn try_from_iter<I>(resources: I) -> Result<FluentErgo<K>, Error>
+    where
+        I: IntoIterator<Item = (LanguageIdentifier, String)>,
+    {
+        let resources: Vec<(LanguageIdentifier, String)> = resources.into_iter().collect();
+        let mut languages: Vec<LanguageIdentifier> = Vec::new();
+        for (lang, _) in &resources {
+            let lang = canonicalize_language(lang);
+            if !languages.contains(&lang) {
+                languages.push(lang);
+            }
+        }
+        let mut fluent = FluentErgo::new_with_kind(&languages);
+        fluent.add_many(resources)?;
+        Ok(fluent)
+    }
+
+    /// Enable or disable Unicode NFC normalization. When enabled, translation text is normalized
+    /// as it is added, and string arguments are normalized before they are interpolated into a
+    /// message.
+    ///
+    /// This is useful when translation files come from tooling (macOS in particular) which saves
+    /// text in decomposed form. Without normalization, a decomposed `é` in a translation will
+    /// never compare equal to a precomposed `é` from elsewhere in the application.
+    ///
+    /// Normalization is off by default. It only applies to text added after it has been enabled.
+    pub fn set_nfc_normalization(&mut self, enabled: bool) {
+        Arc::make_mut(&mut self.options).normalize_nfc = enabled;
+    }
+
+    /// Give an argument of a message a value to use when the caller leaves it out, instead of
+    /// showing `{$name}`. Values which look like numbers are passed as numbers, so that they can
+    /// select plural forms.
+    ///
+    /// Translations can also give defaults themselves, in `default-<name>` attributes of the
+    /// message, and those take precedence over defaults set here because they are in the right
+    /// language:
+    ///
+    /// ```
     /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from("greeting = Hello, { $name }!\n    .default-name = friend"),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(fluent.tr("greeting", None).unwrap(), "Hello, friend!");
+    /// ```
+    ///
+    /// Defaults are copied, not shared, between clones of the translator.
+    pub fn set_default_argument(&mut self, msgid: &str, name: &str, value: &str) {
+        let defaults = Arc::make_mut(&mut self.options)
+            .default_args
+            .entry(String::from(msgid))
+            .or_default();
+        defaults.retain(|(existing, _)| existing != name);
+        defaults.push((String::from(name), String::from(value)));
+    }
+
+    /// Negotiate a fallback chain from the languages a user asked for (for instance, from an
+    /// `Accept-Language` header or the operating system's settings) and the languages which have
+    /// resources. Matching understands likely subtags, so a request for `sr-RS` will find a bundle
+    /// registered as `sr-Cyrl`, and `en` will find `en-US`.
+    ///
+    /// The result is in order of the user's preference, and only contains languages that have
+    /// resources, including lazy resources which have not been parsed yet.
     ///
-    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[eo_id, en_id]);
-    /// let mut args = FluentArgs::new();
-    /// args.insert("value", FluentValue::from("15"));
-    /// let r = fluent.tr("length-without-label", Some(&args));
     /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let eo_id = "eo".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id.clone(), String::from("units = Units")).unwrap();
+    /// fluent.add_lazy_from_text(eo_id.clone(), String::from("units = Unuoj")).unwrap();
     ///
-    /// # Errors
+    /// let requested = ["eo-US".parse().unwrap(), "en".parse().unwrap()];
+    /// let user = fluent.with_languages(&fluent.resolve(&requested));
+    /// assert_eq!(user.tr("units", None).unwrap(), "Unuoj");
+    /// ```
+    pub fn resolve(&self, requested: &[LanguageIdentifier]) -> Vec<LanguageIdentifier> {
+        // Negotiation breaks ties by the order of `available`, so this relies on it being sorted.
+        let available = self.loaded_languages();
+        let requested: Vec<LanguageIdentifier> =
+            requested.iter().map(canonicalize_language).collect();
+        negotiate_languages(&requested, &available, None, NegotiationStrategy::Filtering)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// The languages that `tr` searches, in order.
+    pub fn configured_languages(&self) -> &[LanguageIdentifier] {
+        &self.languages
+    }
+
+    /// Every language that has resources, including lazy resources which have not been parsed
+    /// yet, whether or not `tr` searches it. The list is sorted by language tag.
+    pub fn loaded_languages(&self) -> Vec<LanguageIdentifier> {
+        let mut loaded: Vec<LanguageIdentifier> =
+            self.bundles.read().recovered().keys().cloned().collect();
+        for lang in self.lazy.lock().recovered().keys() {
+            if !loaded.contains(lang) {
+                loaded.push(lang.clone());
+            }
+        }
+        loaded.sort_by_key(|lang| lang.to_string());
+        loaded
+    }
+
+    /// Check that every configured language has at least one resource. Startup code can use this
+    /// to catch a deployment which is missing some of its translation files, instead of finding
+    /// out when users quietly get the fallback language.
     ///
-    /// * NoMatchingMessage -- this will be returned if the message identifier cannot be found in
-    /// any language bundle.
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let eo_id = "eo".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
     ///
-    pub fn tr(&self, msgid: &str, args: Option<&FluentArgs>) -> Result<String, Error> {
-        let bundles = self.bundles.read().unwrap();
-        let result: Option<String> = self
-            .languages
+    /// let readiness = fluent.readiness();
+    /// assert!(!readiness.is_ready());
+    /// assert_eq!(readiness.missing, vec![eo_id]);
+    /// ```
+    pub fn readiness(&self) -> Readiness {
+        let loaded = self.loaded_languages();
+        Readiness {
+            missing: self
+                .languages
+                .iter()
+                .filter(|lang| !loaded.contains(lang))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The IDs of every message which has been added for `lang`, sorted. Terms are not included,
+    /// and neither are lazy resources which have not been loaded yet.
+    pub fn message_ids(&self, lang: &LanguageIdentifier) -> Vec<String> {
+        let entry = match self
+            .bundles
+            .read()
+            .recovered()
+            .get(&canonicalize_language(lang))
+        {
+            Some(entry) => Arc::clone(entry),
+            None => return Vec::new(),
+        };
+        let entry = entry.read().recovered();
+        let mut ids: Vec<String> = entry
+            .resources
             .iter()
-            .map(|lang| {
-                let bundle = bundles.get(lang)?;
-                self.tr_(bundle, msgid, args)
+            .flat_map(|(_, resource)| resource.entries())
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => Some(String::from(message.id.name)),
+                _ => None,
             })
-            .filter(|v| v.is_some())
-            .map(|v| v.unwrap())
-            .next();
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
 
-        match result {
-            Some(r) => Ok(r),
-            _ => Err(Error::NoMatchingMessage(String::from(msgid))),
+    /// Compare the messages of `lang` against those of `base`, usually the language that the
+    /// application is written in, to find what the translators still have to do.
+    pub fn diff_languages(
+        &self,
+        base: &LanguageIdentifier,
+        lang: &LanguageIdentifier,
+    ) -> LanguageDiff {
+        let base_ids = self.message_ids(base);
+        let ids = self.message_ids(lang);
+        LanguageDiff {
+            missing: base_ids
+                .iter()
+                .filter(|id| ids.binary_search(id).is_err())
+                .cloned()
+                .collect(),
+            extra: ids
+                .iter()
+                .filter(|id| base_ids.binary_search(id).is_err())
+                .cloned()
+                .collect(),
+            translated: ids.len(),
+            total: base_ids.len(),
         }
     }
 
-    fn tr_(
-        &self,
-        bundle: &FluentBundle<FluentResource>,
-        msgid: &str,
-        args: Option<&FluentArgs>,
-    ) -> Option<String> {
-        let mut errors = vec![];
-        let pattern = bundle.get_message(msgid).and_then(|msg| msg.value);
-        let res = match pattern {
-            None => None,
-            Some(p) => {
-                let res = bundle.format_pattern(&p, args, &mut errors);
-                if errors.len() > 0 {
-                    println!("Errors in formatting: {:?}", errors)
-                }
+    /// Create a translator which shares all of this translator's resources, but searches
+    /// `languages` instead. This is cheap, and is meant for servers which load every language once
+    /// and then translate each request in the language that the request asked for.
+    pub fn with_languages(&self, languages: &[LanguageIdentifier]) -> FluentErgo<K> {
+        let mut languages: Vec<LanguageIdentifier> =
+            languages.iter().map(canonicalize_language).collect();
+        if self.options.regional_fallback {
+            languages = with_parents(&languages);
+        }
+        FluentErgo {
+            languages,
+            missing: Arc::new(RwLock::new(MissingCache::default())),
+            ..self.clone()
+        }
+    }
 
-                Some(String::from(res))
-            }
-        };
-        match res {
-            Some(mut tr_string) => {
-                tr_string.retain(|v| v != '\u{2068}' && v != '\u{2069}');
-                Some(tr_string)
+    /// Search the more general forms of each language in the fallback chain before moving on to
+    /// the next language, so that a team can keep one shared `pt` catalog and small `pt-BR` and
+    /// `pt-PT` catalogs with only the messages that differ. `pt-BR` is followed by `pt`, and
+    /// `zh-Hant-TW` by `zh-Hant` and then `zh`. A message in both is taken from the regional
+    /// catalog.
+    ///
+    /// ```
+    /// let pt_id = "pt".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let pt_br_id = "pt-BR".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[pt_br_id.clone(), en_id.clone()]);
+    /// fluent.set_regional_fallback(true);
+    /// fluent
+    ///     .add_from_text(pt_id, String::from("save = Salvar\nscreen = Ecrã"))
+    ///     .unwrap();
+    /// fluent.add_from_text(pt_br_id, String::from("screen = Tela")).unwrap();
+    /// fluent.add_from_text(en_id, String::from("quit = Quit")).unwrap();
+    ///
+    /// assert_eq!(fluent.tr("screen", None).unwrap(), "Tela");
+    /// assert_eq!(fluent.tr("save", None).unwrap(), "Salvar");
+    /// assert_eq!(fluent.tr("quit", None).unwrap(), "Quit");
+    /// ```
+    ///
+    /// This is off by default. Turning it on adds the general forms to this translator's chain,
+    /// and to the chains of translators made from it with `with_languages`; turning it off again
+    /// does not take them out of a chain they are already in.
+    pub fn set_regional_fallback(&mut self, enabled: bool) {
+        Arc::make_mut(&mut self.options).regional_fallback = enabled;
+        if enabled {
+            let languages = with_parents(&self.languages);
+            if languages != self.languages {
+                self.languages = languages;
+                self.missing = Arc::new(RwLock::new(MissingCache::default()));
             }
-            None => None,
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::FluentErgo;
-    use fluent::{FluentArgs, FluentValue};
-    use unic_langid::LanguageIdentifier;
+    /// Choose what happens when resources are added for a language that was not included in the
+    /// constructor. By default they are loaded but never searched by `tr`, which is usually a
+    /// mistake in the language list; `UnknownLanguages::Reject` turns that mistake into an error.
+    ///
+    /// `UnknownLanguages::Append` only changes the language list of this translator, not of its
+    /// existing clones.
+    pub fn set_unknown_languages(&mut self, policy: UnknownLanguages) {
+        Arc::make_mut(&mut self.options).unknown_languages = policy;
+    }
 
-    const EN_TRANSLATIONS: &'static str = "
-preferences = Preferences
-history = History
-time_display = {$time} during the day
-nested_display = nesting a time display: {time_display}
-";
+    /// Choose how arguments which a message uses, but the caller did not give and no default
+    /// covers, are rendered. By default Fluent's `{$name}` placeholder is shown.
+    ///
+    /// ```
+    /// use fluent_ergonomics::MissingArguments;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("greeting = Hello, { $name }!")).unwrap();
+    /// fluent.set_missing_arguments(MissingArguments::Marker(String::from("…")));
+    /// assert_eq!(fluent.tr("greeting", None).unwrap(), "Hello, …!");
+    /// ```
+    pub fn set_missing_arguments(&mut self, policy: MissingArguments) {
+        Arc::make_mut(&mut self.options).missing_arguments = policy;
+    }
 
-    const EO_TRANSLATIONS: &'static str = "
-history = Historio
-";
+    /// Choose which parts of a translation are wrapped in Unicode isolation marks. By default
+    /// every mark is removed, which breaks up text that mixes directions, such as an Arabic user
+    /// name in an English sentence; `Isolation::Arguments` wraps only the argument.
+    ///
+    /// ```
+    /// use fluent_ergonomics::Isolation;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id, String::from("greeting = Hello, { $name }!"))
+    ///     .unwrap();
+    /// let mut args = fluent::FluentArgs::new();
+    /// args.set("name", "زينب");
+    ///
+    /// fluent.set_isolation(Isolation::Arguments);
+    /// assert_eq!(
+    ///     fluent.tr("greeting", Some(&args)).unwrap(),
+    ///     "Hello, \u{2068}زينب\u{2069}!"
+    /// );
+    /// ```
+    pub fn set_isolation(&mut self, isolation: Isolation) {
+        Arc::make_mut(&mut self.options).isolation = isolation;
+    }
 
-    #[test]
-    fn translations() {
-        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
-        let mut fluent = FluentErgo::new(&vec![en_id.clone()]);
-        fluent
-            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
-            .expect("text should load");
-        assert_eq!(
-            fluent.tr("preferences", None).unwrap(),
-            String::from("Preferences")
-        );
+    /// Apply the unknown language policy to languages which resources are about to be added for.
+    /// Either every language is accepted or none are.
+    fn accept_languages<'a, I>(&mut self, langs: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a LanguageIdentifier>,
+    {
+        let mut unknown: Vec<LanguageIdentifier> = Vec::new();
+        for lang in langs {
+            let lang = canonicalize_language(lang);
+            if !self.languages.contains(&lang) && !unknown.contains(&lang) {
+                unknown.push(lang);
+            }
+        }
+        match self.options.unknown_languages {
+            UnknownLanguages::Load => Ok(()),
+            UnknownLanguages::Append => {
+                self.languages.extend(unknown);
+                if self.options.regional_fallback {
+                    self.languages = with_parents(&self.languages);
+                }
+                Ok(())
+            }
+            UnknownLanguages::Reject => match unknown.into_iter().next() {
+                Some(lang) => Err(Error::UnregisteredLanguage(lang)),
+                None => Ok(()),
+            },
+        }
     }
 
-    #[test]
-    fn translation_fallback() {
-        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
-        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
-        let mut fluent = FluentErgo::new(&vec![eo_id.clone(), en_id.clone()]);
-        fluent
-            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
-            .expect("text should load");
-        fluent
-            .add_from_text(eo_id, String::from(EO_TRANSLATIONS))
-            .expect("text should load");
-        assert_eq!(
-            fluent.tr("preferences", None).unwrap(),
-            String::from("Preferences")
-        );
-        assert_eq!(
-            fluent.tr("history", None).unwrap(),
-            String::from("Historio")
-        );
+    /// Add a list of translation strings from a string, which can be a constant hard-coded in the
+    /// application, loaded from a file, loaded from the internet, or wherever you like. `lang`
+    /// specifies which language the translation strings being provided.
+    ///
+    /// You should not specify a language that you did not include in the constructor. You can, but
+    /// the translation function will only check those languages specified when this object was
+    /// constructed, unless `set_unknown_languages` says otherwise.
+    ///
+    /// Adding exactly the same text for the same language more than once does nothing, so
+    /// initialization code can safely run more than once.
+    ///
+    /// # Errors
+    ///
+    /// * `DuplicateMessages`
+    /// * `FluentParserError`
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    ///
+    pub fn add_from_text(&mut self, lang: LanguageIdentifier, text: String) -> Result<(), Error> {
+        self.accept_languages(std::slice::from_ref(&lang))?;
+        self.add_text(lang, text)
     }
 
-    #[test]
-    fn placeholder_insertion_should_strip_placeholder_markers() {
-        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
-        let mut fluent = FluentErgo::new(&vec![en_id.clone()]);
-        fluent
-            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
-            .expect("text should load");
-        let mut args = FluentArgs::new();
-        args.insert("time", FluentValue::from(String::from("13:00")));
-        assert_eq!(
-            fluent.tr("time_display", Some(&args)).unwrap(),
-            String::from("13:00 during the day")
-        );
+    fn add_text(&self, lang: LanguageIdentifier, text: String) -> Result<(), Error> {
+        let res = UnparsedResource::Text(text).parse(&self.options, &lang)?;
+        self.add_parsed(vec![(lang, res)])
     }
 
-    #[test]
+    /// Add a set of translation strings for many languages at once. On multi-core machines with
+    /// the `parallel` feature enabled, the resources are parsed in parallel. This is much faster
+    /// than calling `add_from_text` repeatedly for a large catalog.
+    ///
+    /// Every resource is parsed before any of them are added, so if any resource fails to parse,
+    /// none of them will be added.
+    ///
+    /// # Errors
+    ///
+    /// * `DuplicateMessages`
+    /// * `FluentParserError` -- if several resources fail to parse, only the first error is
+    ///   returned.
+    ///
+    pub fn add_many<I>(&mut self, resources: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (LanguageIdentifier, String)>,
+    {
+        let resources = resources
+            .into_iter()
+            .map(|(lang, text)| (lang, UnparsedResource::Text(text)))
+            .collect();
+        self.add_unparsed(resources)
+    }
+
+    /// Load a whole directory of translation files. Each subdirectory of `path` must be named
+    /// for a language, and every `.ftl` file inside of it will be loaded for that language. Other
+    /// files are ignored.
+    ///
+    /// ```ignore
+    /// locales/
+    ///     en-US/
+    ///         main.ftl
+    ///         errors.ftl
+    ///     eo/
+    ///         main.ftl
+    /// ```
+    ///
+    /// Files are read and parsed in the same way as `add_many`.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidLanguage` -- a subdirectory name is not a valid language identifier
+    /// * All of the errors that `add_from_file` can return
+    ///
+    #[cfg(feature = "fs")]
+    pub fn add_from_dir(&mut self, path: &Path) -> Result<(), Error> {
+        let mut resources = Vec::new();
+        for entry in path.read_dir()? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let lang = parse_language(&name)?;
+            let mut files = Vec::new();
+            for file in entry.path().read_dir()? {
+                let file = file?.path();
+                if file.is_file() && file.extension().is_some_and(|ext| ext == "ftl") {
+                    files.push(file);
+                }
+            }
+            files.sort();
+            resources.extend(
+                files
+                    .into_iter()
+                    .map(|file| (lang.clone(), UnparsedResource::File(file))),
+            );
+        }
+        self.add_unparsed(resources)
+    }
+
+    /// Parse a batch of resources in the way `add_many` describes, and add them.
+    fn add_unparsed(
+        &mut self,
+        resources: Vec<(LanguageIdentifier, UnparsedResource)>,
+    ) -> Result<(), Error> {
+        self.accept_languages(resources.iter().map(|(lang, _)| lang))?;
+        let parsed = parse_all(&self.options, resources)?;
+        self.add_parsed(parsed)
+    }
+
+    /// Add the translations that a library ships through its `Localizations` implementation.
+    /// This is the same as passing them to `add_many`, so the application's language list
+    /// decides which of them are searched.
+    ///
+    /// # Errors
+    ///
+    /// All of the errors that `add_many` can return.
+    ///
+    pub fn install(&mut self, localizations: &dyn Localizations) -> Result<(), Error> {
+        self.add_many(localizations.resources())
+    }
+
+    /// Add parsed resources to their bundles, taking the bundle map's write lock only for
+    /// languages which get their first resource.
+    fn add_parsed(
+        &self,
+        resources: Vec<(LanguageIdentifier, ParsedResource)>,
+    ) -> Result<(), Error> {
+        self.add_parsed_under(resources, self.options.duplicate_messages)
+    }
+
+    /// Like `add_parsed`, but with `policy` for duplicate messages instead of the translator's.
+    fn add_parsed_under(
+        &self,
+        resources: Vec<(LanguageIdentifier, ParsedResource)>,
+        policy: DuplicateMessages,
+    ) -> Result<(), Error> {
+        trace_span!("add", resources = resources.len());
+        let (touched, result) = self.insert_parsed(resources, policy);
+        result.and_then(|()| self.validate_terms(&touched))
+    }
+
+    /// Like `add_parsed_under`, but without validating terms, so that the caller can validate
+    /// once after adding several batches. Returns the languages that were touched.
+    fn insert_parsed(
+        &self,
+        resources: Vec<(LanguageIdentifier, ParsedResource)>,
+        policy: DuplicateMessages,
+    ) -> (Vec<LanguageIdentifier>, Result<(), Error>) {
+        let mut result = Ok(());
+        let mut touched = Vec::new();
+        let mut added = Vec::new();
+        for (lang, res) in resources {
+            let lang = canonicalize_language(&lang);
+            let entry = match self.bundle_for(&lang) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+            let mut entry = match entry.write().or_poisoned() {
+                Ok(entry) => entry,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+            entry.modified = entry.modified.max(res.modified);
+            if !touched.contains(&lang) {
+                touched.push(lang.clone());
+            }
+            entry.origins.entry(res.hash).or_insert(res.origin);
+            if !entry.hashes.contains(&res.hash) {
+                added.push((lang.clone(), res.hash));
+            }
+            let checked = entry.add_checked(&lang, res.hash, res.resource, res.path, policy);
+            if result.is_ok() {
+                result = checked;
+            }
+        }
+        self.invalidate_missing();
+        self.check_staleness(&touched);
+        self.lint_added(&added);
+        (touched, result)
+    }
+
+    /// Find the bundle for a language, creating an empty one if this is the first resource for
+    /// the language. Only creating one takes the write lock on the bundle map.
+    fn bundle_for(&self, lang: &LanguageIdentifier) -> Result<Arc<RwLock<BundleEntry<K>>>, Error> {
+        if let Some(entry) = self.bundles.read().or_poisoned()?.get(lang) {
+            return Ok(Arc::clone(entry));
+        }
+        let mut bundles = self.bundles.write().or_poisoned()?;
+        let functions = self.functions.read().or_poisoned()?;
+        let entry = bundles
+            .entry(lang.clone())
+            .or_insert_with(|| Arc::new(RwLock::new(BundleEntry::new(lang.clone(), &functions))));
+        Ok(Arc::clone(entry))
+    }
+
+    /// Whether the bundle for `lang` consists of exactly the one resource with this hash.
+    #[cfg(feature = "http")]
+    fn has_exactly(&self, lang: &LanguageIdentifier, hash: u64) -> bool {
+        let bundles = self.bundles.read().recovered();
+        bundles.get(lang).is_some_and(|entry| {
+            let entry = entry.read().recovered();
+            entry.resources.len() == 1 && entry.resources[0].0 == hash
+        })
+    }
+
+    /// Replace every language's resources at once, for services which reload their whole catalog
+    /// while they are running. The new bundles are built off to the side, and swapped in only
+    /// once every resource has parsed, so translations never see a mix of the old and new
+    /// catalogs. If anything fails, the translator keeps the catalog it had.
+    ///
+    /// Languages which are not in `resources` are removed, along with every lazy resource which
+    /// has not been loaded yet.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id.clone(), String::from("units = Units")).unwrap();
+    ///
+    /// assert!(fluent.replace_all(vec![(en_id.clone(), String::from("units = {"))]).is_err());
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Units");
+    /// fluent.replace_all(vec![(en_id, String::from("units = Measures"))]).unwrap();
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Measures");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// All of the errors that `add_many` can return.
+    ///
+    pub fn replace_all<I>(&mut self, resources: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (LanguageIdentifier, String)>,
+    {
+        let resources: Vec<_> = resources
+            .into_iter()
+            .map(|(lang, text)| (lang, UnparsedResource::Text(text)))
+            .collect();
+        self.accept_languages(resources.iter().map(|(lang, _)| lang))?;
+        let parsed = parse_all(&self.options, resources)?;
+        self.swap_bundles(parsed, true)
+    }
+
+    /// Build bundles from `resources`, off to the side of the ones in use.
+    fn build_entries(
+        &self,
+        resources: Vec<(LanguageIdentifier, ParsedResource)>,
+    ) -> Result<HashMap<LanguageIdentifier, BundleEntry<K>>, Error> {
+        let mut entries: HashMap<LanguageIdentifier, BundleEntry<K>> = HashMap::new();
+        let functions = self.functions.read().or_poisoned()?.clone();
+        for (lang, res) in resources {
+            let lang = canonicalize_language(&lang);
+            let entry = entries
+                .entry(lang.clone())
+                .or_insert_with(|| BundleEntry::new(lang.clone(), &functions));
+            entry.modified = entry.modified.max(res.modified);
+            entry.origins.entry(res.hash).or_insert(res.origin);
+            entry.add_checked(
+                &lang,
+                res.hash,
+                res.resource,
+                res.path,
+                self.options.duplicate_messages,
+            )?;
+        }
+        Ok(entries)
+    }
+
+    /// Build new bundles from `resources`, and swap them in under one lock, so a translation sees
+    /// either every old bundle or every new one. If any resource cannot be added, nothing is
+    /// swapped, and neither is anything that the reload validator vetoes. With `everything`,
+    /// languages without resources are removed; otherwise only the languages in `resources` are
+    /// replaced. Lazy resources which have not been loaded yet for the replaced languages are
+    /// dropped.
+    fn swap_bundles(
+        &self,
+        resources: Vec<(LanguageIdentifier, ParsedResource)>,
+        everything: bool,
+    ) -> Result<(), Error> {
+        trace_span!("reload", resources = resources.len(), everything);
+        let entries = self.build_entries(resources)?;
+        self.validate_reload(&entries, everything)?;
+
+        let touched: Vec<LanguageIdentifier> = entries.keys().cloned().collect();
+        let added: Vec<(LanguageIdentifier, u64)> = entries
+            .iter()
+            .flat_map(|(lang, entry)| {
+                entry
+                    .resources
+                    .iter()
+                    .map(move |(hash, _)| (lang.clone(), *hash))
+            })
+            .collect();
+        let mut lazy = self.lazy.lock().or_poisoned()?;
+        let mut bundles = self.bundles.write().or_poisoned()?;
+        self.history
+            .lock()
+            .or_poisoned()?
+            .push(&bundles, &self.functions.read().or_poisoned()?)?;
+        if everything {
+            lazy.clear();
+            bundles.clear();
+        }
+        for (lang, entry) in entries {
+            lazy.remove(&lang);
+            bundles.insert(lang, Arc::new(RwLock::new(entry)));
+        }
+        self.lazy.update(&lazy);
+        drop(bundles);
+        drop(lazy);
+        self.invalidate_missing();
+        self.check_staleness(&touched);
+        self.lint_added(&added);
+        Ok(())
+    }
+
+    fn invalidate_missing(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Like `add_from_text`, but this will load the translation strings from a file.
+    ///
+    /// Note that this will load the entire file into memory before passing it to Fluent. While I
+    /// think it is unlikely, it is possible that a translation file may be so big as to run the
+    /// computer out of memory. `add_from_file_chunked` reads such a file a piece at a time.
+    ///
+    /// With the `mmap` feature enabled, the file is memory mapped and validated as UTF-8 in place,
+    /// so the only allocation is the single copy that Fluent takes ownership of.
+    ///
+    /// # Errors
+    ///
+    /// * `DuplicateMessages`
+    /// * `FluentParserError`
+    /// * `FileEncodingError` -- all files must be encoded in UTF-8. Most files saved from text
+    ///   editors already do proper UTF-8 encoding, so this should rarely be a problem.
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    ///
+    #[cfg(feature = "fs")]
+    pub fn add_from_file(&mut self, lang: LanguageIdentifier, path: &Path) -> Result<(), Error> {
+        self.accept_languages(std::slice::from_ref(&lang))?;
+        let res = UnparsedResource::File(path.to_path_buf()).parse(&self.options, &lang)?;
+        self.add_parsed(vec![(lang, res)])
+    }
+
+    /// Like `add_from_text`, but the translation strings are not parsed until the first time
+    /// `tr` is called for a language that the translator searches. Registering many languages
+    /// this way costs almost nothing at startup, and only the languages that are actually used
+    /// ever get parsed.
+    ///
+    /// Because parsing is deferred, any errors in the text will be returned from `tr` instead of
+    /// from this function. Each lazy resource is only attempted once, so the error will be
+    /// returned by the `tr` call which first needs the language. Call `load_lazy_resources` to
+    /// parse everything ahead of time and handle the errors there.
+    ///
+    /// # Errors
+    ///
+    /// * `UnregisteredLanguage` -- only with `UnknownLanguages::Reject`
+    ///
+    pub fn add_lazy_from_text(
+        &mut self,
+        lang: LanguageIdentifier,
+        text: String,
+    ) -> Result<(), Error> {
+        self.add_lazy(lang, UnparsedResource::Text(text))
+    }
+
+    /// Like `add_lazy_from_text`, but the translation strings will be loaded from a file. The
+    /// file is not opened until the language is first needed.
+    #[cfg(feature = "fs")]
+    pub fn add_lazy_from_file(
+        &mut self,
+        lang: LanguageIdentifier,
+        path: &Path,
+    ) -> Result<(), Error> {
+        self.add_lazy(lang, UnparsedResource::File(path.to_path_buf()))
+    }
+
+    fn add_lazy(
+        &mut self,
+        lang: LanguageIdentifier,
+        resource: UnparsedResource,
+    ) -> Result<(), Error> {
+        self.accept_languages(std::slice::from_ref(&lang))?;
+        let mut lazy = self.lazy.lock().or_poisoned()?;
+        lazy.entry(canonicalize_language(&lang))
+            .or_default()
+            .push(resource);
+        self.lazy.update(&lazy);
+        drop(lazy);
+        self.invalidate_missing();
+        Ok(())
+    }
+
+    /// Add every resource of `other` to this translator, so that an application made of
+    /// independently localized parts can assemble one translator. The languages that `other`
+    /// searches are added to the end of this translator's fallback chain, unless it already
+    /// searches them.
+    ///
+    /// The resources themselves are shared rather than parsed again, and resources that this
+    /// translator already has are skipped. Any lazy resources of `other` are loaded first.
+    ///
+    /// # Errors
+    ///
+    /// Any of the errors that `load_lazy_resources` might return for `other`. Nothing is merged
+    /// in that case.
+    ///
+    pub fn merge(&mut self, other: &FluentErgo<K>, conflicts: MergeConflicts) -> Result<(), Error> {
+        other.load_lazy_resources()?;
+        for lang in &other.languages {
+            if !self.languages.contains(lang) {
+                self.languages.push(lang.clone());
+            }
+        }
+        if Arc::ptr_eq(&self.bundles, &other.bundles) {
+            return Ok(());
+        }
+
+        // Collect first, so that the other translator's locks are never held at the same time as
+        // this one's.
+        let theirs: Vec<(LanguageIdentifier, Vec<_>, _, _)> = other
+            .bundles
+            .read()
+            .or_poisoned()?
+            .iter()
+            .map(|(lang, entry)| {
+                let entry = entry.read().or_poisoned()?;
+                Ok((
+                    lang.clone(),
+                    entry.resources.clone(),
+                    entry.modified,
+                    entry.origins.clone(),
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+        let touched: Vec<LanguageIdentifier> =
+            theirs.iter().map(|(lang, _, _, _)| lang.clone()).collect();
+        {
+            let mut bundles = self.bundles.write().or_poisoned()?;
+            let functions = self.functions.read().or_poisoned()?;
+            for (lang, resources, modified, origins) in theirs {
+                let entry = bundles
+                    .entry(lang.clone())
+                    .or_insert_with(|| Arc::new(RwLock::new(BundleEntry::new(lang, &functions))));
+                let mut entry = entry.write().or_poisoned()?;
+                entry.modified = entry.modified.max(modified);
+                for (hash, resource) in resources {
+                    if let Some(origin) = origins.get(&hash) {
+                        entry.origins.entry(hash).or_insert_with(|| origin.clone());
+                    }
+                    // The only errors Fluent reports here are the conflicts, which `Keep` ignores
+                    // on purpose.
+                    let _ = entry.add(hash, resource, conflicts == MergeConflicts::Replace);
+                }
+            }
+        }
+        self.invalidate_missing();
+        self.check_staleness(&touched);
+        Ok(())
+    }
+
+    /// Parse every resource registered with `add_lazy_from_text` or `add_lazy_from_file` that has
+    /// not been loaded yet, regardless of which languages the translator searches. The languages
+    /// which it searches are fetched from the `ResourceProvider`, if there is one, first.
+    ///
+    /// # Errors
+    ///
+    /// Any of the errors that `add_from_file` might return. Every resource is still attempted, but
+    /// only the first error is returned. Failed resources will not be attempted again.
+    ///
+    /// Any error from the `ResourceProvider`, in which case nothing is parsed.
+    pub fn load_lazy_resources(&self) -> Result<(), Error> {
+        self.fetch_provided(&self.languages)?;
+        let languages: Vec<LanguageIdentifier> =
+            self.lazy.lock().or_poisoned()?.keys().cloned().collect();
+        self.load_lazy(&languages)
+    }
+
+    /// Load any lazy resources for the specified languages, fetching them from the provider
+    /// first. The lock is held while parsing so that a concurrent `tr` cannot search a
+    /// half-loaded language.
+    fn load_lazy(&self, languages: &[LanguageIdentifier]) -> Result<(), Error> {
+        self.fetch_provided(languages)?;
+        if !self.lazy.pending.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let mut lazy = self.lazy.lock().or_poisoned()?;
+        let mut result = Ok(());
+        for lang in languages {
+            for resource in lazy.remove(lang).unwrap_or_default() {
+                trace_span!("load_lazy", lang = %lang);
+                let loaded = resource
+                    .parse(&self.options, lang)
+                    .and_then(|res| self.add_parsed(vec![(lang.clone(), res)]));
+                if result.is_ok() {
+                    result = loaded;
+                }
+            }
+        }
+        self.lazy.update(&lazy);
+        result
+    }
+
+    /// Run a translation.
+    ///
+    /// `msgid` is the translation identifier as specified in the translation strings. `args` is a
+    /// set of Fluent arguments to be interpolated into the strings.
+    ///
+    /// This function will search language bundles in the order that they were specified in the
+    /// constructor. NoMatchingMessage will be returned only if the message identifier cannot be
+    /// found in any bundle.
+    ///
+    /// ```ignore
+    /// length-without-label = {$value}
+    /// swimming = Swimming
+    /// units = Units
+    /// ```
+    ///
+    /// With this set of translation strings, `length-without-label`, `swimming`, and `units` are
+    /// all valid translation identifiers. See the documentation for `FluentBundle.get_message` for
+    /// more information.
+    ///
+    /// A typical call with arguments would look like this:
+    ///
+    /// ```
+    /// use fluent::{FluentArgs, FluentValue};
+    ///
+    /// let eo_id = "eo".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    ///
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[eo_id, en_id]);
+    /// let mut args = FluentArgs::new();
+    /// args.set("value", FluentValue::from("15"));
+    /// let r = fluent.tr("length-without-label", Some(&args));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * NoMatchingMessage -- this will be returned if the message identifier cannot be found in
+    ///   any language bundle.
+    /// * Any error from loading a lazy resource, if this is the first translation to need it. See
+    ///   `add_lazy_from_text`.
+    /// * `Poisoned` -- another thread panicked while it was changing this translator. Every method
+    ///   which can return an error returns this one from then on, and methods which cannot answer
+    ///   from whatever state is left.
+    ///
+    pub fn tr(&self, msgid: &str, args: Option<&FluentArgs>) -> Result<String, Error> {
+        self.tr_opts(msgid, args, &TrOptions::default())
+    }
+
+    /// Like `tr`, but `opts` controls what happens when the message is found but Fluent has
+    /// trouble formatting it, such as a missing variable or a cyclic reference. `tr` always uses
+    /// `OnFormatError::BestEffort`.
+    ///
+    /// ```
+    /// use fluent_ergonomics::{OnFormatError, TrOptions};
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("greeting = Hello, {$name}")).unwrap();
+    ///
+    /// let strict = TrOptions::on_any_error(OnFormatError::Fail);
+    /// assert!(fluent.tr_opts("greeting", None, &strict).is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    /// * FormatError -- the message could not be formatted, and `opts` asked for an error in that
+    ///   case.
+    ///
+    pub fn tr_opts(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+    ) -> Result<String, Error> {
+        match self.find(msgid, args, opts)? {
+            Some(r) => Ok(r),
+            None => Err(Error::NoMatchingMessage(self.describe_missing(msgid))),
+        }
+    }
+
+    /// Like `tr`, but appends the translation to `out` instead of returning a new `String`. A
+    /// render loop that clears and reuses one buffer stops allocating once the buffer has grown
+    /// to fit its longest message.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// let mut label = String::from("> ");
+    /// fluent.tr_into("units", None, &mut label).unwrap();
+    /// assert_eq!(label, "> Units");
+    /// ```
+    ///
+    /// `out` is left as it was if there is an error.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn tr_into(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        out: &mut String,
+    ) -> Result<(), Error> {
+        if self.find_into(msgid, args, &TrOptions::default(), out)? {
+            Ok(())
+        } else {
+            Err(Error::NoMatchingMessage(self.describe_missing(msgid)))
+        }
+    }
+
+    /// Like `tr`, but returns `None` instead of an error. This is meant for optional strings, such
+    /// as tooltips or experimental labels, where a missing message is expected and building an
+    /// error for it would be wasted work.
+    ///
+    /// Any other error, such as a lazy resource which fails to load, also produces `None`.
+    pub fn tr_opt(&self, msgid: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.find(msgid, args, &TrOptions::default()).ok().flatten()
+    }
+
+    /// Translate a message, or return `default` if no bundle has it. This replaces the common
+    /// `.unwrap_or_else(|_| "...".into())` at call sites, and benefits from the same caching of
+    /// missing messages as `tr_opt`.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let fluent = fluent_ergonomics::FluentErgo::new(&[en_id]);
+    /// assert_eq!(fluent.tr_or("units", None, "Units"), "Units");
+    /// ```
+    pub fn tr_or(&self, msgid: &str, args: Option<&FluentArgs>, default: &str) -> String {
+        self.tr_opt(msgid, args)
+            .unwrap_or_else(|| String::from(default))
+    }
+
+    /// Like `tr_or`, but the default is only computed if it is needed.
+    pub fn tr_or_else<F>(&self, msgid: &str, args: Option<&FluentArgs>, f: F) -> String
+    where
+        F: FnOnce() -> String,
+    {
+        self.tr_opt(msgid, args).unwrap_or_else(f)
+    }
+
+    /// Translate a message without arguments, or return the message identifier if no bundle has
+    /// it. This is the accessor for UI code, where a missing string is cosmetic: it never fails
+    /// and never panics, and an untranslated label shows up plainly as its identifier.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// assert_eq!(fluent.at("units"), "Units");
+    /// assert_eq!(fluent.at("swimming"), "swimming");
+    /// ```
+    ///
+    /// The translator does not implement `Index`, which would have to return a reference to text
+    /// that it formats on every call.
+    pub fn at(&self, msgid: &str) -> String {
+        self.tr_or(msgid, None, msgid)
+    }
+
+    /// Return a value which translates the message when it is displayed, so that translations
+    /// can be embedded directly in `format!` or `write!`. If the message cannot be found, the
+    /// message identifier is displayed instead.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// assert_eq!(format!("{}: km", fluent.display("units", None)), "Units: km");
+    /// assert_eq!(format!("{}", fluent.display("swimming", None)), "swimming");
+    /// ```
+    pub fn display<'a>(
+        &'a self,
+        msgid: &'a str,
+        args: Option<&'a FluentArgs<'a>>,
+    ) -> Translation<'a> {
+        Translation {
+            translator: self,
+            msgid,
+            args,
+        }
+    }
+
+    /// Search every language for a message, returning `None` if no bundle has it.
+    fn find(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+    ) -> Result<Option<String>, Error> {
+        let mut out = String::new();
+        let found = self.find_into(msgid, args, opts, &mut out)?;
+        Ok(if found { Some(out) } else { None })
+    }
+
+    fn find_into(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+        out: &mut String,
+    ) -> Result<bool, Error> {
+        trace_span!(
+            "tr",
+            msgid,
+            lang = tracing::field::Empty,
+            depth = tracing::field::Empty,
+            errors = tracing::field::Empty
+        );
+        if self.find_loaded(msgid, args, opts, out)? {
+            return Ok(true);
+        }
+        match self.resolve_missing(msgid, args, opts)? {
+            Some(result) => {
+                out.push_str(&result);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn find_loaded(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+        out: &mut String,
+    ) -> Result<bool, Error> {
+        let started = self.options.observe_start();
+        self.load_lazy(&self.languages)?;
+
+        let generation = self.current_generation();
+        if self
+            .missing
+            .read()
+            .or_poisoned()?
+            .get(generation, msgid)
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        let found = {
+            let bundles = self.bundles.read().or_poisoned()?;
+            let overrides = self.overrides.read().or_poisoned()?;
+            let layers = self
+                .layers
+                .iter()
+                .map(|layer| layer.bundles.read().or_poisoned())
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut found = None;
+            for lang in &self.languages {
+                if let Some(overridden) = overrides.get(lang) {
+                    if self.write_entry(&overridden.entry, lang, msgid, args, opts, out)? {
+                        found = Some(lang);
+                        break;
+                    }
+                }
+                let entries = std::iter::once(&*bundles)
+                    .chain(layers.iter().map(|layer| &**layer))
+                    .filter_map(|bundles| bundles.get(lang));
+                for entry in entries {
+                    if self.write_entry(
+                        &*entry.read().or_poisoned()?,
+                        lang,
+                        msgid,
+                        args,
+                        opts,
+                        out,
+                    )? {
+                        found = Some(lang);
+                        break;
+                    }
+                }
+                if found.is_some() {
+                    break;
+                }
+            }
+            found
+        };
+        if let Some(lang) = found {
+            trace_record!("lang", tracing::field::display(lang));
+            trace_record!(
+                "depth",
+                self.languages.iter().position(|l| l == lang).unwrap_or(0) as u64
+            );
+            self.touch_intl(lang);
+            self.options.observe(msgid, lang, started);
+            return Ok(true);
+        }
+
+        self.missing
+            .write()
+            .or_poisoned()?
+            .record(generation, msgid, None);
+        Ok(false)
+    }
+
+    /// Append a message from one entry to `out`, straight from its literals if it has no
+    /// placeables.
+    fn write_entry(
+        &self,
+        entry: &BundleEntry<K>,
+        lang: &LanguageIdentifier,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+        out: &mut String,
+    ) -> Result<bool, Error> {
+        self.options.limits.check_references(entry, msgid, lang)?;
+        if let Some(text) = entry.literals.get(msgid) {
+            write_literal(&self.options, opts, lang, msgid, text, out)?;
+            return Ok(true);
+        }
+        write_message::<K>(&self.options, opts, lang, &entry.bundle, msgid, args, out)
+    }
+
+    fn describe_missing(&self, msgid: &str) -> MissingMessage {
+        let generation = self.current_generation();
+        if let Some(Some(description)) = self.missing.read().recovered().get(generation, msgid) {
+            return description.clone();
+        }
+
+        let bundles = self.bundles.read().recovered();
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.bundles.read().recovered())
+            .collect::<Vec<_>>();
+        let description = MissingMessage::new(msgid, &self.languages, |lang| {
+            std::iter::once(&*bundles)
+                .chain(layers.iter().map(|layer| &**layer))
+                .filter_map(|bundles| bundles.get(lang))
+                .map(|entry| valueless::<K>(&entry.read().recovered().bundle, msgid))
+                .reduce(|any, valueless| any || valueless)
+        });
+        self.missing
+            .write()
+            .recovered()
+            .record(generation, msgid, Some(description.clone()));
+        description
+    }
+
+    /// Convert this translator into a `FrozenFluentErgo`, which can no longer have resources added
+    /// to it but which translates without taking any locks.
+    ///
+    /// This is intended for applications that load all of their translations at startup and never
+    /// change them afterwards. The frozen translator does not use a `MissingMessageResolver`.
+    ///
+    /// # Errors
+    ///
+    /// Clones of a `FluentErgo` share their bundles, so this can only succeed on the last
+    /// remaining clone. If any other clone is still alive, if there are lazy resources or
+    /// provided languages which have not been loaded yet, or if any messages are overridden or
+    /// layered, the translator is handed back unchanged. Call `load_lazy_resources` before
+    /// freezing if you have registered any.
+    // Freezing happens once, and boxing the translator that is handed back would only make the
+    // error harder to use.
+    #[allow(clippy::result_large_err)]
+    pub fn freeze(self) -> Result<FrozenFluentErgo<K>, FluentErgo<K>> {
+        let unfetched = self
+            .provider
+            .as_ref()
+            .is_some_and(|provider| provider.pending(&self.languages));
+        if unfetched
+            || !self.lazy.lock().recovered().is_empty()
+            || !self.overrides.read().recovered().is_empty()
+            || !self.layers.is_empty()
+        {
+            return Err(self);
+        }
+        let FluentErgo {
+            languages,
+            bundles,
+            generation,
+            missing,
+            lazy,
+            features,
+            history,
+            resolver,
+            provider,
+            staleness,
+            lints,
+            synthetic,
+            intl,
+            overrides,
+            functions,
+            layers,
+            options,
+        } = self;
+        let bundles = match Arc::try_unwrap(bundles) {
+            Ok(bundles) => bundles,
+            Err(bundles) => {
+                return Err(FluentErgo {
+                    languages,
+                    bundles,
+                    generation,
+                    missing,
+                    lazy,
+                    features,
+                    history,
+                    resolver,
+                    provider,
+                    staleness,
+                    lints,
+                    synthetic,
+                    intl,
+                    overrides,
+                    functions,
+                    layers,
+                    options,
+                })
+            }
+        };
+        // Nothing can roll a frozen translator back.
+        history.lock().recovered().clear();
+        drop(history);
+        let entries: Vec<(LanguageIdentifier, BundleEntry<K>)> = bundles
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .into_iter()
+            .map(|(lang, entry)| match Arc::try_unwrap(entry) {
+                Ok(entry) => (
+                    lang,
+                    entry.into_inner().unwrap_or_else(PoisonError::into_inner),
+                ),
+                Err(_) => unreachable!("bundles are never shared outside of the bundle map"),
+            })
+            .collect();
+        let over_limit = options
+            .limits
+            .over_limit(entries.iter().map(|(lang, entry)| (lang, entry)));
+        let (bundles, literals): (HashMap<_, _>, HashMap<_, _>) = entries
+            .into_iter()
+            .map(|(lang, entry)| ((lang.clone(), entry.bundle), (lang, entry.literals)))
+            .unzip();
+        Ok(FrozenFluentErgo {
+            languages,
+            bundles: Arc::new(bundles),
+            literals: Arc::new(literals),
+            over_limit: Arc::new(over_limit),
+            options,
+        })
+    }
+}
+
+/// A read-only translator, created with `FluentErgo::freeze`.
+///
+/// Since nothing can change after it has been frozen, translations happen without any locking.
+/// Clones are cheap and share the same bundles.
+#[derive(Clone)]
+pub struct FrozenFluentErgo<K: BundleKind = Concurrent> {
+    languages: Vec<LanguageIdentifier>,
+    bundles: Arc<HashMap<LanguageIdentifier, K::Bundle>>,
+    literals: Arc<HashMap<LanguageIdentifier, Literals>>,
+    over_limit: Arc<OverLimit>,
+    options: Arc<Options>,
+}
+
+// Servers share translators between the threads of async runtimes, so this must hold with every
+// combination of features. A field which breaks it fails the build here, rather than in
+// someone's `tokio::spawn`.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+    assert_send_sync::<FluentErgo>();
+    assert_send_sync::<FrozenFluentErgo>();
+    assert_send_sync::<Error>();
+    #[cfg(feature = "http")]
+    assert_send_sync::<OtaHandle>();
+    #[cfg(feature = "http")]
+    assert_send_sync::<OtaClient>();
+};
+
+impl<K: BundleKind> FrozenFluentErgo<K> {
+    /// Run a translation. This behaves exactly like `FluentErgo::tr`.
+    ///
+    /// # Errors
+    ///
+    /// * NoMatchingMessage -- this will be returned if the message identifier cannot be found in
+    ///   any language bundle.
+    ///
+    pub fn tr(&self, msgid: &str, args: Option<&FluentArgs>) -> Result<String, Error> {
+        self.tr_opts(msgid, args, &TrOptions::default())
+    }
+
+    /// Run a translation with per-call options. This behaves exactly like `FluentErgo::tr_opts`.
+    ///
+    /// # Errors
+    ///
+    /// * NoMatchingMessage -- this will be returned if the message identifier cannot be found in
+    ///   any language bundle.
+    /// * FormatError -- the message could not be formatted, and `opts` asked for an error in that
+    ///   case.
+    ///
+    pub fn tr_opts(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+    ) -> Result<String, Error> {
+        match self.find(msgid, args, opts)? {
+            Some(r) => Ok(r),
+            None => Err(Error::NoMatchingMessage(MissingMessage::new(
+                msgid,
+                &self.languages,
+                |lang| Some(valueless::<K>(self.bundles.get(lang)?, msgid)),
+            ))),
+        }
+    }
+
+    /// Append a translation to `out`. This behaves exactly like `FluentErgo::tr_into`.
+    ///
+    /// # Errors
+    ///
+    /// * NoMatchingMessage -- this will be returned if the message identifier cannot be found in
+    ///   any language bundle.
+    ///
+    pub fn tr_into(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        out: &mut String,
+    ) -> Result<(), Error> {
+        if self.find_into(msgid, args, &TrOptions::default(), out)? {
+            return Ok(());
+        }
+        Err(Error::NoMatchingMessage(MissingMessage::new(
+            msgid,
+            &self.languages,
+            |lang| Some(valueless::<K>(self.bundles.get(lang)?, msgid)),
+        )))
+    }
+
+    /// Like `tr`, but returns `None` instead of an error. See `FluentErgo::tr_opt`.
+    pub fn tr_opt(&self, msgid: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.find(msgid, args, &TrOptions::default()).ok().flatten()
+    }
+
+    /// Translate a message, or return `default` if no bundle has it. See `FluentErgo::tr_or`.
+    pub fn tr_or(&self, msgid: &str, args: Option<&FluentArgs>, default: &str) -> String {
+        self.tr_opt(msgid, args)
+            .unwrap_or_else(|| String::from(default))
+    }
+
+    /// Like `tr_or`, but the default is only computed if it is needed.
+    pub fn tr_or_else<F>(&self, msgid: &str, args: Option<&FluentArgs>, f: F) -> String
+    where
+        F: FnOnce() -> String,
+    {
+        self.tr_opt(msgid, args).unwrap_or_else(f)
+    }
+
+    /// Translate a message without arguments, or return the message identifier. See
+    /// `FluentErgo::at`.
+    pub fn at(&self, msgid: &str) -> String {
+        self.tr_or(msgid, None, msgid)
+    }
+
+    /// Return a value which translates the message when it is displayed. See
+    /// `FluentErgo::display`.
+    pub fn display<'a>(
+        &'a self,
+        msgid: &'a str,
+        args: Option<&'a FluentArgs<'a>>,
+    ) -> Translation<'a> {
+        Translation {
+            translator: self,
+            msgid,
+            args,
+        }
+    }
+
+    fn find(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+    ) -> Result<Option<String>, Error> {
+        let mut out = String::new();
+        let found = self.find_into(msgid, args, opts, &mut out)?;
+        Ok(if found { Some(out) } else { None })
+    }
+
+    fn find_into(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+        out: &mut String,
+    ) -> Result<bool, Error> {
+        trace_span!(
+            "tr",
+            msgid,
+            lang = tracing::field::Empty,
+            depth = tracing::field::Empty,
+            errors = tracing::field::Empty
+        );
+        let started = self.options.observe_start();
+        for lang in &self.languages {
+            let over_limit = self
+                .over_limit
+                .get(lang)
+                .and_then(|over_limit| over_limit.get(msgid));
+            if let Some(limit) = over_limit {
+                return Err(Error::LimitExceeded {
+                    id: String::from(msgid),
+                    lang: lang.clone(),
+                    limit: *limit,
+                });
+            }
+            let literal = self
+                .literals
+                .get(lang)
+                .and_then(|literals| literals.get(msgid));
+            let found = match literal {
+                Some(text) => {
+                    write_literal(&self.options, opts, lang, msgid, text, out)?;
+                    true
+                }
+                None => match self.bundles.get(lang) {
+                    Some(bundle) => {
+                        write_message::<K>(&self.options, opts, lang, bundle, msgid, args, out)?
+                    }
+                    None => false,
+                },
+            };
+            if found {
+                trace_record!("lang", tracing::field::display(lang));
+                trace_record!(
+                    "depth",
+                    self.languages.iter().position(|l| l == lang).unwrap_or(0) as u64
+                );
+                self.options.observe(msgid, lang, started);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Anything that a `Translation` can be displayed from.
+trait Lookup {
+    fn lookup(&self, msgid: &str, args: Option<&FluentArgs>) -> Option<String>;
+}
+
+impl<K: BundleKind> Lookup for FluentErgo<K> {
+    fn lookup(&self, msgid: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.tr_opt(msgid, args)
+    }
+}
+
+impl<K: BundleKind> Lookup for FrozenFluentErgo<K> {
+    fn lookup(&self, msgid: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.tr_opt(msgid, args)
+    }
+}
+
+/// A source of translations which a library crate can implement, so that applications which use
+/// the library can add its translations with `FluentErgo::install`. Message IDs share one
+/// namespace per language, so libraries should prefix theirs, e.g. `mylib-not-found`.
+///
+/// ```
+/// use fluent_ergonomics::{FluentErgo, Localizations};
+/// use unic_langid::LanguageIdentifier;
+///
+/// struct MyLibrary;
+///
+/// impl Localizations for MyLibrary {
+///     fn resources(&self) -> Vec<(LanguageIdentifier, String)> {
+///         vec![
+///             ("en-US".parse().unwrap(), String::from("mylib-units = Units")),
+///             ("eo".parse().unwrap(), String::from("mylib-units = Unuoj")),
+///         ]
+///     }
+/// }
+///
+/// let mut fluent = FluentErgo::new(&["eo".parse().unwrap()]);
+/// fluent.install(&MyLibrary).unwrap();
+/// assert_eq!(fluent.tr("mylib-units", None).unwrap(), "Unuoj");
+/// ```
+pub trait Localizations {
+    /// Every resource that the library has, along with the language it is written in.
+    fn resources(&self) -> Vec<(LanguageIdentifier, String)>;
+}
+
+/// The result of `FluentErgo::readiness`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Readiness {
+    /// Configured languages which have no resources at all, in search order
+    pub missing: Vec<LanguageIdentifier>,
+}
+
+impl Readiness {
+    /// Whether every configured language has resources.
+    pub fn is_ready(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl fmt::Display for Readiness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ready() {
+            return write!(f, "every configured language has resources");
+        }
+        let missing: Vec<String> = self.missing.iter().map(|lang| lang.to_string()).collect();
+        write!(f, "no resources for {}", missing.join(", "))
+    }
+}
+
+/// The result of `FluentErgo::diff_languages`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanguageDiff {
+    /// Messages which the base language has, but this language does not
+    pub missing: Vec<String>,
+    /// Messages which this language has, but the base language does not. These are usually
+    /// leftovers from messages that have been removed or renamed.
+    pub extra: Vec<String>,
+    /// How many messages this language has
+    pub translated: usize,
+    /// How many messages the base language has
+    pub total: usize,
+}
+
+impl LanguageDiff {
+    /// The share of the base language's messages which this language translates, from 0 to 1.
+    pub fn coverage(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.total - self.missing.len()) as f64 / self.total as f64
+        }
+    }
+}
+
+/// A message which is translated when it gets displayed, created with `FluentErgo::display`.
+pub struct Translation<'a> {
+    translator: &'a dyn Lookup,
+    msgid: &'a str,
+    args: Option<&'a FluentArgs<'a>>,
+}
+
+impl<'a> fmt::Display for Translation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.translator.lookup(self.msgid, self.args) {
+            Some(tr) => f.write_str(&tr),
+            None => f.write_str(self.msgid),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Translation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Translation({:?})", self.msgid)
+    }
+}
+
+/// Whether a bundle contains the message, but with only attributes and no value.
+fn valueless<K: BundleKind>(bundle: &K::Bundle, msgid: &str) -> bool {
+    K::has_value(bundle, msgid) == Some(false)
+}
+
+fn parse_text(
+    options: &Options,
+    text: String,
+    path: Option<PathBuf>,
+) -> Result<ParsedResource, Error> {
+    trace_span!("parse", path = ?path, bytes = text.len());
+    let text = if options.normalize_nfc {
+        nfc(text)
+    } else {
+        text
+    };
+    let text = if options.legacy_syntax {
+        upgrade_legacy_syntax(text)
+    } else {
+        text
+    };
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    let hash = hasher.finish();
+    match FluentResource::try_new(text) {
+        Ok(resource) => Ok(ParsedResource {
+            hash,
+            resource,
+            origin: path.clone().map_or(Origin::Text, Origin::File),
+            path,
+            modified: None,
+        }),
+        Err(inp) => match path {
+            Some(path) => Err(Error::from(inp).in_file(&path)),
+            None => Err(Error::from(inp)),
+        },
+    }
+}
+
+/// Parse a batch of resources. With the `parallel` feature, the work is split across one thread
+/// per available core.
+fn parse_all(
+    options: &Options,
+    resources: Vec<(LanguageIdentifier, UnparsedResource)>,
+) -> Result<Vec<(LanguageIdentifier, ParsedResource)>, Error> {
+    #[cfg(feature = "parallel")]
+    {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if threads > 1 && resources.len() > 1 {
+            let chunk_size = resources.len().div_ceil(threads);
+            let mut chunks = Vec::new();
+            let mut resources = resources.into_iter().peekable();
+            while resources.peek().is_some() {
+                chunks.push(resources.by_ref().take(chunk_size).collect::<Vec<_>>());
+            }
+            return std::thread::scope(|scope| {
+                let handles: Vec<_> = chunks
+                    .into_iter()
+                    .map(|chunk| scope.spawn(move || parse_sequential(options, chunk)))
+                    .collect();
+                let mut parsed = Vec::new();
+                for handle in handles {
+                    parsed.append(&mut handle.join().map_err(|_| Error::Poisoned)??);
+                }
+                Ok(parsed)
+            });
+        }
+    }
+    parse_sequential(options, resources)
+}
+
+fn parse_sequential(
+    options: &Options,
+    resources: Vec<(LanguageIdentifier, UnparsedResource)>,
+) -> Result<Vec<(LanguageIdentifier, ParsedResource)>, Error> {
+    resources
+        .into_iter()
+        .map(|(lang, resource)| {
+            let res = resource.parse(options, &lang)?;
+            Ok((lang, res))
+        })
+        .collect()
+}
+
+#[cfg(feature = "fs")]
+fn read_file(path: &Path) -> Result<String, Error> {
+    read_file_contents(path).map_err(|err| err.in_file(path))
+}
+
+#[cfg(all(feature = "fs", not(feature = "mmap")))]
+fn read_file_contents(path: &Path) -> Result<String, Error> {
+    let mut v = Vec::new();
+    let mut f = File::open(path)?;
+    f.read_to_end(&mut v)?;
+    String::from_utf8(v).map_err(Error::from)
+}
+
+#[cfg(feature = "mmap")]
+fn read_file_contents(path: &Path) -> Result<String, Error> {
+    let f = File::open(path)?;
+    if f.metadata()?.len() == 0 {
+        // Empty files cannot be mapped on every platform.
+        return Ok(String::new());
+    }
+    // Safety: the map is only read for the duration of this function. If another process
+    // truncates the file while it is mapped, the contents may change underneath us, but that can
+    // only produce garbage text and never touches memory outside of the map.
+    let map = unsafe { memmap2::Mmap::map(&f)? };
+    match std::str::from_utf8(&map) {
+        Ok(text) => Ok(String::from(text)),
+        // Only copy the bytes on the error path, to produce the same error as the unmapped
+        // reader.
+        Err(_) => String::from_utf8(map.to_vec()).map_err(Error::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "http")]
+    use super::OtaClient;
+    use super::{
+        canonicalize_language, to_title_case, Case, Error, EvaluationLimits, FluentArgsExt,
+        FluentErgo, FrozenFluentErgo, IntoFluentValue, LanguageDelta, LanguageListProblem, Limit,
+        LocalizedValue, MergeConflicts, MissingArguments, MissingMessage, MissingMessageResolver,
+        OnFormatError, Resolution, ResourceProvider, SingleThreaded, TrOptions, UnknownLanguages,
+    };
+    use fluent::{FluentArgs, FluentValue};
+    use std::collections::VecDeque;
+    use std::time::Duration;
+    use unic_langid::LanguageIdentifier;
+
+    const EN_TRANSLATIONS: &str = "
+preferences = Preferences
+history = History
+time_display = {$time} during the day
+nested_display = nesting a time display: {time_display}
+";
+
+    const EO_TRANSLATIONS: &str = "
+history = Historio
+";
+
+    #[test]
+    fn translations() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+    }
+
+    #[test]
+    fn translation_fallback() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        fluent
+            .add_from_text(eo_id, String::from(EO_TRANSLATIONS))
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+    }
+
+    #[test]
+    fn placeholder_insertion_should_strip_placeholder_markers() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("time", FluentValue::from(String::from("13:00")));
+        assert_eq!(
+            fluent.tr("time_display", Some(&args)).unwrap(),
+            String::from("13:00 during the day")
+        );
+    }
+
+    #[test]
     fn placeholder_insertion_should_strip_nested_placeholder_markers() {
         let en_id = "en".parse::<LanguageIdentifier>().unwrap();
-        let mut fluent = FluentErgo::new(&vec![en_id.clone()]);
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("time", FluentValue::from(String::from("13:00")));
+        assert_eq!(
+            fluent.tr("nested_display", Some(&args)).unwrap(),
+            String::from("nesting a time display: 13:00 during the day")
+        );
+    }
+
+    #[test]
+    fn nfc_normalization_composes_text_and_arguments() {
+        let fr_id = "fr".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&fr_id));
+        fluent.set_nfc_normalization(true);
+        fluent
+            .add_from_text(fr_id, String::from("cafe = Caf\u{65}\u{301} {$name}"))
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("name", FluentValue::from("Ren\u{65}\u{301}e"));
+        assert_eq!(
+            fluent.tr("cafe", Some(&args)).unwrap(),
+            String::from("Caf\u{e9} Ren\u{e9}e")
+        );
+    }
+
+    #[test]
+    fn missing_messages_are_found_after_new_resources_load() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        assert!(fluent.tr("units", None).is_err());
+        assert!(fluent.tr("units", None).is_err());
+
+        fluent
+            .add_from_text(en_id, String::from("units = Units"))
+            .expect("text should load");
+        assert_eq!(fluent.tr("units", None).unwrap(), String::from("Units"));
+    }
+
+    #[test]
+    fn resources_added_on_another_thread_are_visible_to_clones() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+
+        let mut writer = fluent.clone();
+        std::thread::spawn(move || {
+            writer
+                .add_from_text(eo_id, String::from(EO_TRANSLATIONS))
+                .expect("text should load");
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+    }
+
+    #[test]
+    fn frozen_translations() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        fluent
+            .add_from_text(eo_id, String::from(EO_TRANSLATIONS))
+            .expect("text should load");
+
+        let other = fluent.clone();
+        let fluent = fluent
+            .freeze()
+            .expect_err("freezing should fail while shared");
+        drop(other);
+
+        let frozen = fluent.freeze().expect("freezing should succeed");
+        assert_eq!(
+            frozen.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            frozen.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+        assert!(frozen.tr("units", None).is_err());
+    }
+
+    #[test]
+    fn lazy_resources_load_on_first_translation() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let fr_id = "fr".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_lazy_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .unwrap();
+        fluent
+            .add_lazy_from_text(eo_id, String::from(EO_TRANSLATIONS))
+            .unwrap();
+        fluent
+            .add_lazy_from_text(fr_id, String::from("history = { broken"))
+            .unwrap();
+
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+        assert!(fluent.load_lazy_resources().is_err());
+        assert!(fluent.freeze().is_ok());
+    }
+
+    #[test]
+    fn add_many_loads_every_language() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (en_id.clone(), String::from(EN_TRANSLATIONS)),
+                (eo_id.clone(), String::from(EO_TRANSLATIONS)),
+            ])
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+
+        let result = fluent.add_many(vec![
+            (en_id, String::from("units = Units")),
+            (eo_id, String::from("units = { broken")),
+        ]);
+        assert!(result.is_err());
+        assert!(fluent.tr("units", None).is_err());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn add_from_dir_loads_language_subdirectories() {
+        let root = std::env::temp_dir().join(format!("fluent-ergo-dir-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("en")).unwrap();
+        std::fs::create_dir_all(root.join("eo")).unwrap();
+        std::fs::write(root.join("en").join("main.ftl"), EN_TRANSLATIONS).unwrap();
+        std::fs::write(root.join("en").join("notes.txt"), "not = ftl").unwrap();
+        std::fs::write(root.join("eo").join("main.ftl"), EO_TRANSLATIONS).unwrap();
+
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id, en_id]);
+        let result = fluent.add_from_dir(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        result.expect("directory should load");
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+        assert!(fluent.tr("not", None).is_err());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn from_config_reads_toml_and_json_manifests() {
+        let root = std::env::temp_dir().join(format!("fluent-ergo-config-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("en")).unwrap();
+        std::fs::create_dir_all(root.join("eo")).unwrap();
+        std::fs::write(root.join("en").join("main.ftl"), EN_TRANSLATIONS).unwrap();
+        std::fs::write(root.join("en").join("extra.ftl"), "units = Units").unwrap();
+        std::fs::write(root.join("eo").join("main.ftl"), EO_TRANSLATIONS).unwrap();
+        std::fs::write(
+            root.join("locales.toml"),
+            "languages = [\"eo\", \"en\"]\n[resources]\nen = [\"en/*.ftl\"]\neo = [\"eo/main.ftl\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("locales.json"),
+            r#"{"languages": ["en"], "resources": {"en": ["en/m*.ftl"]}}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("broken.json"), r#"{"langs": []}"#).unwrap();
+
+        let toml = FluentErgo::from_config(&root.join("locales.toml"));
+        let json = FluentErgo::from_config(&root.join("locales.json"));
+        let broken = FluentErgo::from_config(&root.join("broken.json"));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let toml = toml.expect("TOML manifest should load");
+        assert_eq!(toml.tr("history", None).unwrap(), String::from("Historio"));
+        assert_eq!(toml.tr("units", None).unwrap(), String::from("Units"));
+        let json = json.expect("JSON manifest should load");
+        assert_eq!(json.tr("history", None).unwrap(), String::from("History"));
+        assert!(json.tr("units", None).is_err());
+        match broken {
+            Err(Error::ConfigError { path, .. }) => assert!(path.ends_with("broken.json")),
+            other => panic!("expected ConfigError, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn ota_client_swaps_in_downloaded_catalogs() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let response = if request.starts_with("GET /eo.ftl") {
+                    String::from(
+                        "HTTP/1.1 200 OK\r\nContent-Length: 19\r\nConnection: close\r\n\r\nhistory = Historioj",
+                    )
+                } else {
+                    String::from(
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    )
+                };
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (en_id, String::from(EN_TRANSLATIONS)),
+                (eo_id, String::from(EO_TRANSLATIONS)),
+            ])
+            .expect("text should load");
+        let client = OtaClient::new(&format!("http://127.0.0.1:{}/{{lang}}.ftl", port));
+        client.pull(&fluent).expect("pull should succeed");
+        server.join().unwrap();
+
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historioj")
+        );
+        assert_eq!(
+            fluent.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn stale_catalogs_are_reported() {
+        let root = std::env::temp_dir().join(format!("fluent-ergo-stale-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let (en_path, eo_path) = (root.join("en.ftl"), root.join("eo.ftl"));
+        std::fs::write(&eo_path, EO_TRANSLATIONS).unwrap();
+        std::fs::write(&en_path, EN_TRANSLATIONS).unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(&eo_path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        let stale = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = stale.clone();
+        fluent.set_staleness_handler(en_id.clone(), move |event| {
+            reported.lock().unwrap().push(event.lang.clone());
+        });
+        let eo_result = fluent.add_from_file(eo_id.clone(), &eo_path);
+        let en_result = fluent.add_from_file(en_id.clone(), &en_path);
+        std::fs::remove_dir_all(&root).unwrap();
+        eo_result.expect("file should load");
+        en_result.expect("file should load");
+
+        assert_eq!(*stale.lock().unwrap(), vec![eo_id.clone()]);
+        let version = fluent.catalog_version(&eo_id).unwrap();
+        assert_eq!(version.resources, 1);
+        assert_eq!(version.modified, Some(old));
+        assert_ne!(version.hash, fluent.catalog_version(&en_id).unwrap().hash);
+        assert_eq!(fluent.catalog_version(&"fr".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn adding_identical_text_twice_is_harmless() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("identical text should be ignored");
+        assert!(fluent
+            .add_from_text(en_id, String::from("history = Past"))
+            .is_err());
+        assert_eq!(fluent.tr("history", None).unwrap(), String::from("History"));
+    }
+
+    #[test]
+    fn missing_message_errors_describe_the_search() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(en_id.clone(), String::from("units =\n    .title = Units"))
+            .expect("text should load");
+
+        for _ in 0..2 {
+            match fluent.tr("units", None) {
+                Err(Error::NoMatchingMessage(missing)) => {
+                    assert_eq!(missing.id, "units");
+                    assert_eq!(missing.languages, vec![eo_id.clone(), en_id.clone()]);
+                    assert_eq!(missing.loaded, vec![en_id.clone()]);
+                    assert_eq!(missing.without_value, vec![en_id.clone()]);
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn file_errors_carry_their_language_and_path() {
+        let path = std::env::temp_dir().join(format!("fluent-ergo-bad-{}.ftl", std::process::id()));
+        std::fs::write(&path, "history = { broken").unwrap();
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        let result = fluent.add_from_file(en_id.clone(), &path);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(err @ Error::FluentParserError { .. }) => {
+                assert_eq!(err.lang(), Some(&en_id));
+                assert_eq!(err.path(), Some(path.as_path()));
+                assert!(!err.spans().is_empty());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn errors_serialize_as_reports() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        let err = fluent.tr("units", None).unwrap_err();
+        let report = serde_json::to_value(&err).unwrap();
+        assert_eq!(report["kind"], "NoMatchingMessage");
+        assert_eq!(report["message_id"], "units");
+    }
+
+    #[cfg(feature = "miette")]
+    #[test]
+    fn parser_errors_label_the_source() {
+        use miette::Diagnostic;
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        let err = fluent
+            .add_from_text(en_id, String::from("history = { broken"))
+            .unwrap_err();
+        assert!(err.source_code().is_some());
+        assert_eq!(err.labels().unwrap().count(), err.spans().len());
+    }
+
+    #[test]
+    fn format_error_policies() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+
+        assert_eq!(
+            fluent.tr("time_display", None).unwrap(),
+            String::from("{$time} during the day")
+        );
+
+        let strict = TrOptions::on_any_error(OnFormatError::Fail);
+        match fluent.tr_opts("time_display", None, &strict) {
+            Err(err @ Error::FormatError { .. }) => {
+                assert_eq!(err.message_id(), Some("time_display"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let fallback = TrOptions {
+            on_unresolved_reference: OnFormatError::Fallback(String::from("sometime")),
+            ..TrOptions::default()
+        };
+        assert_eq!(
+            fluent.tr_opts("time_display", None, &fallback).unwrap(),
+            String::from("sometime")
+        );
+        assert_eq!(
+            fluent.tr_opts("preferences", None, &strict).unwrap(),
+            String::from("Preferences")
+        );
+    }
+
+    #[test]
+    fn tr_opt_returns_none_for_missing_messages() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr_opt("preferences", None),
+            Some(String::from("Preferences"))
+        );
+        assert_eq!(fluent.tr_opt("tooltip-preferences", None), None);
+        match fluent.tr("tooltip-preferences", None) {
+            Err(Error::NoMatchingMessage(missing)) => assert_eq!(missing.id, "tooltip-preferences"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tr_or_falls_back_to_defaults() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        assert_eq!(fluent.tr_or("history", None, "Past"), "History");
+        assert_eq!(fluent.tr_or("units", None, "Units"), "Units");
+        assert_eq!(
+            fluent.tr_or_else("history", None, || panic!("default should not be needed")),
+            "History"
+        );
+        assert_eq!(
+            fluent.tr_or_else("units", None, || String::from("Units")),
+            "Units"
+        );
+    }
+
+    #[test]
+    fn display_translates_inline() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("time", FluentValue::from(String::from("13:00")));
+        assert_eq!(
+            format!(
+                "{} / {}",
+                fluent.display("history", None),
+                fluent.display("time_display", Some(&args))
+            ),
+            String::from("History / 13:00 during the day")
+        );
+        assert_eq!(format!("{}", fluent.display("units", None)), "units");
+    }
+
+    #[test]
+    fn resolve_negotiates_against_loaded_languages() {
+        let en_us_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let en_gb_id = "en-GB".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_us_id));
+        fluent
+            .add_from_text(en_us_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        fluent
+            .add_from_text(eo_id.clone(), String::from(EO_TRANSLATIONS))
+            .expect("text should load");
+
+        let requested = ["fr".parse().unwrap(), en_gb_id, eo_id.clone()];
+        let resolved = fluent.resolve(&requested);
+        assert_eq!(resolved, vec![en_us_id.clone(), eo_id.clone()]);
+
+        let esperanto = fluent.with_languages(&[eo_id, en_us_id]);
+        assert!(fluent.tr("units", None).is_err());
+        assert_eq!(
+            esperanto.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(fluent.tr("history", None).unwrap(), String::from("History"));
+    }
+
+    #[test]
+    fn language_identifiers_are_canonicalized() {
+        let he_id = "he".parse::<LanguageIdentifier>().unwrap();
+        let iw_id = "iw".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&he_id));
+        fluent
+            .add_from_text(iw_id, String::from("history = היסטוריה"))
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("היסטוריה")
+        );
+        assert_eq!(
+            canonicalize_language(&"sh_ba".parse().unwrap()).to_string(),
+            "sr-Latn-BA"
+        );
+    }
+
+    #[test]
+    fn unknown_languages_follow_the_policy() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent.set_unknown_languages(UnknownLanguages::Reject);
+        match fluent.add_many(vec![
+            (en_id.clone(), String::from(EN_TRANSLATIONS)),
+            (eo_id.clone(), String::from(EO_TRANSLATIONS)),
+        ]) {
+            Err(Error::UnregisteredLanguage(lang)) => assert_eq!(lang, eo_id),
+            other => panic!("expected UnregisteredLanguage, got {:?}", other),
+        }
+        assert!(fluent.tr("history", None).is_err());
+
+        fluent.set_unknown_languages(UnknownLanguages::Append);
+        fluent
+            .add_from_text(eo_id, String::from(EO_TRANSLATIONS))
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+    }
+
+    #[test]
+    fn readiness_counts_lazy_resources() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let fr_id = "fr".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(fr_id.clone(), String::from("history = Histoire"))
+            .expect("text should load");
+        assert_eq!(
+            fluent.readiness().missing,
+            vec![eo_id.clone(), en_id.clone()]
+        );
+
+        fluent
+            .add_lazy_from_text(eo_id.clone(), String::from(EO_TRANSLATIONS))
+            .unwrap();
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        assert!(fluent.readiness().is_ready());
+        assert_eq!(
+            fluent.configured_languages(),
+            &[eo_id.clone(), en_id.clone()]
+        );
+        assert_eq!(fluent.loaded_languages(), vec![en_id, eo_id, fr_id]);
+    }
+
+    #[test]
+    fn merge_combines_translators() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut app = FluentErgo::new(std::slice::from_ref(&en_id));
+        app.add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        let mut library = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        library
+            .add_from_text(
+                en_id.clone(),
+                String::from("history = Library history\nunits = Units"),
+            )
+            .expect("text should load");
+        library
+            .add_lazy_from_text(eo_id.clone(), String::from(EO_TRANSLATIONS))
+            .unwrap();
+
+        let mut kept = app.clone();
+        kept.merge(&library, MergeConflicts::Keep).unwrap();
+        assert_eq!(kept.configured_languages(), &[en_id.clone(), eo_id.clone()]);
+        assert_eq!(kept.tr("history", None).unwrap(), String::from("History"));
+        assert_eq!(kept.tr("units", None).unwrap(), String::from("Units"));
+        let eo = kept.with_languages(std::slice::from_ref(&eo_id));
+        assert_eq!(eo.tr("history", None).unwrap(), String::from("Historio"));
+
+        let mut replaced = FluentErgo::new(std::slice::from_ref(&en_id));
+        replaced
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        replaced.merge(&library, MergeConflicts::Replace).unwrap();
+        assert_eq!(
+            replaced.tr("history", None).unwrap(),
+            String::from("Library history")
+        );
+    }
+
+    #[test]
+    fn diff_languages_compares_message_ids() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_many(vec![
+                (en_id.clone(), String::from(EN_TRANSLATIONS)),
+                (
+                    eo_id.clone(),
+                    String::from("history = Historio\nold = Malnova\n-term = Termino"),
+                ),
+            ])
+            .expect("text should load");
+        assert_eq!(fluent.message_ids(&eo_id), vec!["history", "old"]);
+
+        let diff = fluent.diff_languages(&en_id, &eo_id);
+        assert_eq!(
+            diff.missing,
+            vec!["nested_display", "preferences", "time_display"]
+        );
+        assert_eq!(diff.extra, vec!["old"]);
+        assert_eq!(diff.coverage(), 0.25);
+    }
+
+    #[test]
+    fn check_keys_reports_unknown_and_unused_ids() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        let source = r#"
+            fluent.tr("history", None)?;
+            fluent.tr_or( "preferences", None, "Preferences");
+            tr!("units");
+            attr("not-a-call");
+            fluent.tr(&dynamic_id, None)?;
+        "#;
+        let used = super::extract_message_ids(source);
+        assert_eq!(used, vec!["history", "preferences", "units"]);
+
+        let report = fluent.check_keys(&en_id, &used);
+        assert_eq!(report.unknown, vec!["units"]);
+        assert_eq!(report.unused, vec!["nested_display", "time_display"]);
+    }
+
+    #[test]
+    fn missing_message_resolver_caches_patterns() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Greeter;
+
+        impl MissingMessageResolver for Greeter {
+            fn resolve(
+                &self,
+                msgid: &str,
+                _args: Option<&FluentArgs>,
+                _languages: &[LanguageIdentifier],
+            ) -> Option<Resolution> {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+                match msgid {
+                    "greeting" => Some(Resolution::Pattern {
+                        lang: "en".parse().unwrap(),
+                        source: String::from("Hello, { $name }"),
+                    }),
+                    _ => None,
+                }
+            }
+        }
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+            .expect("text should load");
+        fluent.set_missing_message_resolver(Greeter);
+
+        let mut args = FluentArgs::new();
+        args.set("name", FluentValue::from("Savanni"));
+        for _ in 0..2 {
+            assert_eq!(
+                fluent.tr("greeting", Some(&args)).unwrap(),
+                String::from("Hello, Savanni")
+            );
+        }
+        assert_eq!(fluent.tr("history", None).unwrap(), String::from("History"));
+        assert!(fluent.tr("unknown", None).is_err());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn replace_all_swaps_the_whole_catalog() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(eo_id.clone(), String::from(EO_TRANSLATIONS))
+            .expect("text should load");
+        fluent
+            .add_lazy_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .unwrap();
+        let reader = fluent.clone();
+
+        fluent
+            .replace_all(vec![(en_id.clone(), String::from("history = New history"))])
+            .expect("catalog should load");
+        assert_eq!(fluent.loaded_languages(), vec![en_id]);
+        assert_eq!(
+            reader.tr("history", None).unwrap(),
+            String::from("New history")
+        );
+        assert!(reader.tr("preferences", None).is_err());
+    }
+
+    #[test]
+    fn single_threaded_bundles_translate_and_freeze() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent: FluentErgo<SingleThreaded> =
+            FluentErgo::new_with_kind(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (en_id, String::from(EN_TRANSLATIONS)),
+                (eo_id, String::from(EO_TRANSLATIONS)),
+            ])
+            .expect("text should load");
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+
+        let frozen = fluent.freeze().expect("freezing should succeed");
+        assert_eq!(
+            frozen.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+    }
+
+    #[test]
+    fn intl_cache_limit_drops_least_recently_used_languages() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (en_id.clone(), String::from(EN_TRANSLATIONS)),
+                (eo_id.clone(), String::from(EO_TRANSLATIONS)),
+            ])
+            .expect("text should load");
+        fluent.set_intl_cache_limit(Some(1));
+        let english = fluent.with_languages(std::slice::from_ref(&en_id));
+
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            english.tr("history", None).unwrap(),
+            String::from("History")
+        );
+        assert_eq!(
+            fluent.intl.lock().unwrap().recent,
+            VecDeque::from(vec![en_id.clone()])
+        );
+
+        fluent.clear_intl_cache();
+        assert!(fluent.intl.lock().unwrap().recent.is_empty());
+        assert_eq!(
+            fluent.tr("history", None).unwrap(),
+            String::from("Historio")
+        );
+        assert_eq!(
+            english.tr("preferences", None).unwrap(),
+            String::from("Preferences")
+        );
+    }
+
+    #[test]
+    fn tr_duration_picks_unit_and_plural_form() {
+        let pl_id = "pl".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&pl_id));
+        fluent
+            .add_from_text(
+                pl_id,
+                String::from(
+                    "
+left-seconds = { $count ->
+    [one] { $count } sekunda
+    [few] { $count } sekundy
+   *[many] { $count } sekund
+}
+left-minutes = { $count ->
+    [one] { $count } minuta
+    [few] { $count } minuty
+   *[many] { $count } minut
+}
+left-hours = { $count } h
+left-days = { $count } d
+",
+                ),
+            )
+            .expect("text should load");
+
+        let tr = |secs| {
+            fluent
+                .tr_duration("left", Duration::from_millis(secs))
+                .unwrap()
+        };
+        assert_eq!(tr(500), "0 sekund");
+        assert_eq!(tr(1_000), "1 sekunda");
+        assert_eq!(tr(3_000), "3 sekundy");
+        assert_eq!(tr(5 * 60_000 + 59_000), "5 minut");
+        assert_eq!(tr(22 * 60_000), "22 minuty");
+        assert_eq!(tr(2 * 3_600_000), "2 h");
+        assert_eq!(tr(3 * 86_400_000), "3 d");
+    }
+
+    #[test]
+    fn tr_truncated_cuts_between_graphemes() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("cafe = Cafe\u{301} \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} menu"),
+            )
+            .expect("text should load");
+
+        let full = fluent.tr("cafe", None).unwrap();
+        assert_eq!(
+            fluent.tr_truncated("cafe", None, 11, "ellipsis").unwrap(),
+            full
+        );
+        assert_eq!(
+            fluent.tr_truncated("cafe", None, 7, "ellipsis").unwrap(),
+            "Cafe\u{301} \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}…"
+        );
+        assert_eq!(
+            fluent.tr_truncated("cafe", None, 6, "ellipsis").unwrap(),
+            "Cafe\u{301}…"
+        );
+    }
+
+    #[test]
+    fn case_option_restyles_translations_per_locale() {
+        let tr_id = "tr".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[tr_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (tr_id, String::from("open-file = ileri düzey İçerik")),
+                (en_id, String::from("save-file = Save File As PDF")),
+            ])
+            .expect("text should load");
+
+        let title = TrOptions {
+            case: Case::Title,
+            ..TrOptions::default()
+        };
+        let sentence = TrOptions {
+            case: Case::Sentence,
+            ..TrOptions::default()
+        };
+        assert_eq!(
+            fluent.tr_opts("open-file", None, &title).unwrap(),
+            "İleri Düzey İçerik"
+        );
+        assert_eq!(
+            fluent.tr_opts("open-file", None, &sentence).unwrap(),
+            "İleri düzey içerik"
+        );
+        assert_eq!(
+            fluent.tr_opts("save-file", None, &sentence).unwrap(),
+            "Save file as PDF"
+        );
+        assert_eq!(
+            to_title_case("ǆungla ijs", &"nl".parse().unwrap()),
+            "ǅungla IJs"
+        );
+    }
+
+    #[cfg(feature = "icu")]
+    #[test]
+    fn sort_localized_follows_the_active_language() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let sv_id = "sv".parse::<LanguageIdentifier>().unwrap();
+        let german = FluentErgo::new(&[de_id, sv_id.clone()]);
+        let swedish = german.with_languages(&[sv_id]);
+        let labels = vec![
+            String::from("zoo"),
+            String::from("öl"),
+            String::from("Ost"),
+            String::from("apfel"),
+        ];
+
+        let mut sorted = labels.clone();
+        german.sort_localized(&mut sorted);
+        assert_eq!(sorted, ["apfel", "öl", "Ost", "zoo"]);
+
+        let mut sorted = labels;
+        swedish.sort_localized(&mut sorted);
+        assert_eq!(sorted, ["apfel", "Ost", "zoo", "öl"]);
+    }
+
+    #[test]
+    fn parse_number_uses_the_active_locale() {
+        let parse =
+            |tag: &str, text: &str| FluentErgo::new(&[tag.parse().unwrap()]).parse_number(text);
+        assert_eq!(parse("en-US", " 1,234.5 ").unwrap(), 1234.5);
+        assert_eq!(parse("de", "1.234,5").unwrap(), 1234.5);
+        assert_eq!(parse("de-CH", "1’234.5").unwrap(), 1234.5);
+        assert_eq!(parse("fr", "\u{2212}1\u{202f}234,5").unwrap(), -1234.5);
+        assert_eq!(parse("ar", "\u{661}\u{662}\u{66b}\u{665}").unwrap(), 12.5);
+        assert_eq!(parse("es-MX", "+3.25").unwrap(), 3.25);
+
+        match parse("de", "1,234.5") {
+            Err(err @ Error::InvalidNumber { .. }) => {
+                assert_eq!(err.lang(), Some(&"de".parse().unwrap()))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(parse("en", ",5").is_err());
+        assert!(parse("en", "1,").is_err());
+        assert!(parse("en", "1.2.3").is_err());
+        assert!(parse("en", "").is_err());
+    }
+
+    #[test]
+    fn functions_and_localized_values_reach_every_bundle() {
+        #[derive(Debug)]
+        struct LanguageTag;
+
+        impl LocalizedValue for LanguageTag {
+            fn format(&self, lang: &LanguageIdentifier) -> String {
+                lang.to_string()
+            }
+        }
+
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(eo_id, String::from("eo-tag = { TAG() }"))
+            .expect("text should load");
+        fluent
+            .add_function("TAG", |_, _| LanguageTag.into_fluent_value())
+            .expect("function should be added");
+        fluent
+            .add_from_text(en_id, String::from("en-tag = { TAG() } { $count }"))
+            .expect("text should load");
+
+        let mut args = FluentArgs::new();
+        args.set_value("count", 3);
+        assert_eq!(fluent.tr("eo-tag", None).unwrap(), "eo");
+        assert_eq!(fluent.tr("en-tag", Some(&args)).unwrap(), "en 3");
+        fluent.clear_intl_cache();
+        assert_eq!(fluent.tr("eo-tag", None).unwrap(), "eo");
+
+        match fluent.add_function("TAG", |_, _| FluentValue::None) {
+            Err(Error::FluentError { errors, .. }) => assert_eq!(errors.len(), 1),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_arguments_fall_back_to_defaults() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from(
+                    "
+inbox = { $user } has { $count ->
+        [one] one message
+       *[other] { $count } messages
+    }
+    .default-user = Someone
+",
+                ),
+            )
+            .expect("text should load");
+        let plain = fluent.clone();
+        fluent.set_default_argument("inbox", "count", "1");
+        fluent.set_default_argument("inbox", "user", "Nobody");
+
+        assert_eq!(fluent.tr("inbox", None).unwrap(), "Someone has one message");
+        let mut args = FluentArgs::new();
+        args.set("user", "Ana");
+        args.set("count", 4);
+        assert_eq!(
+            fluent.tr("inbox", Some(&args)).unwrap(),
+            "Ana has 4 messages"
+        );
+        assert_eq!(
+            plain.tr("inbox", None).unwrap(),
+            "Someone has {$count} messages"
+        );
+    }
+
+    #[test]
+    fn missing_argument_policies() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("shared = { $user } shared { $file } with you"),
+            )
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("user", "Ana");
+
+        assert_eq!(
+            fluent.tr("shared", Some(&args)).unwrap(),
+            "Ana shared {$file} with you"
+        );
+        fluent.set_missing_arguments(MissingArguments::Empty);
+        assert_eq!(
+            fluent.tr("shared", Some(&args)).unwrap(),
+            "Ana shared  with you"
+        );
+        fluent.set_missing_arguments(MissingArguments::Marker(String::from("something")));
+        assert_eq!(
+            fluent.tr("shared", Some(&args)).unwrap(),
+            "Ana shared something with you"
+        );
+        assert_eq!(
+            fluent.tr("shared", None).unwrap(),
+            "something shared something with you"
+        );
+        fluent.set_missing_arguments(MissingArguments::Fail);
+        match fluent.tr("shared", Some(&args)) {
+            Err(Error::FormatError { errors, .. }) => assert_eq!(errors.len(), 1),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn terms_can_be_formatted_and_validated() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent.set_term_validation(true);
+        fluent
+            .add_many(vec![
+                (
+                    de_id.clone(),
+                    String::from(
+                        "
+-brand = { $case ->
+   *[nominative] Feuerfuchs
+    [genitive] Feuerfuchses
+}
+about = Über { -brand(case: \"genitive\") }
+",
+                    ),
+                ),
+                (
+                    en_id.clone(),
+                    String::from("-brand = Firefox\n-vendor = Mozilla"),
+                ),
+            ])
+            .expect("text should load");
+
+        assert_eq!(fluent.term("-brand", None).unwrap(), "Feuerfuchs");
+        let mut args = FluentArgs::new();
+        args.set("case", "genitive");
+        assert_eq!(fluent.term("brand", Some(&args)).unwrap(), "Feuerfuchses");
+        assert_eq!(fluent.term("vendor", None).unwrap(), "Mozilla");
+        assert!(fluent.term("-missing", None).is_err());
+
+        match fluent.add_from_text(de_id, String::from("made-by = Von { -vendor }")) {
+            Err(Error::UnknownTerms { lang, terms }) => {
+                assert_eq!(lang, "de".parse::<LanguageIdentifier>().unwrap());
+                assert_eq!(terms, vec![String::from("-vendor")]);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(fluent.tr("made-by", None).is_ok());
+
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("-greeting = Hi { $name }\n-marked = a\u{2068}b\u{2069}c"),
+            )
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("name", "Ana");
+        assert_eq!(fluent.term("greeting", Some(&args)).unwrap(), "Hi Ana");
+        assert_eq!(fluent.term("marked", None).unwrap(), "abc");
+        fluent.set_isolation(crate::Isolation::Arguments);
+        assert_eq!(
+            fluent.term("greeting", Some(&args)).unwrap(),
+            "Hi \u{2068}Ana\u{2069}"
+        );
+        assert_eq!(fluent.term("marked", None).unwrap(), "a\u{2068}b\u{2069}c");
+    }
+
+    #[test]
+    fn overrides_take_precedence_within_their_language() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from("cta = Sign up\nonly-en = Hello"),
+            )
+            .expect("text should load");
+        fluent
+            .add_from_text(de_id.clone(), String::from("cta = Registrieren"))
+            .expect("text should load");
+
+        assert!(fluent.tr("missing", None).is_err());
+        fluent.set_override("cta", &en_id, "Join now").unwrap();
+        assert_eq!(fluent.tr("cta", None).unwrap(), "Registrieren");
+        fluent.set_override("only-en", &en_id, "Hi\nthere").unwrap();
+        assert_eq!(fluent.tr("only-en", None).unwrap(), "Hi\nthere");
+        fluent.set_override("missing", &en_id, "Found").unwrap();
+        assert_eq!(fluent.tr("missing", None).unwrap(), "Found");
+        fluent
+            .set_override("cta", &de_id, "Jetzt beitreten")
+            .unwrap();
+        assert_eq!(fluent.tr("cta", None).unwrap(), "Jetzt beitreten");
+        assert!(fluent.set_override("cta", &de_id, "{ broken").is_err());
+        assert_eq!(fluent.tr("cta", None).unwrap(), "Jetzt beitreten");
+
+        let fluent = match fluent.freeze() {
+            Ok(_) => panic!("a translator with overrides should not freeze"),
+            Err(mut fluent) => {
+                fluent.clear_overrides();
+                fluent
+            }
+        };
+        assert_eq!(fluent.tr("cta", None).unwrap(), "Registrieren");
+        assert_eq!(fluent.tr("only-en", None).unwrap(), "Hello");
+        assert!(fluent.tr("missing", None).is_err());
+        assert!(fluent.freeze().is_ok());
+    }
+
+    #[test]
+    fn layers_stack_catalogs_per_language() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let languages = [de_id.clone(), en_id.clone()];
+        let mut product = FluentErgo::new(&languages);
+        product
+            .add_from_text(en_id.clone(), String::from("title = Acme\nsave = Save"))
+            .expect("text should load");
+        product
+            .add_from_text(de_id.clone(), String::from("save = Speichern"))
+            .expect("text should load");
+        let mut brand = FluentErgo::new(&languages);
+        brand
+            .add_from_text(
+                en_id.clone(),
+                String::from("title = Initech\n-brand = Initech"),
+            )
+            .expect("text should load");
+        let brand = brand.with_layer("product", &product);
+        let mut tenant = FluentErgo::new(&languages);
+        tenant
+            .add_from_text(en_id.clone(), String::from("save = Store"))
+            .expect("text should load");
+        let tenant = tenant
+            .with_layer("brand", &brand)
+            .with_layer("again", &product);
+
+        assert_eq!(tenant.layer_names(), vec!["brand", "product"]);
+        assert_eq!(tenant.tr("title", None).unwrap(), "Initech");
+        assert_eq!(tenant.tr("save", None).unwrap(), "Speichern");
+        assert_eq!(tenant.term("brand", None).unwrap(), "Initech");
+        assert!(tenant.tr("help", None).is_err());
+
+        product
+            .add_from_text(en_id, String::from("help = Help"))
+            .expect("text should load");
+        assert_eq!(tenant.tr("help", None).unwrap(), "Help");
+        assert!(tenant.freeze().is_err());
+    }
+
+    #[test]
+    fn resource_provider_is_asked_once_per_language() {
+        struct Flaky(std::sync::Arc<std::sync::Mutex<Vec<LanguageIdentifier>>>);
+
+        impl ResourceProvider for Flaky {
+            fn fetch(&self, lang: &LanguageIdentifier) -> Result<Vec<String>, Error> {
+                let mut asked = self.0.lock().unwrap();
+                asked.push(lang.clone());
+                if asked.len() == 1 {
+                    let missing = MissingMessage::new("down", &[], |_| None);
+                    return Err(Error::NoMatchingMessage(missing));
+                }
+                Ok(match lang.language.as_str() {
+                    "de" => vec![String::from("hello = Hallo")],
+                    _ => vec![String::from("hello = Hello\nbye = Bye")],
+                })
+            }
+        }
+
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        let asked = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        fluent.set_resource_provider(Flaky(std::sync::Arc::clone(&asked)));
+
+        assert!(fluent.tr("hello", None).is_err());
+        assert_eq!(fluent.tr("hello", None).unwrap(), "Hallo");
+        assert_eq!(fluent.tr("bye", None).unwrap(), "Bye");
+        assert_eq!(fluent.clone().tr("bye", None).unwrap(), "Bye");
+        assert_eq!(*asked.lock().unwrap(), vec![de_id.clone(), de_id, en_id]);
+        assert!(fluent.freeze().is_ok());
+    }
+
+    #[test]
+    fn slow_provider_only_holds_up_its_own_language() {
+        use std::sync::mpsc;
+
+        struct Slow {
+            started: std::sync::Mutex<mpsc::Sender<()>>,
+            release: std::sync::Mutex<mpsc::Receiver<()>>,
+        }
+
+        impl ResourceProvider for Slow {
+            fn fetch(&self, lang: &LanguageIdentifier) -> Result<Vec<String>, Error> {
+                if lang.language.as_str() != "de" {
+                    return Ok(vec![String::from("bye = Bye")]);
+                }
+                self.started.lock().unwrap().send(()).unwrap();
+                self.release.lock().unwrap().recv().unwrap();
+                Ok(vec![String::from("hello = Hallo")])
+            }
+        }
+
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id, en_id.clone()]);
+        let (started, on_start) = mpsc::channel();
+        let (release, on_release) = mpsc::channel();
+        fluent.set_resource_provider(Slow {
+            started: std::sync::Mutex::new(started),
+            release: std::sync::Mutex::new(on_release),
+        });
+        let english = fluent.with_languages(&[en_id]);
+        assert_eq!(english.tr("bye", None).unwrap(), "Bye");
+
+        let german = fluent.clone();
+        let waiting = std::thread::spawn(move || german.tr("hello", None).unwrap());
+        on_start.recv().unwrap();
+        assert_eq!(english.tr("bye", None).unwrap(), "Bye");
+        release.send(()).unwrap();
+        assert_eq!(waiting.join().unwrap(), "Hallo");
+        assert_eq!(fluent.tr("hello", None).unwrap(), "Hallo");
+    }
+
+    #[test]
+    fn warm_up_loads_and_counts_as_a_use() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent.set_intl_cache_limit(Some(2));
+        fluent
+            .add_lazy_from_text(de_id.clone(), String::from("items = { $count } Dinge"))
+            .unwrap();
+        fluent
+            .add_lazy_from_text(en_id.clone(), String::from("broken = {"))
+            .unwrap();
+
+        fluent.warm_up(&de_id).unwrap();
+        fluent
+            .warm_up(&"fr".parse::<LanguageIdentifier>().unwrap())
+            .unwrap();
+        assert_eq!(
+            fluent.intl.lock().unwrap().recent,
+            VecDeque::from(vec![de_id])
+        );
+        assert!(fluent.warm_up(&en_id).is_err());
+        assert!(fluent.warm_up(&en_id).is_ok());
+    }
+
+    #[test]
+    fn futures_are_send_and_finish_on_first_poll() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        fn poll_once<F: Future + Send + 'static>(future: F) -> F::Output {
+            let mut future = std::pin::pin!(future);
+            match future
+                .as_mut()
+                .poll(&mut Context::from_waker(Waker::noop()))
+            {
+                Poll::Ready(output) => output,
+                Poll::Pending => panic!("the future should not wait"),
+            }
+        }
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_lazy_from_text(en_id.clone(), String::from("greeting = Hello, { $name }"))
+            .unwrap();
+        poll_once(fluent.load_lazy_resources_async()).unwrap();
+        poll_once(fluent.warm_up_async(&en_id)).unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("name", String::from("Ana"));
+        let greeting = fluent.tr_async("greeting", Some(args));
+        drop(fluent);
+        assert_eq!(poll_once(greeting).unwrap(), "Hello, Ana");
+    }
+
+    #[test]
+    fn tr_into_appends_to_a_reused_buffer() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("greeting = Hello, { $name }!\nunits = Units"),
+            )
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("name", "Ana");
+
+        let mut out = String::with_capacity(64);
+        fluent.tr_into("greeting", Some(&args), &mut out).unwrap();
+        assert_eq!(out, "Hello, Ana!");
+        out.push(' ');
+        assert!(fluent.tr_into("missing", None, &mut out).is_err());
+        assert_eq!(out, "Hello, Ana! ");
+        fluent.tr_into("units", None, &mut out).unwrap();
+        assert_eq!(out, "Hello, Ana! Units");
+
+        let frozen = fluent.freeze().unwrap();
+        out.clear();
+        frozen.tr_into("greeting", Some(&args), &mut out).unwrap();
+        assert_eq!(out, "Hello, Ana!");
+        assert!(out.capacity() >= 64);
+    }
+
+    #[test]
+    fn literal_messages_match_formatted_ones() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from("plain = Save file\nlines =\n    First\n    Second\nkept = First"),
+            )
+            .expect("text should load");
+        assert!(fluent
+            .add_from_text(en_id.clone(), String::from("kept = Second"))
+            .is_err());
+        fluent
+            .add_from_text(en_id.clone(), String::from("marked = a\u{2068}b\u{2069}c"))
+            .expect("text should load");
+        let mut other = FluentErgo::new(std::slice::from_ref(&en_id));
+        other
+            .add_from_text(en_id, String::from("plain = Save { $name }"))
+            .expect("text should load");
+
+        assert_eq!(fluent.tr("plain", None).unwrap(), "Save file");
+        assert_eq!(fluent.tr("lines", None).unwrap(), "First\nSecond");
+        assert_eq!(fluent.tr("kept", None).unwrap(), "First");
+        let title = TrOptions {
+            case: Case::Title,
+            ..TrOptions::default()
+        };
+        assert_eq!(fluent.tr_opts("plain", None, &title).unwrap(), "Save File");
+        assert_eq!(fluent.tr("marked", None).unwrap(), "abc");
+        assert_eq!(&*fluent.tr_arc("marked", None).unwrap(), "abc");
+        let isolated = TrOptions {
+            isolation: Some(crate::Isolation::Arguments),
+            ..TrOptions::default()
+        };
+        assert_eq!(
+            fluent.tr_opts("marked", None, &isolated).unwrap(),
+            "a\u{2068}b\u{2069}c"
+        );
+
+        fluent.merge(&other, MergeConflicts::Replace).unwrap();
+        let mut args = FluentArgs::new();
+        args.set("name", "notes");
+        assert_eq!(fluent.tr("plain", Some(&args)).unwrap(), "Save notes");
+        drop(other);
+        let frozen = fluent.freeze().unwrap();
+        assert_eq!(frozen.tr("kept", None).unwrap(), "First");
+        assert_eq!(frozen.tr("plain", Some(&args)).unwrap(), "Save notes");
+        assert_eq!(frozen.tr("marked", None).unwrap(), "abc");
+    }
+
+    #[test]
+    fn tr_arc_interns_literal_messages() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (de_id, String::from("greeting = Hallo, { $name }")),
+                (en_id, String::from("greeting = Hello\nunits = Units")),
+            ])
+            .expect("text should load");
+        let mut args = FluentArgs::new();
+        args.set("name", "Ana");
+
+        let units = fluent.tr_arc("units", None).unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            &units,
+            &fluent.tr_arc("units", None).unwrap()
+        ));
+        assert_eq!(
+            &*fluent.tr_arc("greeting", Some(&args)).unwrap(),
+            "Hallo, Ana"
+        );
+        assert!(fluent.tr_arc("missing", None).is_err());
+
+        let frozen = fluent.freeze().unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            &units,
+            &frozen.tr_arc("units", None).unwrap()
+        ));
+        assert_eq!(
+            &*frozen.tr_arc("greeting", Some(&args)).unwrap(),
+            "Hallo, Ana"
+        );
+    }
+
+    #[test]
+    fn memory_report_counts_parsed_and_pending_resources() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        let en_text = "-brand = Acme\nsave = Save\nitems = { $n ->\n    [one] One item\n   *[other] { $n } items\n}";
+        fluent
+            .add_from_text(en_id.clone(), String::from(en_text))
+            .expect("text should load");
+        fluent
+            .add_lazy_from_text(de_id.clone(), String::from("save = Speichern"))
+            .unwrap();
+
+        let report = fluent.memory_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].lang, de_id);
+        assert_eq!(report[0].resources, 0);
+        assert_eq!(report[0].pending_bytes, "save = Speichern".len());
+        assert_eq!(report[1].lang, en_id);
+        assert_eq!((report[1].messages, report[1].terms), (2, 1));
+        assert_eq!(report[1].source_bytes, en_text.len());
+        assert!(report[1].estimated_heap_bytes > en_text.len());
+
+        fluent.load_lazy_resources().unwrap();
+        let report = fluent.memory_report();
+        assert_eq!((report[0].resources, report[0].pending_bytes), (1, 0));
+    }
+
+    #[test]
+    fn idle_languages_are_unloaded_and_reloaded() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (de_id.clone(), String::from("hello = Hallo")),
+                (en_id.clone(), String::from("only-en = English")),
+            ])
+            .expect("text should load");
+        fluent.set_unload_after(Some(Duration::from_millis(40)));
+        assert!(fluent.unload_idle_languages().is_empty());
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(fluent.tr("hello", None).unwrap(), "Hallo");
+        let report = fluent.memory_report();
+        assert_eq!((report[0].lang.clone(), report[0].resources), (de_id, 1));
+        assert_eq!((report[1].lang.clone(), report[1].resources), (en_id, 0));
+        assert_eq!(report[1].pending_bytes, "only-en = English".len());
+
+        assert_eq!(fluent.tr("only-en", None).unwrap(), "English");
+        assert_eq!(fluent.memory_report()[1].resources, 1);
+    }
+
+    #[test]
+    fn try_new_checks_the_fallback_list() {
+        let he_id = "he".parse::<LanguageIdentifier>().unwrap();
+        let iw_id = "iw".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+
+        match FluentErgo::try_new(&[]) {
+            Err(Error::InvalidLanguageList(LanguageListProblem::Empty)) => (),
+            _ => panic!("an empty list should be rejected"),
+        }
+        match FluentErgo::try_new(&[iw_id.clone(), en_id.clone()]) {
+            Err(Error::InvalidLanguageList(LanguageListProblem::NotCanonical(lang))) => {
+                assert_eq!(lang, iw_id)
+            }
+            _ => panic!("a deprecated code should be rejected"),
+        }
+        match FluentErgo::try_new(&[he_id.clone(), en_id.clone(), he_id.clone()]) {
+            Err(err @ Error::InvalidLanguageList(LanguageListProblem::Duplicate(_))) => {
+                assert_eq!(err.lang(), Some(&he_id))
+            }
+            _ => panic!("a repeated language should be rejected"),
+        }
+        assert!(FluentErgo::try_new(&[he_id.clone(), en_id.clone()]).is_ok());
+
+        let fluent =
+            FluentErgo::try_new_deduplicated(&[iw_id, en_id.clone(), he_id.clone()]).unwrap();
+        assert_eq!(fluent.languages, vec![he_id, en_id]);
+    }
+
+    #[test]
+    fn from_tags_names_the_invalid_tag() {
+        let fluent = FluentErgo::from_tags(&["eo", "en_US"]).unwrap();
+        assert_eq!(
+            fluent.languages,
+            vec![
+                "eo".parse::<LanguageIdentifier>().unwrap(),
+                "en-US".parse::<LanguageIdentifier>().unwrap()
+            ]
+        );
+        match FluentErgo::from_tags(&["eo", "en US"]) {
+            Err(Error::InvalidLanguage(tag)) => assert_eq!(tag, "en US"),
+            _ => panic!("the tag should not parse"),
+        }
+    }
+
+    #[test]
+    fn translators_build_from_pairs() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let fluent: FluentErgo = FluentErgo::try_from_iter(vec![
+            (en_id.clone(), String::from("bye = Goodbye")),
+            (eo_id.clone(), String::from("hello = Saluton")),
+            (en_id.clone(), String::from("hello = Hello")),
+        ])
+        .expect("text should load");
+        assert_eq!(fluent.languages, vec![en_id.clone(), eo_id]);
+        assert_eq!(fluent.tr("hello", None).unwrap(), "Hello");
+        assert_eq!(fluent.tr("bye", None).unwrap(), "Goodbye");
+
+        match FluentErgo::<SingleThreaded>::try_from_iter(vec![(en_id, String::from("bye ="))]) {
+            Err(Error::FluentParserError { .. }) => (),
+            _ => panic!("the resource should not parse"),
+        }
+    }
+
+    #[test]
+    fn at_falls_back_to_the_message_id() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("units = Units\ngreeting = Hello, { $name }"),
+            )
+            .unwrap();
+        assert_eq!(fluent.at("units"), "Units");
+        assert_eq!(fluent.at("greeting"), "Hello, {$name}");
+        assert_eq!(fluent.at("swimming"), "swimming");
+        assert_eq!(fluent.freeze().unwrap().at("swimming"), "swimming");
+    }
+
+    #[test]
+    fn simple_fluent_reports_errors_and_falls_back() {
+        use crate::prelude::SimpleFluent;
+
+        let reported = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut fluent = SimpleFluent::new(&["en", "not a tag"]);
+        assert_eq!(fluent.translator().languages.len(), 1);
+        let sink = std::sync::Arc::clone(&reported);
+        fluent.on_error(move |err| sink.lock().unwrap().push(err.to_string()));
+
+        fluent.add_text("en", "units = Units");
+        fluent.add_text("en", "broken =");
+        assert_eq!(fluent.tr("units"), "Units");
+        assert_eq!(fluent.tr("swimming"), "swimming");
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 2);
+        assert!(reported[0].starts_with("Fluent Parser Error in en"));
+        assert!(reported[1].starts_with("No matching message for swimming"));
+    }
+
+    #[test]
+    fn debug_output_shows_what_is_loaded() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .unwrap();
+        fluent
+            .add_lazy_from_text(eo_id.clone(), String::from(EO_TRANSLATIONS))
+            .unwrap();
+
+        let stats = fluent.stats();
+        assert_eq!(stats.languages, vec![eo_id.clone(), en_id.clone()]);
+        assert_eq!(stats.bundles[0].lang, en_id);
+        assert_eq!(stats.bundles[0].messages, 4);
+        assert_eq!(
+            (stats.bundles[1].resources, stats.bundles[1].pending),
+            (0, 1)
+        );
+        assert_eq!(
+            format!("{:?}", fluent),
+            "FluentErgo { languages: [eo, en-US], bundles: {en-US: 4 messages in 1 resources, 0 pending, eo: 0 messages in 0 resources, 1 pending} }"
+        );
+
+        fluent.load_lazy_resources().unwrap();
+        assert_eq!(
+            format!("{:?}", fluent.freeze().unwrap()),
+            "FrozenFluentErgo { languages: [eo, en-US], loaded: [en-US, eo] }"
+        );
+    }
+
+    #[test]
+    fn diagnostics_describe_each_language() {
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("-brand = Foo\nhello = Hello\nbye = Bye"),
+            )
+            .unwrap();
+        fluent
+            .add_lazy_from_text(de_id, String::from("hello = Hallo"))
+            .unwrap();
+
+        let diagnostics = fluent.diagnostics();
+        assert_eq!(diagnostics.languages, vec!["eo", "en"]);
+        assert_eq!(diagnostics.bundles.len(), 2);
+        let de = &diagnostics.bundles[0];
+        assert_eq!((de.lang.as_str(), de.searched), ("de", false));
+        assert_eq!(de.origins.len(), 1);
+        assert!(!de.origins[0].loaded && de.origins[0].path.is_none());
+        assert_eq!(de.loaded_at, None);
+        let en = &diagnostics.bundles[1];
+        assert_eq!((en.messages, en.terms), (2, 1));
+        assert!(en.searched && en.origins[0].loaded);
+        assert_eq!(diagnostics.last_reload, en.loaded_at);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_value(&diagnostics).unwrap();
+            assert_eq!(json["bundles"][1]["lang"], "en");
+            assert_eq!(json["bundles"][0]["origins"][0]["loaded"], false);
+        }
+    }
+
+    #[test]
+    fn origin_of_follows_the_definition_in_use() {
+        use super::Origin;
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_labeled_text(en_id.clone(), "base", String::from("a = A\nb = B"))
+            .unwrap();
+        fluent
+            .add_from_text(en_id.clone(), String::from("c = C"))
+            .unwrap();
+        let mut patch = FluentErgo::new(std::slice::from_ref(&en_id));
+        patch
+            .add_labeled_text(en_id.clone(), "patch", String::from("b = Bee"))
+            .unwrap();
+        fluent.merge(&patch, MergeConflicts::Replace).unwrap();
+        fluent.set_override("c", &en_id, "See").unwrap();
+
+        let label = |label: &str| Some(Origin::Label(String::from(label)));
+        assert_eq!(fluent.origin_of("a", &en_id), label("base"));
+        assert_eq!(fluent.origin_of("b", &en_id), label("patch"));
+        assert_eq!(fluent.origin_of("c", &en_id), Some(Origin::Override));
+        assert_eq!(fluent.origin_of("d", &en_id), None);
+
+        let mut plain = FluentErgo::new(std::slice::from_ref(&en_id));
+        plain
+            .add_from_text(en_id.clone(), String::from("c = C"))
+            .unwrap();
+        assert_eq!(plain.origin_of("c", &en_id), Some(Origin::Text));
+    }
+
+    #[test]
+    fn duplicate_messages_follow_the_policy() {
+        use super::{DuplicateMessages, Origin};
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let base = || {
+            let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+            fluent
+                .add_labeled_text(en_id.clone(), "base", String::from("-brand = Foo\na = A"))
+                .unwrap();
+            fluent
+        };
+        let extra = String::from("-brand = Bar\na = Ay\nb = Bee");
+
+        let mut fluent = base();
+        match fluent.add_labeled_text(en_id.clone(), "extra", extra.clone()) {
+            Err(err @ Error::DuplicateMessages { .. }) => {
+                assert_eq!(
+                    err.to_string(),
+                    "Defined twice in en: -brand (kept base, ignored extra), a (kept base, ignored extra)"
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(fluent.tr("a", None).unwrap(), "A");
+        assert_eq!(fluent.tr("b", None).unwrap(), "Bee");
+
+        let mut fluent = base();
+        fluent.set_duplicate_messages(DuplicateMessages::FirstWins);
+        fluent
+            .add_labeled_text(en_id.clone(), "extra", extra.clone())
+            .unwrap();
+        assert_eq!(fluent.tr("a", None).unwrap(), "A");
+
+        let mut fluent = base();
+        fluent.set_duplicate_messages(DuplicateMessages::LastWins);
+        fluent
+            .add_labeled_text(en_id.clone(), "extra", extra)
+            .unwrap();
+        assert_eq!(fluent.tr("a", None).unwrap(), "Ay");
+        assert_eq!(
+            fluent.origin_of("-brand", &en_id),
+            Some(Origin::Label(String::from("extra")))
+        );
+    }
+
+    #[test]
+    fn lints_flag_escaped_braces_and_uneven_variants() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        let lints = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let found = std::sync::Arc::clone(&lints);
+        fluent.set_lint_handler(None, move |lint| {
+            found.lock().unwrap().push(lint.to_string())
+        });
+        let text = String::from(
+            "
+hello = Hello, { \"{\" }name{ \"}\" }
+items = { $count ->
+    [one] one item{ \" \" }
+   *[other] { $count } items
+} left
+fine = { $count ->
+    [one] one
+   *[other] many
+}
+",
+        );
+        fluent.add_from_text(en_id.clone(), text.clone()).unwrap();
+        fluent.add_from_text(en_id, text).unwrap();
+
+        assert_eq!(
+            *lints.lock().unwrap(),
+            vec![
+                "hello in en: string literal \"{\" looks like an escaped placeable",
+                "hello in en: string literal \"}\" looks like an escaped placeable",
+                "items in en: variant [one] ends in whitespace, but other variants do not",
+            ]
+        );
+    }
+
+    #[test]
+    fn placeholder_mismatches_are_reported_per_language() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (
+                    en_id.clone(),
+                    String::from(
+                        "
+items = { $count ->
+    [one] One item in { $place }
+   *[other] { $count } items in { $place }
+}
+hi = Hi, { $name }
+    .title = { $title }
+",
+                    ),
+                ),
+                (
+                    de_id.clone(),
+                    String::from("items = { $count } Dinge in { $place }\nhi = Hallo\n    .title = { $title }"),
+                ),
+                (
+                    eo_id.clone(),
+                    String::from("items = { $count } aĵoj en { $loko }\nextra = { $x }"),
+                ),
+            ])
+            .unwrap();
+
+        let mismatches = fluent.placeholder_mismatches(&en_id);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(
+            (&mismatches[0].lang, mismatches[0].id.as_str()),
+            (&de_id, "hi")
+        );
+        assert_eq!(mismatches[0].missing, vec!["name"]);
+        assert!(mismatches[0].extra.is_empty());
+        assert_eq!(
+            (&mismatches[1].lang, mismatches[1].id.as_str()),
+            (&eo_id, "items")
+        );
+        assert_eq!(mismatches[1].missing, vec!["place"]);
+        assert_eq!(mismatches[1].extra, vec!["loko"]);
+    }
+
+    #[test]
+    fn length_report_uses_sample_args() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let fi_id = "fi".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[fi_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from("hi = Hi, { $name }\nok = OK\nonly-en = Only"),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(
+                fi_id.clone(),
+                String::from("hi = Hei, { $name }\nok = Selvä"),
+            )
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("name", "Anna");
+        let report = fluent.length_report(&en_id, Some(&args), 1.5);
+        assert_eq!(report.len(), 2);
+        assert_eq!(
+            (
+                report[0].id.as_str(),
+                report[0].reference_len,
+                report[0].len
+            ),
+            ("hi", 8, 9)
+        );
+        assert!(!report[0].too_long);
+        assert_eq!(
+            (
+                report[1].id.as_str(),
+                report[1].reference_len,
+                report[1].len
+            ),
+            ("ok", 2, 5)
+        );
+        assert!(report[1].too_long);
+        assert!(report.iter().all(|length| length.lang == fi_id));
+    }
+
+    #[test]
+    fn legacy_syntax_is_only_upgraded_where_it_does_not_parse() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let text = String::from(
+            "
+[[ Branding ]]
+-brand =
+    {
+       *[nominative] Aurora
+        [genitive] Aurora's
+    }
+plain = { -brand }
+about = { -brand[genitive] } settings
+dashes = Not a -brand[genitive] reference
+",
+        );
+
+        let mut strict = FluentErgo::new(std::slice::from_ref(&en_id));
+        assert!(strict.add_from_text(en_id.clone(), text.clone()).is_err());
+
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent.set_legacy_syntax(true);
+        fluent.add_from_text(en_id, text).unwrap();
+        assert_eq!(fluent.tr("plain", None).unwrap(), "Aurora");
+        assert_eq!(fluent.tr("about", None).unwrap(), "Aurora's settings");
+        assert_eq!(
+            fluent.tr("dashes", None).unwrap(),
+            "Not a -brand[genitive] reference"
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn add_from_file_chunked_splits_on_message_boundaries() {
+        let path =
+            std::env::temp_dir().join(format!("fluent-ergo-chunked-{}.ftl", std::process::id()));
+        std::fs::write(
+            &path,
+            "
+about = About { -brand }
+# The brand is defined after its first use.
+-brand = Aurora
+items = { $count ->
+    [one] One item
+   *[other] { $count } items
+}
+",
+        )
+        .unwrap();
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent.set_term_validation(true);
+        let result = fluent.add_from_file_chunked(en_id, &path, 1);
+        std::fs::remove_file(&path).unwrap();
+
+        result.expect("file should load");
+        assert_eq!(fluent.stats().bundles[0].resources, 3);
+        assert_eq!(fluent.tr("about", None).unwrap(), "About Aurora");
+        let mut args = FluentArgs::new();
+        args.set("count", 2);
+        assert_eq!(fluent.tr("items", Some(&args)).unwrap(), "2 items");
+    }
+
+    /// Build a ZIP archive of (name, contents, deflate) files.
+    #[cfg(feature = "archive")]
+    fn zip_archive(files: &[(&str, &str, bool)]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut zip = Vec::new();
+        let mut directory = Vec::new();
+        for (name, contents, deflate) in files {
+            let data = if *deflate {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(contents.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                contents.as_bytes().to_vec()
+            };
+            let method: u16 = if *deflate { 8 } else { 0 };
+            let mut fields = Vec::new();
+            fields.extend_from_slice(&[20, 0, 0, 0]);
+            fields.extend_from_slice(&method.to_le_bytes());
+            fields.extend_from_slice(&[0; 4]);
+            fields.extend_from_slice(&crc32fast::hash(contents.as_bytes()).to_le_bytes());
+            fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fields.extend_from_slice(&[0, 0]);
+
+            directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            directory.extend_from_slice(&[20, 0]);
+            directory.extend_from_slice(&fields);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&(zip.len() as u32).to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+
+            zip.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            zip.extend_from_slice(&fields);
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(&data);
+        }
+        let directory_offset = zip.len() as u32;
+        zip.extend_from_slice(&directory);
+        zip.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        zip.extend_from_slice(&[0; 4]);
+        zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&directory_offset.to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+        zip
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn add_from_archive_loads_stored_and_deflated_files() {
+        let zip = zip_archive(&[
+            ("de/main.ftl", "units = Einheiten", false),
+            ("eo/main.ftl", EO_TRANSLATIONS, true),
+            ("readme.txt", "not = ftl", false),
+        ]);
+        let path =
+            std::env::temp_dir().join(format!("fluent-ergo-pack-{}.zip", std::process::id()));
+        std::fs::write(&path, &zip).unwrap();
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), eo_id]);
+        let result = fluent.add_from_archive(&path);
+        std::fs::write(&path, b"not a zip").unwrap();
+        let garbage = fluent.add_from_archive(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        result.expect("archive should load");
+        assert_eq!(fluent.tr("units", None).unwrap(), "Einheiten");
+        assert_eq!(fluent.tr("history", None).unwrap(), "Historio");
+        assert!(fluent.tr("not", None).is_err());
+        assert_eq!(
+            fluent.origin_of("units", &de_id),
+            Some(crate::Origin::Archive {
+                archive: path.clone(),
+                entry: String::from("de/main.ftl")
+            })
+        );
+        assert!(matches!(garbage, Err(Error::IOError { .. })));
+    }
+
+    #[cfg(feature = "signed")]
+    #[test]
+    fn signed_archives_only_load_with_a_trusted_signature() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let zip = zip_archive(&[("de/main.ftl", "units = Einheiten", true)]);
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let signature = pair.sign(&zip);
+        let mut key = [0; 32];
+        key.copy_from_slice(pair.public_key().as_ref());
+        let path =
+            std::env::temp_dir().join(format!("fluent-ergo-signed-{}.zip", std::process::id()));
+
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&de_id));
+        let mut tampered = zip.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, &tampered).unwrap();
+        let rejected = fluent.add_from_signed_archive(&path, signature.as_ref(), &[key]);
+        std::fs::write(&path, &zip).unwrap();
+        let untrusted = fluent.add_from_signed_archive(&path, signature.as_ref(), &[[1; 32]]);
+        let accepted = fluent.add_from_signed_archive(&path, signature.as_ref(), &[[1; 32], key]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(rejected, Err(Error::InvalidSignature { .. })));
+        assert!(matches!(untrusted, Err(Error::InvalidSignature { .. })));
+        accepted.expect("signed archive should load");
+        assert_eq!(fluent.tr("units", None).unwrap(), "Einheiten");
+    }
+
+    #[test]
+    fn apply_delta_is_all_or_nothing() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (en_id.clone(), String::from(EN_TRANSLATIONS)),
+                (eo_id.clone(), String::from(EO_TRANSLATIONS)),
+            ])
+            .unwrap();
+        let reader = fluent.clone();
+
+        let broken = fluent.apply_delta(vec![
+            LanguageDelta {
+                lang: eo_id.clone(),
+                changed: String::from("history = Historio de foliumado"),
+                removed: vec![],
+            },
+            LanguageDelta {
+                lang: en_id.clone(),
+                changed: String::from("preferences = {"),
+                removed: vec![],
+            },
+        ]);
+        assert!(broken.is_err());
+        assert_eq!(reader.tr("history", None).unwrap(), "Historio");
+
+        fluent
+            .apply_delta(vec![
+                LanguageDelta {
+                    lang: eo_id,
+                    changed: String::from("history = Historio de foliumado"),
+                    removed: vec![],
+                },
+                LanguageDelta {
+                    lang: en_id,
+                    changed: String::from("-brand = Aurora\nabout = About { -brand }"),
+                    removed: vec![String::from("preferences")],
+                },
+            ])
+            .unwrap();
+        assert_eq!(reader.tr("history", None).unwrap(), "Historio de foliumado");
+        assert_eq!(reader.tr("about", None).unwrap(), "About Aurora");
+        assert!(reader.tr("preferences", None).is_err());
+        assert_eq!(
+            reader.tr("time_display", None).unwrap(),
+            "{$time} during the day"
+        );
+    }
+
+    #[test]
+    fn message_fingerprints_ignore_formatting_and_follow_references() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let fingerprint = |text: &str, msgid: &str| {
+            let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+            fluent
+                .add_from_text(en_id.clone(), String::from(text))
+                .unwrap();
+            fluent.message_fingerprint(msgid, &en_id)
+        };
+
+        let plain = fingerprint("-brand = Aurora\nabout = About {-brand}", "about");
+        assert_eq!(
+            plain,
+            fingerprint(
+                "# The about box\nabout = About { -brand }\n\n-brand = Aurora",
+                "about"
+            )
+        );
+        assert_ne!(
+            plain,
+            fingerprint("-brand = Aurora Browser\nabout = About { -brand }", "about")
+        );
+        // Fingerprints may be stored, so a change to how they are computed must be deliberate.
+        assert_eq!(plain, Some(15597663636870732538));
+        assert_eq!(fingerprint("about = About", "missing"), None);
+    }
+
+    #[test]
+    fn observers_see_frozen_translations() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id.clone(), String::from(EN_TRANSLATIONS))
+            .unwrap();
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observed = std::sync::Arc::clone(&seen);
+        fluent.set_observer(move |msgid, lang, _| {
+            observed
+                .lock()
+                .unwrap()
+                .push((String::from(msgid), lang.clone()))
+        });
+        fluent.tr("history", None).unwrap();
+
+        let frozen = fluent.freeze().expect("freezing should succeed");
+        frozen.tr("preferences", None).unwrap();
+        assert!(frozen.tr("swimming", None).is_err());
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (String::from("history"), en_id.clone()),
+                (String::from("preferences"), en_id),
+            ]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn loading_and_translating_are_traced() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+
+        /// Records each span's name and fields as text, and which spans have been entered.
+        #[derive(Default)]
+        struct Spans(
+            Mutex<Vec<(String, &'static tracing::Metadata<'static>)>>,
+            Mutex<Vec<u64>>,
+        );
+
+        struct Fields<'a>(&'a mut String);
+
+        impl Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push_str(&format!(" {}={:?}", field.name(), value));
+            }
+        }
+
+        impl tracing::Subscriber for Spans {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.0.lock().unwrap();
+                let mut text = String::from(span.metadata().name());
+                span.record(&mut Fields(&mut text));
+                spans.push((text, span.metadata()));
+                Id::from_u64(spans.len() as u64)
+            }
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.0.lock().unwrap();
+                values.record(&mut Fields(&mut spans[span.into_u64() as usize - 1].0));
+            }
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &tracing::Event<'_>) {}
+            fn enter(&self, span: &Id) {
+                self.1.lock().unwrap().push(span.into_u64());
+            }
+            fn exit(&self, _: &Id) {
+                self.1.lock().unwrap().pop();
+            }
+            fn current_span(&self) -> tracing_core::span::Current {
+                match self.1.lock().unwrap().last() {
+                    Some(id) => tracing_core::span::Current::new(
+                        Id::from_u64(*id),
+                        self.0.lock().unwrap()[*id as usize - 1].1,
+                    ),
+                    None => tracing_core::span::Current::none(),
+                }
+            }
+        }
+
+        let spans = Arc::new(Spans::default());
+        tracing::subscriber::with_default(Arc::clone(&spans), || {
+            let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+            let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+            let mut fluent = FluentErgo::new(&[eo_id, en_id.clone()]);
+            fluent
+                .add_from_text(en_id, String::from(EN_TRANSLATIONS))
+                .unwrap();
+            fluent.tr("preferences", None).unwrap();
+            fluent.tr("time_display", None).unwrap();
+        });
+        let spans: Vec<String> = spans
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(text, _)| text.clone())
+            .collect();
+        assert!(spans.iter().any(|span| span.starts_with("parse ")));
+        assert!(spans.contains(&String::from("add resources=1")));
+        assert!(spans.contains(&String::from("tr msgid=\"preferences\" lang=en depth=1")));
+        assert!(spans
+            .iter()
+            .any(|span| span.starts_with("tr msgid=\"time_display\" errors=[")));
+    }
+
+    #[test]
+    fn hostile_input_does_not_panic() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        for text in [
+            "= = =\n\u{0}\u{feff}",
+            "open = { { { {",
+            "-self = { -self }\nloop = { loop } { -self } { -self(case: \"x\") }",
+            "count = { $count ->\n *[one] { NUMBER($count, minimumFractionDigits: 999) }\n}",
+        ] {
+            let _ = fluent.add_from_text(en_id.clone(), String::from(text));
+        }
+        let mut args = FluentArgs::new();
+        args.set("count", FluentValue::from(f64::NAN));
+        for msgid in ["loop", "open", "count", "", "-self", "count.nope"] {
+            let _ = fluent.tr(msgid, Some(&args));
+        }
+
+        struct Panicking;
+        impl ResourceProvider for Panicking {
+            fn fetch(&self, _: &LanguageIdentifier) -> Result<Vec<String>, Error> {
+                panic!("the provider has a bug")
+            }
+        }
+        let mut poisoned = FluentErgo::new(std::slice::from_ref(&en_id));
+        poisoned.set_resource_provider(Panicking);
+        let clone = poisoned.clone();
+        assert!(std::thread::spawn(move || clone.tr("history", None))
+            .join()
+            .is_err());
+        assert!(matches!(poisoned.tr("history", None), Err(Error::Poisoned)));
+        assert_eq!(poisoned.tr_opt("history", None), None);
+        assert_eq!(poisoned.stats().bundles.len(), 0);
+    }
+
+    #[test]
+    fn evaluation_limits_stop_pathological_messages() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from(
+                    "-a = A\nb = { -a }\nc = { b }\nd = { c }\necho = { $text }\nlong = Far too long",
+                ),
+            )
+            .unwrap();
+        fluent.set_evaluation_limits(EvaluationLimits {
+            max_depth: Some(3),
+            max_len: Some(8),
+            ..EvaluationLimits::default()
+        });
+        let over = |result: Result<String, Error>| match result {
+            Err(Error::LimitExceeded { limit, .. }) => Some(limit),
+            _ => None,
+        };
+        let mut huge = FluentArgs::new();
+        huge.set("text", "x".repeat(1 << 20));
+
+        assert_eq!(fluent.tr("c", None).unwrap(), "A");
+        assert_eq!(over(fluent.tr("d", None)), Some(Limit::Depth));
+        assert_eq!(over(fluent.tr("echo", Some(&huge))), Some(Limit::Length));
+        assert_eq!(over(fluent.tr("long", None)), Some(Limit::Length));
+        assert!(fluent.tr_arc("long", None).is_err());
+
+        let frozen = fluent.freeze().unwrap();
+        assert_eq!(frozen.tr("c", None).unwrap(), "A");
+        assert_eq!(over(frozen.tr("d", None)), Some(Limit::Depth));
+        assert_eq!(over(frozen.tr("echo", Some(&huge))), Some(Limit::Length));
+        assert!(frozen.tr_arc("long", None).is_err());
+    }
+
+    #[test]
+    fn evaluation_limits_stop_long_reference_chains() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        let chain: String = (0..50_000)
+            .map(|i| format!("m{} = {{ m{} }}\n", i, i + 1))
+            .collect();
+        fluent.add_from_text(en_id, chain + "m50000 = End").unwrap();
+        let over = |result: Result<String, Error>| match result {
+            Err(Error::LimitExceeded { limit, .. }) => Some(limit),
+            _ => None,
+        };
+
+        fluent.set_evaluation_limits(EvaluationLimits {
+            max_placeables: Some(10),
+            ..EvaluationLimits::default()
+        });
+        assert_eq!(over(fluent.tr("m0", None)), Some(Limit::Placeables));
+        fluent.set_evaluation_limits(EvaluationLimits {
+            max_depth: Some(10),
+            ..EvaluationLimits::default()
+        });
+        assert_eq!(over(fluent.tr("m0", None)), Some(Limit::Depth));
+        assert_eq!(fluent.tr("m49991", None).unwrap(), "End");
+
+        let frozen = fluent.freeze().unwrap();
+        assert_eq!(over(frozen.tr("m0", None)), Some(Limit::Depth));
+        assert_eq!(over(frozen.tr("m49990", None)), Some(Limit::Depth));
+        assert_eq!(frozen.tr("m49991", None).unwrap(), "End");
+    }
+
+    #[test]
+    fn suggest_ranks_similar_source_texts() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from(
+                    "history = Show history\nhistory-all = Show { $count } history items\n\
+                     clear = Clear the whole history\nnew = Show all history",
+                ),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(
+                eo_id.clone(),
+                String::from("history = Montri historion\nhistory-all = Montri { $count } erojn"),
+            )
+            .unwrap();
+
+        let suggestions = fluent.suggest("new", &eo_id);
+        let ids: Vec<&str> = suggestions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["history", "history-all"]);
+        assert_eq!(suggestions[1].source, "Show { $count } history items");
+        assert_eq!(suggestions[1].translation, "Montri { $count } erojn");
+        assert!(suggestions[0].score > suggestions[1].score);
+        assert!(fluent.suggest("missing", &eo_id).is_empty());
+        assert!(fluent.suggest("new", &en_id).is_empty());
+    }
+
+    #[test]
+    fn editing_session_previews_and_exports() {
+        let en_id = "en-US".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from(
+                    "history = History\n    .title = Past pages\npreferences = Preferences",
+                ),
+            )
+            .unwrap();
+        let app = fluent.clone();
+
+        let mut session = fluent.edit(&en_id);
+        session.set("history", "Past { $count } pages").unwrap();
+        session.set("preferences", "Settings").unwrap();
+        session.set("about", "About").unwrap();
+        assert!(session.set("broken", "{ $").is_err());
+        let mut args = FluentArgs::new();
+        args.set("count", 3);
+        assert_eq!(app.tr("history", Some(&args)).unwrap(), "Past 3 pages");
+        assert_eq!(app.tr("about", None).unwrap(), "About");
+
+        session.revert("preferences").unwrap();
+        assert_eq!(app.tr("preferences", None).unwrap(), "Preferences");
+        assert_eq!(
+            session.export().unwrap(),
+            "history = Past { $count } pages\n    .title = Past pages\npreferences = Preferences\nabout = About\n"
+        );
+        assert_eq!(session.edits().len(), 2);
+
+        session.discard().unwrap();
+        assert_eq!(app.tr("history", None).unwrap(), "History");
+        assert!(app.tr("about", None).is_err());
+    }
+
+    #[test]
+    fn review_export_lists_every_row() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
         fluent
-            .add_from_text(en_id, String::from(EN_TRANSLATIONS))
-            .expect("text should load");
+            .add_from_text(
+                en_id.clone(),
+                String::from(
+                    "-brand = Aurora\n\
+                     # Shown in \"Settings\"\n\
+                     # twice\n\
+                     history = History\n    .title = Recent { -brand } pages\n\
+                     ok = OK",
+                ),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(
+                eo_id.clone(),
+                String::from("-brand = Aurora\nhistory = Historio\nok = Bone\nold = Malnova"),
+            )
+            .unwrap();
+
+        assert_eq!(
+            fluent.export_review(&en_id, &eo_id).unwrap(),
+            "id,comment,source,target,status\n\
+             -brand,,Aurora,Aurora,unchanged\n\
+             history,\"Shown in \"\"Settings\"\"\ntwice\",History,Historio,translated\n\
+             history.title,\"Shown in \"\"Settings\"\"\ntwice\",Recent { -brand } pages,,missing\n\
+             ok,,OK,Bone,translated\n\
+             old,,,Malnova,obsolete\n"
+        );
+    }
+
+    #[test]
+    fn review_import_applies_edited_rows() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from(
+                    "history = History\n    .title = Recent pages\n\
+                     greet = Hello, { $name }\n\
+                     ok = OK",
+                ),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(
+                eo_id.clone(),
+                String::from("history = Historio\nok = Bone\nold = Malnova"),
+            )
+            .unwrap();
+
+        let csv = fluent
+            .export_review(&en_id, &eo_id)
+            .unwrap()
+            .replace(
+                ",Recent pages,,missing",
+                ",Recent pages,Lastaj paĝoj,translated",
+            )
+            .replace(",OK,Bone,", ",OK,\"Bone,\nvere\",")
+            .replace(",,missing", ",\"Saluton, { $nomo }\",translated")
+            .replace(",Malnova,", ",Forigita,")
+            .replace('\n', "\r\n");
+        let mismatches = fluent.import_review(&en_id, &eo_id, &csv).unwrap();
+
+        assert_eq!(
+            mismatches,
+            vec![crate::PlaceholderMismatch {
+                lang: eo_id.clone(),
+                id: String::from("greet"),
+                missing: vec![String::from("name")],
+                extra: vec![String::from("nomo")],
+            }]
+        );
+        assert!(fluent
+            .export_review(&en_id, &eo_id)
+            .unwrap()
+            .contains("history.title,,Recent pages,Lastaj paĝoj,translated\n"));
+        assert_eq!(fluent.tr("history", None).unwrap(), "Historio");
+        assert_eq!(fluent.tr("ok", None).unwrap(), "Bone,\nvere");
+        assert_eq!(fluent.tr("old", None).unwrap(), "Malnova");
+        assert!(fluent.tr("greet", None).unwrap().starts_with("Hello"));
+
+        assert!(matches!(
+            fluent.import_review(&en_id, &eo_id, "id,target\nok,\"Bone"),
+            Err(Error::IOError { .. })
+        ));
+        assert!(matches!(
+            fluent.import_review(&en_id, &eo_id, "id,source\nok,OK"),
+            Err(Error::IOError { .. })
+        ));
+    }
+
+    #[cfg(feature = "icu")]
+    #[test]
+    fn language_display_names_come_from_the_cldr() {
+        let name = |lang: &str, in_locale: &str| {
+            crate::language_display_name(&lang.parse().unwrap(), &in_locale.parse().unwrap())
+        };
+        assert_eq!(name("eo", "eo"), "Esperanto");
+        assert_eq!(name("ja", "ja"), "日本語");
+        assert_eq!(name("de", "fr"), "allemand");
+        assert_eq!(name("de-CH", "de"), "Schweizer Hochdeutsch");
+        assert_eq!(name("pt-BR", "en"), "Brazilian Portuguese");
+        assert_eq!(name("qaa", "en"), "qaa");
+    }
+
+    #[test]
+    fn regional_fallback_layers_regional_catalogs_over_the_base_language() {
+        let pt_id = "pt".parse::<LanguageIdentifier>().unwrap();
+        let pt_br_id = "pt-BR".parse::<LanguageIdentifier>().unwrap();
+        let pt_pt_id = "pt-PT".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[pt_br_id.clone(), en_id.clone()]);
+        fluent.set_unknown_languages(UnknownLanguages::Reject);
+        assert!(fluent
+            .add_from_text(pt_id.clone(), String::from("save = Salvar"))
+            .is_err());
+
+        fluent.set_regional_fallback(true);
+        assert_eq!(
+            fluent.languages,
+            [pt_br_id.clone(), pt_id.clone(), en_id.clone()]
+        );
+        fluent
+            .add_from_text(
+                pt_id,
+                String::from("save = Salvar\nscreen = Ecrã\nfile = Ficheiro"),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(pt_br_id, String::from("screen = Tela\nfile = Arquivo"))
+            .unwrap();
+        fluent
+            .add_from_text(pt_pt_id.clone(), String::from("file = Ficheiro"))
+            .unwrap_err();
+        fluent
+            .add_from_text(en_id.clone(), String::from("quit = Quit"))
+            .unwrap();
+        assert_eq!(fluent.tr("screen", None).unwrap(), "Tela");
+        assert_eq!(fluent.tr("save", None).unwrap(), "Salvar");
+        assert_eq!(fluent.tr("quit", None).unwrap(), "Quit");
+
+        let portugal = fluent.with_languages(&[pt_pt_id, en_id]);
+        assert_eq!(portugal.tr("screen", None).unwrap(), "Ecrã");
+        assert_eq!(portugal.tr("file", None).unwrap(), "Ficheiro");
+
+        let hant = "zh-Hant-TW".parse::<LanguageIdentifier>().unwrap();
+        assert_eq!(
+            crate::language::with_parents(&[hant])
+                .iter()
+                .map(|lang| lang.to_string())
+                .collect::<Vec<_>>(),
+            ["zh-Hant-TW", "zh-Hant", "zh"]
+        );
+    }
+
+    #[test]
+    fn text_direction_follows_the_script() {
+        let rtl = |tag: &str| crate::is_rtl(&tag.parse().unwrap());
+        assert!(rtl("ar"));
+        assert!(rtl("fa-IR"));
+        assert!(rtl("iw"));
+        assert!(rtl("ks"));
+        assert!(!rtl("ks-Deva"));
+        assert!(rtl("az-Arab"));
+        assert!(!rtl("az"));
+        assert!(!rtl("mn-Mong"));
+        assert!(!rtl("ja"));
+
+        let fluent = FluentErgo::from_tags(&["en-US", "ar"]).unwrap();
+        assert_eq!(
+            fluent.text_direction_of_current(),
+            crate::TextDirection::LeftToRight
+        );
+        let fluent = fluent.with_languages(&["ur".parse().unwrap()]);
+        assert_eq!(
+            fluent.text_direction_of_current(),
+            crate::TextDirection::RightToLeft
+        );
+        assert_eq!(
+            FluentErgo::new(&[]).text_direction_of_current(),
+            crate::TextDirection::LeftToRight
+        );
+    }
+
+    #[test]
+    fn isolation_modes_wrap_what_they_say() {
+        use crate::Isolation;
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from(
+                    "-brand = Aurora\n\
+                     said = { $name } said: { -brand } \u{2068}hi\u{2069} { $count } \
+                     { $count ->\n [one] time\n *[other] times\n }\n\
+                     name = { $name }",
+                ),
+            )
+            .unwrap();
         let mut args = FluentArgs::new();
-        args.insert("time", FluentValue::from(String::from("13:00")));
+        args.set("name", "זיו");
+        args.set("count", 2);
+        let say = |fluent: &FluentErgo| fluent.tr("said", Some(&args)).unwrap();
+
+        assert_eq!(say(&fluent), "זיו said: Aurora hi 2 times");
+        fluent.set_isolation(Isolation::Arguments);
         assert_eq!(
-            fluent.tr("nested_display", Some(&args)).unwrap(),
-            String::from("nesting a time display: 13:00 during the day")
+            say(&fluent),
+            "\u{2068}זיו\u{2069} said: Aurora \u{2068}hi\u{2069} \u{2068}2\u{2069} times"
+        );
+        assert_eq!(
+            fluent.tr("name", Some(&args)).unwrap(),
+            "\u{2068}זיו\u{2069}"
+        );
+        fluent.set_isolation(Isolation::Placeables);
+        assert_eq!(
+            say(&fluent),
+            "\u{2068}זיו\u{2069} said: Aurora \u{2068}hi\u{2069} \u{2068}2\u{2069} \u{2068}times\u{2069}"
+        );
+        assert_eq!(fluent.tr("name", Some(&args)).unwrap(), "זיו");
+
+        let plain = TrOptions {
+            isolation: Some(Isolation::Strip),
+            ..TrOptions::default()
+        };
+        assert_eq!(
+            fluent.tr_opts("said", Some(&args), &plain).unwrap(),
+            "זיו said: Aurora hi 2 times"
+        );
+        let frozen = fluent.freeze().expect("freezing should succeed");
+        assert_eq!(
+            frozen.tr_opts("said", Some(&args), &plain).unwrap(),
+            "זיו said: Aurora hi 2 times"
+        );
+        assert!(frozen
+            .tr("said", Some(&args))
+            .unwrap()
+            .starts_with('\u{2068}'));
+    }
+
+    #[test]
+    fn explain_traces_the_variants_taken() {
+        use crate::{Origin, Selection};
+
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_labeled_text(
+                en_id.clone(),
+                "shared",
+                String::from(
+                    "shared = { $user } shared { $count ->\n\
+                     \x20   [one] { $kind ->\n\
+                     \x20       [photo] a photo\n\
+                     \x20      *[file] a file\n\
+                     \x20   }\n\
+                     \x20  *[other] { $count } files\n\
+                     }\n    .default-kind = photo",
+                ),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(de_id.clone(), String::from("quit = Beenden"))
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("count", 1);
+        let explanation = fluent.explain("shared", Some(&args)).unwrap();
+        assert_eq!(explanation.tried, [de_id.clone(), en_id.clone()]);
+        assert_eq!(explanation.lang, Some(en_id));
+        assert_eq!(
+            explanation.origin,
+            Some(Origin::Label(String::from("shared")))
+        );
+        assert_eq!(
+            explanation.selections,
+            [
+                Selection {
+                    selector: String::from("$count"),
+                    variant: String::from("one"),
+                    default: false,
+                },
+                Selection {
+                    selector: String::from("$kind"),
+                    variant: String::from("photo"),
+                    default: false,
+                },
+            ]
+        );
+        assert_eq!(explanation.variables, ["count", "kind", "user"]);
+        assert_eq!(explanation.missing, ["user"]);
+        assert_eq!(
+            explanation.translation.as_deref(),
+            Some("{$user} shared a photo")
+        );
+
+        args.set("count", 5);
+        let explanation = fluent.explain("shared", Some(&args)).unwrap();
+        assert_eq!(explanation.selections.len(), 1);
+        assert!(explanation.selections[0].default);
+
+        let explanation = fluent.explain("nowhere", None).unwrap();
+        assert_eq!(explanation.tried.len(), 2);
+        assert_eq!(explanation.lang, None);
+        assert_eq!(explanation.translation, None);
+    }
+
+    #[test]
+    fn annotated_translations_locate_arguments() {
+        use crate::{ArgumentSpan, Isolation};
+
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from(
+                    "-app = Pager\n\
+                     sent = { $from } sent { $to } { -app } { $count ->\n\
+                     \x20   [one] one page\n\
+                     \x20  *[other] { $count } pages\n\
+                     }\n\
+                     plain = No arguments",
+                ),
+            )
+            .unwrap();
+        let mut args = FluentArgs::new();
+        args.set("from", "Ana");
+        args.set("to", "Бо");
+        args.set("count", 2);
+
+        let annotated = fluent.tr_annotated("sent", Some(&args)).unwrap();
+        assert_eq!(annotated.text, "Ana sent Бо Pager 2 pages");
+        assert_eq!(
+            annotated.arguments,
+            [
+                ArgumentSpan {
+                    range: 0..3,
+                    name: Some(String::from("from")),
+                },
+                ArgumentSpan {
+                    range: 9..13,
+                    name: Some(String::from("to")),
+                },
+                ArgumentSpan {
+                    range: 20..21,
+                    name: Some(String::from("count")),
+                },
+            ]
+        );
+        assert_eq!(fluent.tr("sent", Some(&args)).unwrap(), annotated.text);
+
+        fluent.set_isolation(Isolation::Arguments);
+        let annotated = fluent.tr_annotated("sent", Some(&args)).unwrap();
+        let to = &annotated.arguments[1];
+        assert_eq!(&annotated.text[to.range.clone()], "\u{2068}Бо\u{2069}");
+        assert_eq!(to.name.as_deref(), Some("to"));
+
+        let annotated = fluent.tr_annotated("plain", None).unwrap();
+        assert_eq!(annotated.text, "No arguments");
+        assert!(annotated.arguments.is_empty());
+        assert!(fluent.tr_annotated("nowhere", None).is_err());
+    }
+
+    #[test]
+    fn truncate_function_counts_graphemes() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from(
+                    "title = “{ TRUNCATE($title, 5) }”\n\
+                     ellipsis = { TRUNCATE($title, 5, ellipsis: \"…\") }\n\
+                     broken = { TRUNCATE($title) }",
+                ),
+            )
+            .unwrap();
+        let title = |text: &str, msgid: &str| {
+            let mut args = FluentArgs::new();
+            args.set("title", String::from(text));
+            fluent.tr(msgid, Some(&args)).unwrap()
+        };
+        assert_eq!(title("Short", "title"), "“Short”");
+        assert_eq!(title("Shorter", "title"), "“Short”");
+        assert_eq!(
+            title("ne\u{301}e\u{301} cafe\u{301}", "title"),
+            "“ne\u{301}e\u{301} c”"
+        );
+        assert_eq!(title("Elephants", "ellipsis"), "Elep…");
+        assert_eq!(title("Ant", "ellipsis"), "Ant");
+        assert_eq!(title("Ants go", "ellipsis"), "Ants…");
+        assert_eq!(title("Ant", "broken"), "TRUNCATE()");
+
+        assert!(fluent
+            .add_function("TRUNCATE", |_, _| FluentValue::None)
+            .is_err());
+    }
+
+    #[test]
+    fn quote_function_follows_the_language() {
+        let quoted = |tag: &str| {
+            let lang = tag.parse::<LanguageIdentifier>().unwrap();
+            let mut fluent = FluentErgo::new(std::slice::from_ref(&lang));
+            fluent
+                .add_from_text(lang, String::from("said = { QUOTE($text) }"))
+                .unwrap();
+            let mut args = FluentArgs::new();
+            args.set("text", "hi");
+            fluent.tr("said", Some(&args)).unwrap()
+        };
+        assert_eq!(quoted("en-US"), "“hi”");
+        assert_eq!(quoted("de"), "„hi“");
+        assert_eq!(quoted("de-CH"), "«hi»");
+        assert_eq!(quoted("fr"), "«hi»");
+        assert_eq!(quoted("pl"), "„hi”");
+        assert_eq!(quoted("ja"), "「hi」");
+        assert_eq!(quoted("zh-TW"), "「hi」");
+        assert_eq!(quoted("zh"), "“hi”");
+        assert_eq!(quoted("pt-BR"), "“hi”");
+        assert_eq!(quoted("pt-PT"), "«hi»");
+    }
+
+    #[test]
+    fn range_function_joins_numbers() {
+        let range = |tag: &str, min: f64, max: f64| {
+            let lang = tag.parse::<LanguageIdentifier>().unwrap();
+            let mut fluent = FluentErgo::new(std::slice::from_ref(&lang));
+            fluent
+                .add_from_text(lang, String::from("takes = { RANGE($min, $max) }"))
+                .unwrap();
+            let mut args = FluentArgs::new();
+            args.set("min", min);
+            args.set("max", max);
+            fluent.tr("takes", Some(&args)).unwrap()
+        };
+        assert_eq!(range("en", 3.0, 5.0), "3–5");
+        assert_eq!(range("es", 3.0, 5.0), "3-5");
+        assert_eq!(range("ja", 3.0, 5.0), "3～5");
+        assert_eq!(range("en", 2.5, 4.0), "2.5–4");
+        assert_eq!(range("en", 3.0, 3.0), "3");
+    }
+
+    #[test]
+    fn percent_function_places_the_sign() {
+        let percent = |tag: &str, message: &str, args: &FluentArgs| {
+            let lang = tag.parse::<LanguageIdentifier>().unwrap();
+            let mut fluent = FluentErgo::new(std::slice::from_ref(&lang));
+            fluent
+                .add_from_text(lang, format!("done = {}", message))
+                .unwrap();
+            fluent.tr("done", Some(args)).unwrap()
+        };
+        let mut args = FluentArgs::new();
+        args.set("ratio", 0.25);
+        assert_eq!(percent("en", "{ PERCENT($ratio) }", &args), "25%");
+        assert_eq!(percent("de", "{ PERCENT($ratio) }", &args), "25\u{a0}%");
+        assert_eq!(percent("fr", "{ PERCENT($ratio) }", &args), "25\u{202f}%");
+        assert_eq!(percent("tr", "{ PERCENT($ratio) }", &args), "%25");
+        assert_eq!(percent("en", "{ PERMILLE($ratio) }", &args), "250‰");
+
+        args.set("ratio", 0.1234);
+        assert_eq!(
+            percent("de", "{ PERCENT($ratio, maximumFractionDigits: 1) }", &args),
+            "12,3\u{a0}%"
+        );
+        assert_eq!(percent("en", "{ PERCENT($ratio) }", &args), "12%");
+
+        let mut args = FluentArgs::new();
+        args.set_value("ratio", crate::Percent(0.5));
+        assert_eq!(percent("tr", "{ $ratio }", &args), "%50");
+        args.set_value("ratio", crate::Permille(-0.0004));
+        assert_eq!(percent("en", "{ $ratio }", &args), "0‰");
+    }
+
+    #[test]
+    fn formatters_get_the_language_of_the_message() {
+        struct Postal;
+
+        impl crate::DomainFormatter for Postal {
+            fn format(
+                &self,
+                value: &str,
+                lang: &LanguageIdentifier,
+                options: &FluentArgs,
+            ) -> Option<String> {
+                if !value.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+                    return None;
+                }
+                let prefix = match options.get("prefix") {
+                    Some(FluentValue::String(prefix)) => prefix.to_string(),
+                    _ => String::new(),
+                };
+                match lang.region.as_ref().map(|region| region.as_str()) {
+                    Some("GB") if value.len() > 3 => {
+                        let (outward, inward) = value.split_at(value.len() - 3);
+                        Some(format!("{}{} {}", prefix, outward, inward))
+                    }
+                    _ => Some(format!("{}{}", prefix, value)),
+                }
+            }
+        }
+
+        let gb_id = "en-GB".parse::<LanguageIdentifier>().unwrap();
+        let de_id = "de-DE".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[gb_id.clone(), de_id.clone()]);
+        fluent
+            .add_from_text(gb_id, String::from("gb = { POSTAL($code) }"))
+            .unwrap();
+        fluent.add_formatter("POSTAL", Postal).unwrap();
+        fluent
+            .add_from_text(
+                de_id,
+                String::from("de = { POSTAL($code, prefix: \"D-\") }\nbad = { POSTAL() }"),
+            )
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("code", "SW1A1AA");
+        assert_eq!(fluent.tr("gb", Some(&args)).unwrap(), "SW1A 1AA");
+        args.set("code", 10115);
+        assert_eq!(fluent.tr("de", Some(&args)).unwrap(), "D-10115");
+        args.set("code", "10 115");
+        assert_eq!(fluent.tr("gb", Some(&args)).unwrap(), "10 115");
+        assert_eq!(fluent.tr("bad", None).unwrap(), "POSTAL()");
+
+        assert!(fluent.add_formatter("POSTAL", Postal).is_err());
+        assert!(fluent.add_formatter("QUOTE", Postal).is_err());
+    }
+
+    #[test]
+    fn trg_passes_the_gender_and_finds_gaps() {
+        use crate::Gender;
+
+        let ru_id = "ru".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[ru_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                ru_id.clone(),
+                String::from(
+                    "
+left = { $name } { $gender ->
+    [masculine] вышел
+    [feminine] вышла
+    [neuter] вышло
+   *[other] вышли
+}
+seen = { $gender ->
+    [masculine] { $case ->
+        [genitive] его
+       *[other] он
+    }
+   *[other] { $case ->
+        [genitive] её
+       *[other] она
+    }
+}
+",
+                ),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("left = { $name } left\nseen = { $gender ->\n   *[other] them\n}"),
+            )
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("name", "Анна");
+        args.set("gender", "masculine");
+        assert_eq!(
+            fluent.trg("left", Gender::Feminine, Some(&args)).unwrap(),
+            "Анна вышла"
+        );
+        assert_eq!(
+            fluent.trg("left", Gender::Other, Some(&args)).unwrap(),
+            "Анна вышли"
+        );
+        assert_eq!(
+            fluent
+                .trg_case("seen", Gender::Feminine, "genitive", None)
+                .unwrap(),
+            "её"
+        );
+        assert_eq!(
+            fluent
+                .trg_case("seen", Gender::Masculine, "nominative", None)
+                .unwrap(),
+            "он"
+        );
+
+        let gaps = fluent.gender_gaps();
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].lang, ru_id);
+        assert_eq!(gaps[0].id, "seen");
+        assert_eq!(gaps[0].missing, vec![Gender::Feminine, Gender::Neuter]);
+    }
+
+    #[test]
+    fn tr_ctx_falls_back_to_the_message_without_a_context() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(
+                en_id,
+                String::from("open = Open\nopen--state = Open\npost--verb = Post { $what }"),
+            )
+            .unwrap();
+        fluent
+            .add_from_text(de_id, String::from("open = Öffnen\npost = Beitrag"))
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("what", "it");
+        assert_eq!(fluent.tr_ctx("open", "verb", None).unwrap(), "Öffnen");
+        assert_eq!(fluent.tr_ctx("open", "state", None).unwrap(), "Open");
+        assert_eq!(
+            fluent.tr_ctx("post", "verb", Some(&args)).unwrap(),
+            "Post it"
+        );
+        assert_eq!(fluent.tr_ctx("post", "noun", None).unwrap(), "Beitrag");
+        match fluent.tr_ctx("close", "verb", None) {
+            Err(Error::NoMatchingMessage(missing)) => assert_eq!(missing.id, "close"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn subset_dir_keeps_what_used_messages_need() {
+        let root = std::env::temp_dir().join(format!("fluent-subset-{}", std::process::id()));
+        let from = root.join("from");
+        let to = root.join("to");
+        std::fs::create_dir_all(from.join("de")).unwrap();
+        std::fs::write(
+            from.join("de").join("terms.ftl"),
+            "-brand = Acme\n-unused = Nie\nbrand-name = { -brand }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            from.join("de").join("main.ftl"),
+            "# Ein Kommentar\nabout = Über { brand-name }\n    .title = { -brand }\nopen--verb = Öffnen\nhelp = Hilfe\n",
+        )
+        .unwrap();
+        std::fs::write(from.join("de").join("notes.txt"), "not Fluent").unwrap();
+
+        let used = crate::extract_message_ids(
+            r#"fluent.tr("about", None); fluent.tr_ctx("open", "verb", None);"#,
+        );
+        crate::subset_dir(&from, &to, &used).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(to.join("de").join("terms.ftl")).unwrap(),
+            "-brand = Acme\nbrand-name = { -brand }\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(to.join("de").join("main.ftl")).unwrap(),
+            "about = Über { brand-name }\n    .title = { -brand }\nopen--verb = Öffnen\n"
+        );
+        assert!(!to.join("de").join("notes.txt").exists());
+
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&de_id));
+        fluent.add_from_dir(&to).unwrap();
+        assert_eq!(fluent.tr("about", None).unwrap(), "Über Acme");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn features_load_and_unload_their_resources() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_from_text(en_id.clone(), String::from("save = Save\nshare = Share"))
+            .unwrap();
+        fluent
+            .add_feature_text("sharing", de_id.clone(), String::from("share = Teilen"))
+            .unwrap();
+        fluent
+            .add_feature_text("broken", de_id.clone(), String::from("oops = {"))
+            .unwrap();
+        let clone = fluent.clone();
+
+        assert_eq!(fluent.tr("share", None).unwrap(), "Share");
+        fluent.set_feature_enabled("sharing", true).unwrap();
+        assert_eq!(clone.tr("share", None).unwrap(), "Teilen");
+        assert_eq!(
+            fluent.origin_of("share", &de_id),
+            Some(crate::Origin::Label(String::from("sharing")))
+        );
+        fluent
+            .add_feature_text("sharing", de_id.clone(), String::from("invite = Einladen"))
+            .unwrap();
+        assert_eq!(fluent.tr("invite", None).unwrap(), "Einladen");
+        assert_eq!(fluent.enabled_features(), vec!["sharing"]);
+
+        assert!(fluent.set_feature_enabled("broken", true).is_err());
+        assert_eq!(fluent.enabled_features(), vec!["sharing"]);
+
+        fluent
+            .add_feature_text(
+                "clashing",
+                de_id.clone(),
+                String::from("mail = Post\nshare = Tauschen"),
+            )
+            .unwrap();
+        assert!(fluent.set_feature_enabled("clashing", true).is_err());
+        assert_eq!(fluent.tr("share", None).unwrap(), "Teilen");
+        assert!(fluent.tr("mail", None).is_err());
+        assert!(fluent
+            .add_feature_text(
+                "sharing",
+                de_id.clone(),
+                String::from("post = Post\ninvite = Laden")
+            )
+            .is_err());
+        assert!(fluent.tr("post", None).is_err());
+        assert_eq!(fluent.tr("invite", None).unwrap(), "Einladen");
+
+        fluent
+            .add_from_text(de_id, String::from("save = Speichern"))
+            .unwrap();
+        fluent.set_feature_enabled("sharing", false).unwrap();
+        assert_eq!(clone.tr("share", None).unwrap(), "Share");
+        assert!(fluent.tr("invite", None).is_err());
+        assert_eq!(fluent.tr("save", None).unwrap(), "Speichern");
+        assert!(fluent.enabled_features().is_empty());
+    }
+
+    #[test]
+    fn tr_weighted_buckets_by_seed_and_weight() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id,
+                String::from(
+                    "
+cta = { $variant ->
+    [b] Buy { $item } now
+   *[a] Get { $item }
+}
+",
+                ),
+            )
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("item", "Pro");
+        args.set("variant", "b");
+        let weights = [("a", 3), ("b", 1)];
+        let mut counts = std::collections::HashMap::new();
+        for user in 0..2000 {
+            let seed = format!("user-{}", user);
+            let shown = fluent
+                .tr_weighted("cta", &weights, &seed, Some(&args))
+                .unwrap();
+            assert_eq!(
+                fluent
+                    .tr_weighted("cta", &weights, &seed, Some(&args))
+                    .unwrap(),
+                shown
+            );
+            let expected = match shown.variant.as_deref() {
+                Some("a") => "Get Pro",
+                Some("b") => "Buy Pro now",
+                other => panic!("unexpected variant: {:?}", other),
+            };
+            assert_eq!(shown.text, expected);
+            *counts.entry(shown.variant.unwrap()).or_insert(0) += 1;
+        }
+        assert!((1300..1700).contains(&counts["a"]), "{:?}", counts);
+
+        let only_b = fluent.tr_weighted("cta", &[("a", 0), ("b", 1)], "anyone", Some(&args));
+        assert_eq!(only_b.unwrap().text, "Buy Pro now");
+        let none = fluent
+            .tr_weighted("cta", &[("a", 0)], "anyone", Some(&args))
+            .unwrap();
+        assert_eq!(none.variant, None);
+        assert_eq!(none.text, "Get Pro");
+    }
+
+    #[test]
+    fn environment_overlays_replace_messages() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(
+                en_id.clone(),
+                String::from("-brand = Acme\ntitle = { -brand }"),
+            )
+            .unwrap();
+        for (environment, text) in [
+            ("staging", "title = [staging] { -brand }\nbanner = Staging"),
+            ("dev", "title = [dev] { -brand }\nbanner = Staging"),
+        ] {
+            fluent
+                .add_environment_text(environment, en_id.clone(), String::from(text))
+                .unwrap();
+        }
+        fluent
+            .add_environment_text("broken", en_id.clone(), String::from("title = {"))
+            .unwrap();
+        let clone = fluent.clone();
+
+        fluent.set_environment(Some("staging")).unwrap();
+        assert_eq!(clone.tr("title", None).unwrap(), "[staging] Acme");
+        assert_eq!(fluent.environment().as_deref(), Some("staging"));
+        assert_eq!(
+            fluent.origin_of("title", &en_id),
+            Some(crate::Origin::Environment(String::from("staging")))
+        );
+        fluent
+            .add_from_text(en_id.clone(), String::from("title = Late\nsave = Save"))
+            .unwrap();
+        assert_eq!(fluent.tr("title", None).unwrap(), "[staging] Acme");
+
+        assert!(fluent.set_environment(Some("broken")).is_err());
+        assert_eq!(fluent.environment().as_deref(), Some("staging"));
+        fluent.set_environment(Some("dev")).unwrap();
+        assert_eq!(fluent.tr("title", None).unwrap(), "[dev] Acme");
+        assert_eq!(fluent.tr("banner", None).unwrap(), "Staging");
+
+        fluent.set_environment(None).unwrap();
+        assert_eq!(fluent.tr("title", None).unwrap(), "Acme");
+        assert!(fluent.tr("banner", None).is_err());
+        assert_eq!(fluent.tr("save", None).unwrap(), "Save");
+
+        // Overlays are checked like any other resource.
+        fluent.set_term_validation(true);
+        fluent
+            .add_environment_text("typo", en_id, String::from("title = { -brnad }"))
+            .unwrap();
+        assert!(matches!(
+            fluent.set_environment(Some("typo")),
+            Err(Error::UnknownTerms { .. })
+        ));
+        assert_eq!(fluent.tr("title", None).unwrap(), "Acme");
+    }
+
+    #[test]
+    fn reload_validator_sees_the_diff_and_can_veto() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[en_id.clone(), de_id.clone()]);
+        fluent
+            .add_from_text(en_id.clone(), String::from("save = Save\nopen = Open"))
+            .unwrap();
+        fluent
+            .add_from_text(de_id.clone(), String::from("save = Speichern"))
+            .unwrap();
+
+        let diff = fluent
+            .reload_diff(vec![(
+                en_id.clone(),
+                String::from("# Edited\nsave = Save\nopen = Open…\nclose = Close"),
+            )])
+            .unwrap();
+        assert_eq!(diff.added, vec![(en_id.clone(), String::from("close"))]);
+        assert_eq!(diff.changed, vec![(en_id.clone(), String::from("open"))]);
+        assert_eq!(diff.removed, vec![(de_id.clone(), String::from("save"))]);
+        assert!(fluent
+            .reload_diff(vec![
+                (en_id.clone(), String::from("open = Open\nsave = Save")),
+                (de_id.clone(), String::from("save = Speichern")),
+            ])
+            .unwrap()
+            .is_empty());
+
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = std::sync::Arc::clone(&seen);
+        fluent.set_reload_validator(move |diff| {
+            recorded.lock().unwrap().push(diff.clone());
+            match diff.removed.is_empty() {
+                true => Ok(()),
+                false => Err(String::from("messages were removed")),
+            }
+        });
+        match fluent.replace_all(vec![(en_id.clone(), String::from("save = Save"))]) {
+            Err(Error::ReloadVetoed { reason }) => assert_eq!(reason, "messages were removed"),
+            other => panic!("expected a veto, got {:?}", other),
+        }
+        assert_eq!(fluent.tr("open", None).unwrap(), "Open");
+        fluent
+            .replace_all(vec![
+                (en_id.clone(), String::from("save = Save\nopen = Open…")),
+                (de_id, String::from("save = Speichern")),
+            ])
+            .unwrap();
+        assert_eq!(fluent.tr("open", None).unwrap(), "Open…");
+        assert_eq!(seen.lock().unwrap().len(), 2);
+        assert_eq!(
+            seen.lock().unwrap()[1].changed,
+            vec![(en_id, String::from("open"))]
         );
     }
 
+    #[test]
+    fn rollback_restores_the_catalogs_before_reloads() {
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(std::slice::from_ref(&en_id));
+        fluent
+            .add_from_text(en_id.clone(), String::from("units = Units"))
+            .unwrap();
+        assert!(!fluent.rollback().unwrap());
+
+        fluent.set_catalog_history(2);
+        let clone = fluent.clone();
+        for text in ["units = Unitz", "units = Measures", "units = Broken"] {
+            fluent
+                .replace_all(vec![(en_id.clone(), String::from(text))])
+                .unwrap();
+        }
+        assert_eq!(fluent.catalog_history_len(), 2);
+        assert!(fluent.rollback().unwrap());
+        assert_eq!(clone.tr("units", None).unwrap(), "Measures");
+        assert!(fluent.rollback().unwrap());
+        assert_eq!(clone.tr("units", None).unwrap(), "Unitz");
+        assert!(!fluent.rollback().unwrap());
+        assert_eq!(fluent.tr("units", None).unwrap(), "Unitz");
+
+        fluent
+            .replace_all(vec![(en_id.clone(), String::from("units = Measures"))])
+            .unwrap();
+        fluent.set_catalog_history(0);
+        assert_eq!(fluent.catalog_history_len(), 0);
+        drop(clone);
+        fluent.set_catalog_history(1);
+        fluent
+            .replace_all(vec![(en_id, String::from("units = Units"))])
+            .unwrap();
+        let frozen = fluent.freeze().unwrap();
+        assert_eq!(frozen.tr("units", None).unwrap(), "Units");
+    }
+
+    #[test]
+    fn rollback_after_a_partial_reload_ignores_later_additions() {
+        let de_id = "de".parse::<LanguageIdentifier>().unwrap();
+        let en_id = "en".parse::<LanguageIdentifier>().unwrap();
+        let mut fluent = FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+        fluent
+            .add_many(vec![
+                (de_id.clone(), String::from("save = Sichern")),
+                (en_id.clone(), String::from("save = Save")),
+            ])
+            .unwrap();
+        fluent.set_catalog_history(1);
+
+        // This is how an `OtaClient` reloads: only the languages it downloaded are replaced.
+        let parsed =
+            super::parse_text(&fluent.options, String::from("save = Speichern"), None).unwrap();
+        fluent.swap_bundles(vec![(de_id, parsed)], false).unwrap();
+        fluent
+            .add_from_text(en_id, String::from("quit = Quit"))
+            .unwrap();
+        assert_eq!(fluent.tr("quit", None).unwrap(), "Quit");
+
+        assert!(fluent.rollback().unwrap());
+        assert_eq!(fluent.tr("save", None).unwrap(), "Sichern");
+        assert!(fluent.tr("quit", None).is_err());
+    }
+
     #[test]
     fn test_send() {
         fn assert_send<T: Send>() {}
         assert_send::<FluentErgo>();
+        assert_send::<FrozenFluentErgo>();
     }
 
     #[test]
     fn test_sync() {
         fn assert_sync<T: Sync>() {}
         assert_sync::<FluentErgo>();
+        assert_sync::<FrozenFluentErgo>();
     }
 }
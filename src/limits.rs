@@ -0,0 +1,333 @@
+use crate::{BundleEntry, BundleKind, Error, FluentErgo};
+use fluent_syntax::ast;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// Limits on what formatting a single message may do, for translators which load translations
+/// that are not trusted. See `FluentErgo::set_evaluation_limits`. `None` leaves a limit off.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvaluationLimits {
+    /// How many messages and terms deep references may go. A message which refers to nothing has
+    /// a depth of 1.
+    pub max_depth: Option<usize>,
+    /// How many placeables a message may expand into, counting the placeables of every message
+    /// and term it refers to each time it is referred to. Fluent itself stops at 100.
+    pub max_placeables: Option<usize>,
+    /// The longest translation, in bytes
+    pub max_len: Option<usize>,
+}
+
+/// Which of the `EvaluationLimits` a message went over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Depth,
+    Placeables,
+    Length,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Depth => "reference depth",
+            Limit::Placeables => "placeable",
+            Limit::Length => "length",
+        })
+    }
+}
+
+/// The messages of each language that go over the depth or placeable limits, worked out once
+/// when a translator is frozen.
+pub(crate) type OverLimit = HashMap<LanguageIdentifier, HashMap<String, Limit>>;
+
+impl EvaluationLimits {
+    fn checks_references(&self) -> bool {
+        self.max_depth.is_some() || self.max_placeables.is_some()
+    }
+
+    /// Whether a translation of `len` bytes is short enough.
+    pub(crate) fn allows_len(&self, len: usize) -> bool {
+        self.max_len.is_none_or(|max_len| len <= max_len)
+    }
+
+    /// An error if a translation of `len` bytes would be too long.
+    pub(crate) fn check_len(
+        &self,
+        msgid: &str,
+        lang: &LanguageIdentifier,
+        len: usize,
+    ) -> Result<(), Error> {
+        if self.allows_len(len) {
+            Ok(())
+        } else {
+            Err(Error::LimitExceeded {
+                id: String::from(msgid),
+                lang: lang.clone(),
+                limit: Limit::Length,
+            })
+        }
+    }
+
+    /// An error if formatting `msgid` from `entry` would go too deep or expand into too many
+    /// placeables. This walks the message and everything it refers to, so it only happens when
+    /// one of those limits is set.
+    pub(crate) fn check_references<K: BundleKind>(
+        &self,
+        entry: &BundleEntry<K>,
+        msgid: &str,
+        lang: &LanguageIdentifier,
+    ) -> Result<(), Error> {
+        if !self.checks_references() {
+            return Ok(());
+        }
+        match self.exceeded(&mut Walk::new(entry, self), msgid) {
+            Some(limit) => Err(Error::LimitExceeded {
+                id: String::from(msgid),
+                lang: lang.clone(),
+                limit,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn exceeded<K: BundleKind>(&self, walk: &mut Walk<'_, K>, msgid: &str) -> Option<Limit> {
+        walk.truncated = false;
+        let cost = walk.reference(msgid, None);
+        if self.max_depth.is_some_and(|max| cost.depth > max) {
+            Some(Limit::Depth)
+        } else if self.max_placeables.is_some_and(|max| cost.placeables > max) {
+            Some(Limit::Placeables)
+        } else {
+            None
+        }
+    }
+
+    /// Every message in `entries` which goes over the depth or placeable limits.
+    pub(crate) fn over_limit<'a, K: BundleKind + 'a>(
+        &self,
+        entries: impl Iterator<Item = (&'a LanguageIdentifier, &'a BundleEntry<K>)>,
+    ) -> OverLimit {
+        if !self.checks_references() {
+            return OverLimit::new();
+        }
+        entries
+            .map(|(lang, entry)| {
+                let mut walk = Walk::new(entry, self);
+                let over = entry
+                    .message_ids()
+                    .into_iter()
+                    .filter_map(|msgid| {
+                        let limit = self.exceeded(&mut walk, msgid)?;
+                        Some((String::from(msgid), limit))
+                    })
+                    .collect();
+                (lang.clone(), over)
+            })
+            .collect()
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Refuse to format messages which go over `limits`, so that an untrusted community
+    /// translation pack cannot nest references until the stack runs out, or expand a few lines
+    /// into a string of several megabytes. A translation which would go over a limit returns
+    /// `Error::LimitExceeded`, whatever the `TrOptions` say, and its text is never built.
+    ///
+    /// ```
+    /// use fluent_ergonomics::{Error, EvaluationLimits, Limit};
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from("lol = lol\nlols = { lol } { lol } { lol }\nmore = { lols } { lols }"),
+    ///     )
+    ///     .unwrap();
+    /// fluent.set_evaluation_limits(EvaluationLimits {
+    ///     max_placeables: Some(4),
+    ///     ..EvaluationLimits::default()
+    /// });
+    ///
+    /// assert_eq!(fluent.tr("lols", None).unwrap(), "lol lol lol");
+    /// match fluent.tr("more", None) {
+    ///     Err(Error::LimitExceeded { limit, .. }) => assert_eq!(limit, Limit::Placeables),
+    ///     other => panic!("{:?}", other),
+    /// }
+    /// ```
+    ///
+    /// The depth and placeable limits walk the message and everything it refers to on every
+    /// translation, so they make formatting slower; a frozen translator walks every message once,
+    /// when it is frozen. They do not apply to messages from a `MissingMessageResolver`.
+    pub fn set_evaluation_limits(&mut self, limits: EvaluationLimits) {
+        Arc::make_mut(&mut self.options).limits = limits;
+    }
+}
+
+/// How deep a pattern goes, and how many placeables it expands into.
+#[derive(Clone, Copy, Default)]
+struct Cost {
+    depth: usize,
+    placeables: usize,
+}
+
+impl Cost {
+    fn then(self, other: Cost) -> Cost {
+        Cost {
+            depth: self.depth.max(other.depth),
+            placeables: self.placeables.saturating_add(other.placeables),
+        }
+    }
+
+    fn or(self, other: Cost) -> Cost {
+        Cost {
+            depth: self.depth.max(other.depth),
+            placeables: self.placeables.max(other.placeables),
+        }
+    }
+}
+
+/// Works out the cost of messages in one entry, remembering each one so that a message which is
+/// referred to many times is only walked once. Fluent resolves references in the bundle which
+/// has the message, so they are looked up in the same entry.
+///
+/// The walk stops going deeper once the references it is inside already go over a limit, so that
+/// a pack with a very long chain of references cannot run the stack out here instead.
+struct Walk<'e, K: BundleKind> {
+    entry: &'e BundleEntry<K>,
+    max_depth: Option<usize>,
+    max_placeables: Option<usize>,
+    costs: HashMap<String, Cost>,
+    active: HashSet<String>,
+    /// Whether the walk stopped short somewhere below the references it is inside. Their costs
+    /// are only lower bounds then, so they are not remembered.
+    truncated: bool,
+}
+
+impl<'e, K: BundleKind> Walk<'e, K> {
+    fn new(entry: &'e BundleEntry<K>, limits: &EvaluationLimits) -> Walk<'e, K> {
+        Walk {
+            entry,
+            max_depth: limits.max_depth,
+            max_placeables: limits.max_placeables,
+            costs: HashMap::new(),
+            active: HashSet::new(),
+            truncated: false,
+        }
+    }
+
+    /// The cost of a message or term, or of one of its attributes. Fluent refuses to format a
+    /// cycle, so a reference back into one costs nothing here.
+    fn reference(&mut self, id: &str, attribute: Option<&str>) -> Cost {
+        let key = match attribute {
+            Some(attribute) => format!("{}.{}", id, attribute),
+            None => String::from(id),
+        };
+        if let Some(cost) = self.costs.get(&key) {
+            return *cost;
+        }
+        // Every reference that the walk is inside adds one to the depth, and sits in a placeable
+        // of the one before it, so past either limit the message is already over it.
+        let nesting = self.active.len();
+        let too_deep = self.max_depth.is_some_and(|max| nesting >= max);
+        let too_many = self.max_placeables.is_some_and(|max| nesting > max);
+        if too_deep || too_many {
+            self.truncated = true;
+            return Cost {
+                depth: if too_deep { usize::MAX } else { 0 },
+                placeables: if too_many { usize::MAX } else { 0 },
+            };
+        }
+        if !self.active.insert(key.clone()) {
+            return Cost::default();
+        }
+        let definition = self.entry.definition(id).map(|(_, definition)| definition);
+        let pattern = definition.and_then(|definition| match (definition, attribute) {
+            (ast::Entry::Message(msg), None) => msg.value.as_ref(),
+            (ast::Entry::Term(term), None) => Some(&term.value),
+            (ast::Entry::Message(msg), Some(name)) => msg
+                .attributes
+                .iter()
+                .find(|attr| attr.id.name == name)
+                .map(|attr| &attr.value),
+            (ast::Entry::Term(term), Some(name)) => term
+                .attributes
+                .iter()
+                .find(|attr| attr.id.name == name)
+                .map(|attr| &attr.value),
+            _ => None,
+        });
+        // A reference which Fluent cannot resolve is only an error, so it costs nothing either.
+        let mut cost = Cost::default();
+        if let Some(pattern) = pattern {
+            cost = self.pattern(pattern);
+            cost.depth = cost.depth.saturating_add(1);
+        }
+        self.active.remove(&key);
+        if !self.truncated {
+            self.costs.insert(key, cost);
+        }
+        cost
+    }
+
+    fn pattern(&mut self, pattern: &ast::Pattern<&str>) -> Cost {
+        let mut cost = Cost::default();
+        for element in &pattern.elements {
+            if let ast::PatternElement::Placeable { expression } = element {
+                cost = cost.then(self.placeable(expression));
+            }
+        }
+        cost
+    }
+
+    fn placeable(&mut self, expression: &ast::Expression<&str>) -> Cost {
+        let inner = match expression {
+            ast::Expression::Select { selector, variants } => {
+                // Only one variant is formatted, so the most expensive one is what counts.
+                let variants = variants.iter().fold(Cost::default(), |cost, variant| {
+                    cost.or(self.pattern(&variant.value))
+                });
+                self.inline(selector).then(variants)
+            }
+            ast::Expression::Inline(inline) => self.inline(inline),
+        };
+        Cost {
+            depth: 0,
+            placeables: 1,
+        }
+        .then(inner)
+    }
+
+    fn inline(&mut self, inline: &ast::InlineExpression<&str>) -> Cost {
+        let (cost, arguments) = match inline {
+            ast::InlineExpression::MessageReference { id, attribute } => (
+                self.reference(id.name, attribute.as_ref().map(|attr| attr.name)),
+                None,
+            ),
+            ast::InlineExpression::TermReference {
+                id,
+                attribute,
+                arguments,
+            } => (
+                self.reference(
+                    &format!("-{}", id.name),
+                    attribute.as_ref().map(|attr| attr.name),
+                ),
+                arguments.as_ref(),
+            ),
+            ast::InlineExpression::FunctionReference { arguments, .. } => {
+                (Cost::default(), Some(arguments))
+            }
+            ast::InlineExpression::Placeable { expression } => (self.placeable(expression), None),
+            _ => (Cost::default(), None),
+        };
+        arguments.map_or(cost, |arguments| {
+            arguments
+                .positional
+                .iter()
+                .chain(arguments.named.iter().map(|argument| &argument.value))
+                .fold(cost, |cost, argument| cost.then(self.inline(argument)))
+        })
+    }
+}
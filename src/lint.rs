@@ -0,0 +1,338 @@
+use crate::{canonicalize_language, BundleKind, FluentErgo, PoisonExt};
+use fluent::FluentResource;
+use fluent_syntax::ast;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// A suspicious pattern in a message which still loaded, reported to the handler given to
+/// `FluentErgo::set_lint_handler`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lint {
+    pub lang: LanguageIdentifier,
+    /// The message ID, or the term ID with its leading `-`
+    pub id: String,
+    pub kind: LintKind,
+}
+
+/// What a `Lint` is about.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// A string literal with a brace in it, such as `{ "{" }name{ "}" }`. This is usually a
+    /// placeable from another message format which was escaped instead of converted.
+    LiteralBrace(String),
+    /// Some variants of a selector end in whitespace and others do not, so the text around the
+    /// selector is spaced differently depending on which one is chosen.
+    TrailingWhitespace { variant: String },
+    /// The message is exactly the same as in the reference language, and has text which could
+    /// have been translated.
+    Untranslated,
+    /// A variable differs only by case from one which the reference language uses for the same
+    /// message, and so will never be given a value.
+    VariableCase { found: String, expected: String },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}: ", self.id, self.lang)?;
+        match &self.kind {
+            LintKind::LiteralBrace(literal) => {
+                write!(
+                    f,
+                    "string literal {:?} looks like an escaped placeable",
+                    literal
+                )
+            }
+            LintKind::TrailingWhitespace { variant } => write!(
+                f,
+                "variant [{}] ends in whitespace, but other variants do not",
+                variant
+            ),
+            LintKind::Untranslated => write!(f, "same as the reference language"),
+            LintKind::VariableCase { found, expected } => {
+                write!(f, "${} should probably be ${}", found, expected)
+            }
+        }
+    }
+}
+
+pub(crate) struct LintCheck {
+    reference: Option<LanguageIdentifier>,
+    handler: Box<dyn Fn(&Lint) + Send + Sync>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Check every resource for common mistakes as it is added, and call `handler` for each one
+    /// found. The resource is added either way. With a `reference` language, usually the one the
+    /// application is written in, messages are also compared against what is loaded for it at
+    /// that point, so the reference language should be loaded first.
+    ///
+    /// ```
+    /// use fluent_ergonomics::LintKind;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    ///
+    /// let lints = Arc::new(Mutex::new(Vec::new()));
+    /// let found = Arc::clone(&lints);
+    /// fluent.set_lint_handler(Some(en_id.clone()), move |lint| {
+    ///     found.lock().unwrap().push(lint.kind.clone())
+    /// });
+    /// fluent
+    ///     .add_from_text(en_id, String::from("save = Save\nhi = Hi, { $userName }"))
+    ///     .unwrap();
+    /// fluent
+    ///     .add_from_text(de_id, String::from("save = Save\nhi = Hallo, { $username }"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     *lints.lock().unwrap(),
+    ///     vec![
+    ///         LintKind::Untranslated,
+    ///         LintKind::VariableCase {
+    ///             found: String::from("username"),
+    ///             expected: String::from("userName")
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// The handler is shared by every clone of this translator which is created after this call.
+    pub fn set_lint_handler<F>(&mut self, reference: Option<LanguageIdentifier>, handler: F)
+    where
+        F: Fn(&Lint) + Send + Sync + 'static,
+    {
+        self.lints = Some(Arc::new(LintCheck {
+            reference: reference.as_ref().map(canonicalize_language),
+            handler: Box::new(handler),
+        }));
+    }
+
+    /// Lint the resources with these hashes, which have just been added to their languages.
+    pub(crate) fn lint_added(&self, added: &[(LanguageIdentifier, u64)]) {
+        let check = match &self.lints {
+            Some(check) => check,
+            None => return,
+        };
+        let mut resources: Vec<(LanguageIdentifier, Arc<FluentResource>)> = Vec::new();
+        let mut reference: Vec<Arc<FluentResource>> = Vec::new();
+        {
+            let bundles = self.bundles.read().recovered();
+            for (lang, hash) in added {
+                let entry = match bundles.get(lang) {
+                    Some(entry) => entry.read().recovered(),
+                    None => continue,
+                };
+                if let Some((_, resource)) = entry.resources.iter().find(|(h, _)| h == hash) {
+                    resources.push((lang.clone(), Arc::clone(resource)));
+                }
+            }
+            if let Some(entry) = check.reference.as_ref().and_then(|lang| bundles.get(lang)) {
+                reference = entry
+                    .read()
+                    .recovered()
+                    .resources
+                    .iter()
+                    .map(|(_, resource)| Arc::clone(resource))
+                    .collect();
+            }
+        }
+
+        let mut reference_entries = HashMap::new();
+        for resource in &reference {
+            for entry in resource.entries() {
+                if let Some((id, value, attributes)) = entry_parts(entry) {
+                    reference_entries.entry(id).or_insert((value, attributes));
+                }
+            }
+        }
+        for (lang, resource) in &resources {
+            let is_reference = check.reference.as_ref() == Some(lang);
+            for entry in resource.entries() {
+                let (id, value, attributes) = match entry_parts(entry) {
+                    Some(parts) => parts,
+                    None => continue,
+                };
+                let mut kinds = Vec::new();
+                for pattern in value.into_iter().chain(attributes.iter().map(|a| &a.value)) {
+                    pattern_lints(pattern, &mut kinds);
+                }
+                if let (false, Some((ref_value, ref_attributes))) =
+                    (is_reference, reference_entries.get(&id))
+                {
+                    if value == *ref_value && attributes == *ref_attributes && has_words(value) {
+                        kinds.push(LintKind::Untranslated);
+                    }
+                    let mut expected = BTreeSet::new();
+                    for pattern in ref_value
+                        .iter()
+                        .copied()
+                        .chain(ref_attributes.iter().map(|a| &a.value))
+                    {
+                        pattern_variables(pattern, &mut expected);
+                    }
+                    let mut found = BTreeSet::new();
+                    for pattern in value.into_iter().chain(attributes.iter().map(|a| &a.value)) {
+                        pattern_variables(pattern, &mut found);
+                    }
+                    for name in found.difference(&expected) {
+                        if let Some(other) = expected.iter().find(|e| e.eq_ignore_ascii_case(name))
+                        {
+                            kinds.push(LintKind::VariableCase {
+                                found: String::from(*name),
+                                expected: String::from(*other),
+                            });
+                        }
+                    }
+                }
+                for kind in kinds {
+                    (check.handler)(&Lint {
+                        lang: lang.clone(),
+                        id: id.clone(),
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+}
+
+type EntryParts<'r> = (
+    String,
+    Option<&'r ast::Pattern<&'r str>>,
+    &'r [ast::Attribute<&'r str>],
+);
+
+fn entry_parts<'r>(entry: &'r ast::Entry<&'r str>) -> Option<EntryParts<'r>> {
+    match entry {
+        ast::Entry::Message(msg) => Some((
+            String::from(msg.id.name),
+            msg.value.as_ref(),
+            &msg.attributes,
+        )),
+        ast::Entry::Term(term) => Some((
+            format!("-{}", term.id.name),
+            Some(&term.value),
+            &term.attributes,
+        )),
+        _ => None,
+    }
+}
+
+/// Whether a pattern has any letters outside of its placeables.
+fn has_words(pattern: Option<&ast::Pattern<&str>>) -> bool {
+    pattern.is_some_and(|pattern| {
+        pattern.elements.iter().any(|element| match element {
+            ast::PatternElement::TextElement { value } => value.chars().any(char::is_alphabetic),
+            ast::PatternElement::Placeable { .. } => false,
+        })
+    })
+}
+
+fn pattern_lints(pattern: &ast::Pattern<&str>, kinds: &mut Vec<LintKind>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_lints(expression, kinds);
+        }
+    }
+}
+
+fn expression_lints(expression: &ast::Expression<&str>, kinds: &mut Vec<LintKind>) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            inline_lints(selector, kinds);
+            let trailing: Vec<bool> = variants
+                .iter()
+                .map(|variant| ends_in_whitespace(&variant.value))
+                .collect();
+            if trailing.contains(&true) && trailing.contains(&false) {
+                for (variant, _) in variants.iter().zip(trailing).filter(|(_, t)| *t) {
+                    kinds.push(LintKind::TrailingWhitespace {
+                        variant: variant_key(&variant.key),
+                    });
+                }
+            }
+            for variant in variants {
+                pattern_lints(&variant.value, kinds);
+            }
+        }
+        ast::Expression::Inline(inline) => inline_lints(inline, kinds),
+    }
+}
+
+fn inline_lints(inline: &ast::InlineExpression<&str>, kinds: &mut Vec<LintKind>) {
+    match inline {
+        ast::InlineExpression::StringLiteral { value } if value.contains(['{', '}']) => {
+            kinds.push(LintKind::LiteralBrace(String::from(*value)));
+        }
+        ast::InlineExpression::Placeable { expression } => expression_lints(expression, kinds),
+        _ => (),
+    }
+}
+
+fn ends_in_whitespace(pattern: &ast::Pattern<&str>) -> bool {
+    match pattern.elements.last() {
+        Some(ast::PatternElement::TextElement { value }) => value.ends_with(char::is_whitespace),
+        Some(ast::PatternElement::Placeable {
+            expression: ast::Expression::Inline(ast::InlineExpression::StringLiteral { value }),
+        }) => value.ends_with(char::is_whitespace),
+        _ => false,
+    }
+}
+
+fn variant_key(key: &ast::VariantKey<&str>) -> String {
+    match key {
+        ast::VariantKey::Identifier { name } => String::from(*name),
+        ast::VariantKey::NumberLiteral { value } => String::from(*value),
+    }
+}
+
+/// Collect the names of the variables that a pattern uses, including in selectors and the
+/// arguments of functions and terms.
+pub(crate) fn pattern_variables<'r>(pattern: &ast::Pattern<&'r str>, vars: &mut BTreeSet<&'r str>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_variables(expression, vars);
+        }
+    }
+}
+
+fn expression_variables<'r>(expression: &ast::Expression<&'r str>, vars: &mut BTreeSet<&'r str>) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            inline_variables(selector, vars);
+            for variant in variants {
+                pattern_variables(&variant.value, vars);
+            }
+        }
+        ast::Expression::Inline(inline) => inline_variables(inline, vars),
+    }
+}
+
+fn inline_variables<'r>(inline: &ast::InlineExpression<&'r str>, vars: &mut BTreeSet<&'r str>) {
+    let arguments = match inline {
+        ast::InlineExpression::VariableReference { id } => {
+            vars.insert(id.name);
+            None
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => Some(arguments),
+        ast::InlineExpression::TermReference { arguments, .. } => arguments.as_ref(),
+        ast::InlineExpression::Placeable { expression } => {
+            expression_variables(expression, vars);
+            None
+        }
+        _ => None,
+    };
+    if let Some(arguments) = arguments {
+        for argument in &arguments.positional {
+            inline_variables(argument, vars);
+        }
+        for argument in &arguments.named {
+            inline_variables(&argument.value, vars);
+        }
+    }
+}
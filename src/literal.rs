@@ -0,0 +1,177 @@
+use crate::{
+    BundleEntry, BundleKind, Case, Error, FluentErgo, FrozenFluentErgo, Options, PoisonExt,
+    TrOptions,
+};
+use fluent::{FluentArgs, FluentResource};
+use fluent_syntax::ast;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// The text of every message in a bundle whose value has no placeables, by message ID. These
+/// messages always format to the same text, so they are served from here without asking Fluent.
+pub(crate) type Literals = HashMap<String, Arc<str>>;
+
+impl<K: BundleKind> BundleEntry<K> {
+    /// Record the literal messages of a resource that is about to be added to the bundle. This
+    /// must be called before the resource is added, to tell which messages the bundle already
+    /// has: without `replace`, Fluent keeps those, and with it, the new ones win.
+    pub(crate) fn record_literals(&mut self, resource: &FluentResource, replace: bool) {
+        let mut seen = HashSet::new();
+        for entry in resource.entries() {
+            let msg = match entry {
+                ast::Entry::Message(msg) => msg,
+                _ => continue,
+            };
+            let id = msg.id.name;
+            let first = seen.insert(id);
+            if !replace && (!first || K::has_value(&self.bundle, id).is_some()) {
+                continue;
+            }
+            match msg.value.as_ref().and_then(literal_text) {
+                Some(text) => {
+                    self.literals.insert(String::from(id), Arc::from(text));
+                }
+                None => {
+                    self.literals.remove(id);
+                }
+            }
+        }
+    }
+
+    /// `Some(None)` if the bundle has a value for the message but it is not literal, and `None`
+    /// if it has no value.
+    fn literal(&self, msgid: &str) -> Option<Option<Arc<str>>> {
+        if let Some(text) = self.literals.get(msgid) {
+            return Some(Some(Arc::clone(text)));
+        }
+        match K::has_value(&self.bundle, msgid) {
+            Some(true) => Some(None),
+            _ => None,
+        }
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Like `tr`, but returns a shared string. Messages without placeables are interned: every
+    /// call returns the same allocation, so a UI which keeps thousands of labels alive holds one
+    /// copy of each. Other messages are formatted into a new allocation each time, since their
+    /// terms and functions may give a different result on every call.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// let first = fluent.tr_arc("units", None).unwrap();
+    /// let second = fluent.tr_arc("units", None).unwrap();
+    /// assert_eq!(&*first, "Units");
+    /// assert!(Arc::ptr_eq(&first, &second));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn tr_arc(&self, msgid: &str, args: Option<&FluentArgs>) -> Result<Arc<str>, Error> {
+        self.load_lazy(&self.languages)?;
+        match self.find_literal(msgid) {
+            Some(text) if self.options.limits.allows_len(text.len()) => Ok(text),
+            _ => self.tr(msgid, args).map(Arc::from),
+        }
+    }
+
+    /// The interned text of the message that `tr` would use, if it has no placeables.
+    fn find_literal(&self, msgid: &str) -> Option<Arc<str>> {
+        let bundles = self.bundles.read().recovered();
+        let overrides = self.overrides.read().recovered();
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.bundles.read().recovered())
+            .collect::<Vec<_>>();
+        for lang in &self.languages {
+            if let Some(overridden) = overrides.get(lang) {
+                if let Some(found) = overridden.entry.literal(msgid) {
+                    return found;
+                }
+            }
+            let entries = std::iter::once(&*bundles)
+                .chain(layers.iter().map(|layer| &**layer))
+                .filter_map(|bundles| bundles.get(lang));
+            for entry in entries {
+                if let Some(found) = entry.read().recovered().literal(msgid) {
+                    return found;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<K: BundleKind> FrozenFluentErgo<K> {
+    /// Translate into a shared string. This behaves exactly like `FluentErgo::tr_arc`.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn tr_arc(&self, msgid: &str, args: Option<&FluentArgs>) -> Result<Arc<str>, Error> {
+        for lang in &self.languages {
+            if let Some(text) = self
+                .literals
+                .get(lang)
+                .and_then(|literals| literals.get(msgid))
+            {
+                if self.options.limits.allows_len(text.len()) {
+                    return Ok(Arc::clone(text));
+                }
+                break;
+            }
+            let bundle = self.bundles.get(lang);
+            if bundle.and_then(|bundle| K::has_value(bundle, msgid)) == Some(true) {
+                break;
+            }
+        }
+        self.tr(msgid, args).map(Arc::from)
+    }
+}
+
+/// The text of a pattern which consists only of text. Text with isolation marks of its own is
+/// left to Fluent, since what becomes of the marks depends on the `Isolation` mode of each call.
+fn literal_text(pattern: &ast::Pattern<&str>) -> Option<String> {
+    pattern
+        .elements
+        .iter()
+        .map(|element| match element {
+            ast::PatternElement::TextElement { value }
+                if !value.contains(['\u{2068}', '\u{2069}']) =>
+            {
+                Some(*value)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Append a literal message to `out`, as `write_message` would have.
+pub(crate) fn write_literal(
+    options: &Options,
+    tr_options: &TrOptions,
+    lang: &LanguageIdentifier,
+    msgid: &str,
+    text: &str,
+    out: &mut String,
+) -> Result<(), Error> {
+    if tr_options.case == Case::AsWritten {
+        options.limits.check_len(msgid, lang, text.len())?;
+        out.push_str(text);
+    } else {
+        let text = tr_options.case.apply(String::from(text), lang);
+        options.limits.check_len(msgid, lang, text.len())?;
+        out.push_str(&text);
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use crate::{BundleKind, FluentErgo, PoisonExt, UnparsedResource};
+use fluent::FluentResource;
+use fluent_syntax::ast;
+use std::collections::BTreeMap;
+use std::mem::size_of;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// How much memory one language takes, from `FluentErgo::memory_report`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LanguageMemory {
+    /// The language
+    pub lang: LanguageIdentifier,
+    /// How many resources have been parsed
+    pub resources: usize,
+    /// The size of the Fluent source of the parsed resources, in bytes
+    pub source_bytes: usize,
+    /// How many messages the parsed resources define
+    pub messages: usize,
+    /// How many terms the parsed resources define
+    pub terms: usize,
+    /// An estimate of the heap that the parsed resources and their bundle use, in bytes. This
+    /// includes the source, the syntax tree, and the translator's own bookkeeping, but not any
+    /// plural rules or other locale data that Fluent has cached.
+    pub estimated_heap_bytes: usize,
+    /// The size of lazy resources which have not been parsed yet, in bytes. Text is already in
+    /// memory; files are measured on disk.
+    pub pending_bytes: usize,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Summarize the memory that each language takes, sorted by language, so that an application
+    /// can budget how many languages to keep loaded and which ones to load lazily.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// let report = fluent.memory_report();
+    /// assert_eq!(report[0].messages, 1);
+    /// assert!(report[0].estimated_heap_bytes > report[0].source_bytes);
+    /// ```
+    ///
+    /// Resources which are shared with other languages or other translators are counted in full
+    /// for each of them.
+    pub fn memory_report(&self) -> Vec<LanguageMemory> {
+        let mut report: BTreeMap<String, LanguageMemory> = BTreeMap::new();
+        for (lang, resources) in self.lazy.lock().recovered().iter() {
+            let memory = report
+                .entry(lang.to_string())
+                .or_insert_with(|| LanguageMemory {
+                    lang: lang.clone(),
+                    ..LanguageMemory::default()
+                });
+            memory.pending_bytes += resources.iter().map(pending_size).sum::<usize>();
+        }
+
+        let bundles = self.bundles.read().recovered();
+        for (lang, entry) in bundles.iter() {
+            let entry = entry.read().recovered();
+            let memory = report
+                .entry(lang.to_string())
+                .or_insert_with(|| LanguageMemory {
+                    lang: lang.clone(),
+                    ..LanguageMemory::default()
+                });
+            memory.resources = entry.resources.len();
+            let mut heap = entry.resources.len() * (size_of::<(u64, Arc<FluentResource>)>() + 8);
+            for (_, resource) in &entry.resources {
+                memory.source_bytes += resource.source().len();
+                heap += resource.source().len();
+                for entry in resource.entries() {
+                    heap += size_of::<ast::Entry<&str>>();
+                    match entry {
+                        ast::Entry::Message(msg) => {
+                            memory.messages += 1;
+                            heap += bundle_slot(msg.id.name);
+                            heap += msg.value.as_ref().map_or(0, pattern_size);
+                            heap += attributes_size(&msg.attributes);
+                        }
+                        ast::Entry::Term(term) => {
+                            memory.terms += 1;
+                            heap += bundle_slot(term.id.name);
+                            heap += pattern_size(&term.value);
+                            heap += attributes_size(&term.attributes);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            heap += entry
+                .literals
+                .iter()
+                .map(|(id, text)| bundle_slot(id) + text.len())
+                .sum::<usize>();
+            heap += entry
+                .definitions
+                .keys()
+                .map(|id| bundle_slot(id) + 8)
+                .sum::<usize>();
+            memory.estimated_heap_bytes = heap;
+        }
+        report.into_values().collect()
+    }
+}
+
+fn pending_size(resource: &UnparsedResource) -> usize {
+    match resource {
+        UnparsedResource::Text(text) => text.len(),
+        #[cfg(feature = "fs")]
+        UnparsedResource::File(path) => {
+            std::fs::metadata(path).map_or(0, |meta| meta.len() as usize)
+        }
+    }
+}
+
+/// The rough cost of one entry in a hash map keyed by `id`.
+fn bundle_slot(id: &str) -> usize {
+    id.len() + size_of::<(String, usize)>() + 8
+}
+
+fn attributes_size(attributes: &[ast::Attribute<&str>]) -> usize {
+    attributes
+        .iter()
+        .map(|attr| size_of::<ast::Attribute<&str>>() + pattern_size(&attr.value))
+        .sum()
+}
+
+/// The elements of a pattern, and those of any patterns within it, although the rest of each
+/// nested expression is only counted once.
+fn pattern_size(pattern: &ast::Pattern<&str>) -> usize {
+    pattern
+        .elements
+        .iter()
+        .map(|element| {
+            size_of::<ast::PatternElement<&str>>()
+                + match element {
+                    ast::PatternElement::Placeable {
+                        expression: ast::Expression::Select { variants, .. },
+                    } => variants
+                        .iter()
+                        .map(|variant| {
+                            size_of::<ast::Variant<&str>>() + pattern_size(&variant.value)
+                        })
+                        .sum(),
+                    ast::PatternElement::Placeable { .. } => size_of::<ast::Expression<&str>>(),
+                    ast::PatternElement::TextElement { .. } => 0,
+                }
+        })
+        .sum()
+}
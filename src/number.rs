@@ -0,0 +1,285 @@
+use crate::{BundleKind, Error, FluentErgo, LocalizedValue};
+use fluent::{FluentArgs, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+/// Spaces which languages that group digits with a space might use: a plain space, a no-break
+/// space, or a narrow no-break space.
+const SPACES: &[char] = &[' ', '\u{a0}', '\u{202f}'];
+
+/// The characters a language writes between the integer and fractional parts of a number, and
+/// between groups of digits.
+struct Separators {
+    decimal: &'static [char],
+    group: &'static [char],
+}
+
+impl Separators {
+    /// The separators for `lang`, from the common CLDR conventions. Languages which are not
+    /// listed use `.` and `,`, as English does.
+    fn for_language(lang: &LanguageIdentifier) -> Separators {
+        let (decimal, group): (&[char], &[char]) = match (
+            lang.language.as_str(),
+            lang.region.as_ref().map(|r| r.as_str()),
+        ) {
+            ("de", Some("CH")) | ("de", Some("LI")) | ("it", Some("CH")) => (&['.'], &['’', '\'']),
+            ("es", Some(region))
+                if [
+                    "MX", "US", "GT", "HN", "NI", "PA", "PE", "PR", "DO", "SV", "CU",
+                ]
+                .contains(&region) =>
+            {
+                (&['.'], &[','])
+            }
+            ("pt", Some("PT")) => (&[','], SPACES),
+            ("ar", _) | ("fa", _) => (&['٫', '.'], &['٬', ',']),
+            (
+                "bg" | "be" | "cs" | "et" | "fi" | "fr" | "hu" | "kk" | "lt" | "lv" | "nb" | "nn"
+                | "no" | "pl" | "ru" | "sk" | "sv" | "uk",
+                _,
+            ) => (&[','], SPACES),
+            (
+                "ca" | "da" | "de" | "el" | "es" | "hr" | "id" | "is" | "it" | "nl" | "pt" | "ro"
+                | "sl" | "sr" | "tr" | "vi",
+                _,
+            ) => (&[','], &['.']),
+            _ => (&['.'], &[',']),
+        };
+        Separators { decimal, group }
+    }
+}
+
+/// What `lang` puts between the ends of a range of numbers, from the CLDR range patterns.
+pub(crate) fn range_separator(lang: &LanguageIdentifier) -> &'static str {
+    match lang.language.as_str() {
+        "es" | "it" | "nl" | "pt" | "tr" | "zh" => "-",
+        "ja" => "～",
+        "ko" => "~",
+        _ => "–",
+    }
+}
+
+/// `RANGE($min, $max)`, which joins two numbers with `separator`. See
+/// `FluentErgo::add_function`.
+pub(crate) fn range_function<'a>(
+    positional: &[FluentValue<'a>],
+    separator: &str,
+) -> FluentValue<'a> {
+    let (min, max) = match positional {
+        [FluentValue::Number(min), FluentValue::Number(max)] => (min, max),
+        _ => return FluentValue::Error,
+    };
+    let (min, max) = (min.as_string(), max.as_string());
+    if min == max {
+        FluentValue::from(min.into_owned())
+    } else {
+        FluentValue::from(format!("{}{}{}", min, separator, max))
+    }
+}
+
+/// A fraction to show as a percentage in the language of the bundle, so that `Percent(0.25)` is
+/// 25% in English, 25 % in German and %25 in Turkish. It rounds to a whole percent; use the
+/// `PERCENT` function in the message for more digits.
+///
+/// ```
+/// use fluent::FluentArgs;
+/// use fluent_ergonomics::{FluentArgsExt, Percent};
+///
+/// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+/// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone()]);
+/// fluent
+///     .add_from_text(de_id, String::from("progress = { $done } erledigt"))
+///     .unwrap();
+///
+/// let mut args = FluentArgs::new();
+/// args.set_value("done", Percent(0.25));
+/// assert_eq!(fluent.tr("progress", Some(&args)).unwrap(), "25\u{a0}% erledigt");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Percent(pub f64);
+
+/// A fraction to show in per mille in the language of the bundle, as `Percent` does, so that
+/// `Permille(0.025)` is 25‰ in English.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Permille(pub f64);
+
+impl LocalizedValue for Percent {
+    fn format(&self, lang: &LanguageIdentifier) -> String {
+        PercentStyle::for_language(lang).format(self.0 * 100.0, '%', 0, 0)
+    }
+}
+
+impl LocalizedValue for Permille {
+    fn format(&self, lang: &LanguageIdentifier) -> String {
+        PercentStyle::for_language(lang).format(self.0 * 1000.0, '‰', 0, 0)
+    }
+}
+
+/// How a language writes a percentage, from the CLDR percent patterns.
+#[derive(Clone, Copy)]
+pub(crate) struct PercentStyle {
+    /// Whether the sign comes before the number
+    before: bool,
+    /// What goes between the number and the sign
+    space: &'static str,
+    decimal: char,
+}
+
+impl PercentStyle {
+    /// The style for `lang`. Languages which are not listed write the sign straight after the
+    /// number, as English does.
+    pub(crate) fn for_language(lang: &LanguageIdentifier) -> PercentStyle {
+        let (before, space) = match (
+            lang.language.as_str(),
+            lang.region.as_ref().map(|r| r.as_str()),
+        ) {
+            ("de", Some("CH")) | ("de", Some("LI")) | ("fr", Some("CH")) => (false, ""),
+            ("tr", _) => (true, ""),
+            ("eu", _) => (true, "\u{a0}"),
+            ("fr", _) => (false, "\u{202f}"),
+            (
+                "be" | "ca" | "cs" | "da" | "de" | "es" | "fi" | "hr" | "kk" | "lt" | "lv" | "nb"
+                | "nn" | "no" | "ro" | "ru" | "sk" | "sl" | "sv" | "uk",
+                _,
+            ) => (false, "\u{a0}"),
+            _ => (false, ""),
+        };
+        let decimal = Separators::for_language(lang).decimal[0];
+        PercentStyle {
+            before,
+            space,
+            decimal,
+        }
+    }
+
+    /// `value`, already multiplied by 100 or 1000, rounded to at most `max_digits` fractional
+    /// digits and written with `sign`. Trailing zeros are dropped down to `min_digits`.
+    fn format(&self, value: f64, sign: char, min_digits: usize, max_digits: usize) -> String {
+        let max_digits = max_digits.max(min_digits);
+        let mut number = format!("{:.*}", max_digits, value);
+        if let Some(point) = number.find('.') {
+            let keep = point + 1 + min_digits;
+            while number.len() > keep && number.ends_with('0') {
+                number.pop();
+            }
+            if number.ends_with('.') {
+                number.pop();
+            }
+        }
+        if number.starts_with('-') && number[1..].chars().all(|ch| ch == '0' || ch == '.') {
+            number.remove(0);
+        }
+        let number = number.replace('.', self.decimal.encode_utf8(&mut [0; 4]));
+        if self.before {
+            format!("{}{}{}", sign, self.space, number)
+        } else {
+            format!("{}{}{}", number, self.space, sign)
+        }
+    }
+}
+
+/// `PERCENT($value)` and `PERMILLE($value)`, which write a fraction with `sign` in `style`,
+/// multiplied by `scale`. See `FluentErgo::add_function`.
+pub(crate) fn percent_function<'a>(
+    positional: &[FluentValue<'a>],
+    named: &FluentArgs,
+    style: PercentStyle,
+    sign: char,
+    scale: f64,
+) -> FluentValue<'a> {
+    let number = match positional {
+        [FluentValue::Number(number)] => number,
+        _ => return FluentValue::Error,
+    };
+    let digits = |name: &str, set: Option<usize>| match named.get(name) {
+        Some(FluentValue::Number(digits)) if digits.value >= 0.0 => Some(digits.value as usize),
+        _ => set,
+    };
+    let min_digits = digits(
+        "minimumFractionDigits",
+        number.options.minimum_fraction_digits,
+    )
+    .unwrap_or(0);
+    let max_digits = digits(
+        "maximumFractionDigits",
+        number.options.maximum_fraction_digits,
+    )
+    .unwrap_or(0);
+    FluentValue::from(style.format(number.value * scale, sign, min_digits, max_digits))
+}
+
+/// The value of a decimal digit in the Latin, Arabic-Indic, Persian, or Devanagari scripts.
+fn digit_value(ch: char) -> Option<u32> {
+    let zero = match ch {
+        '0'..='9' => '0',
+        '\u{660}'..='\u{669}' => '\u{660}',
+        '\u{6f0}'..='\u{6f9}' => '\u{6f0}',
+        '\u{966}'..='\u{96f}' => '\u{966}',
+        _ => return None,
+    };
+    Some(ch as u32 - zero as u32)
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Read a number that a user typed, using the decimal and grouping separators of the first
+    /// language of the fallback chain, so that a form which displays `1.234,5` to a German user
+    /// can read it back.
+    ///
+    /// ```
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let fluent = fluent_ergonomics::FluentErgo::new(&[de_id]);
+    /// assert_eq!(fluent.parse_number("-1.234,5").unwrap(), -1234.5);
+    /// ```
+    ///
+    /// Surrounding whitespace and a leading sign are allowed. Grouping separators are optional,
+    /// but may only appear between digits of the integer part. Arabic-Indic, Persian, and
+    /// Devanagari digits are understood as well as Latin ones.
+    ///
+    /// # Errors
+    ///
+    /// * InvalidNumber -- the text is not a number in the language
+    ///
+    pub fn parse_number(&self, text: &str) -> Result<f64, Error> {
+        let lang = self.languages.first().cloned().unwrap_or_default();
+        let invalid = || Error::InvalidNumber {
+            text: String::from(text),
+            lang: lang.clone(),
+        };
+        let separators = Separators::for_language(&lang);
+
+        let trimmed = text.trim();
+        let mut chars = trimmed.chars().peekable();
+        let mut number = String::with_capacity(trimmed.len());
+        match chars.peek() {
+            Some('-') | Some('\u{2212}') => {
+                number.push('-');
+                chars.next();
+            }
+            Some('+') => {
+                chars.next();
+            }
+            _ => (),
+        }
+
+        let mut digits = false;
+        let mut after_group = false;
+        let mut after_decimal = false;
+        for ch in chars {
+            if let Some(value) = digit_value(ch) {
+                number.push(char::from_digit(value, 10).ok_or_else(invalid)?);
+                digits = true;
+                after_group = false;
+            } else if separators.decimal.contains(&ch) && !after_decimal && !after_group {
+                number.push('.');
+                after_decimal = true;
+            } else if separators.group.contains(&ch) && digits && !after_decimal && !after_group {
+                after_group = true;
+            } else {
+                return Err(invalid());
+            }
+        }
+        if !digits || after_group {
+            return Err(invalid());
+        }
+        number.parse().map_err(|_| invalid())
+    }
+}
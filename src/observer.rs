@@ -0,0 +1,64 @@
+use crate::{BundleKind, FluentErgo, Options};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use unic_langid::LanguageIdentifier;
+
+type ObserverFn = dyn Fn(&str, &LanguageIdentifier, Duration) + Send + Sync;
+
+/// The callback from `FluentErgo::set_observer`.
+pub(crate) struct Observer(Box<ObserverFn>);
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+impl Options {
+    /// When a translation started, if anything is observing translations.
+    pub(crate) fn observe_start(&self) -> Option<Instant> {
+        self.observer.as_ref().map(|_| Instant::now())
+    }
+
+    /// Report a message which was found in `lang`.
+    pub(crate) fn observe(&self, msgid: &str, lang: &LanguageIdentifier, started: Option<Instant>) {
+        if let (Some(observer), Some(started)) = (&self.observer, started) {
+            (observer.0)(msgid, lang, started.elapsed());
+        }
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Call `observer` after every translation which finds its message, with the message ID, the
+    /// language it was found in, and how long the translation took, so that an application can
+    /// measure which strings are rendered most and what formatting costs. Messages from a
+    /// `MissingMessageResolver`, and messages which are not found, are not reported.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// let counts = Arc::new(Mutex::new(Vec::new()));
+    /// let seen = Arc::clone(&counts);
+    /// fluent.set_observer(move |msgid, lang, _duration| {
+    ///     seen.lock().unwrap().push(format!("{} {}", msgid, lang))
+    /// });
+    /// fluent.tr("units", None).unwrap();
+    /// assert!(fluent.tr("swimming", None).is_err());
+    /// assert_eq!(*counts.lock().unwrap(), vec!["units en-US"]);
+    /// ```
+    ///
+    /// The observer runs on the thread that translates, so it should be quick. It is shared by
+    /// every clone of this translator which is created after this call, and by a frozen
+    /// translator.
+    pub fn set_observer<F>(&mut self, observer: F)
+    where
+        F: Fn(&str, &LanguageIdentifier, Duration) + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.options).observer = Some(Arc::new(Observer(Box::new(observer))));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{
+    canonicalize_language, parse_text, BundleEntry, BundleKind, Error, FluentErgo, PoisonExt,
+};
+use fluent::FluentResource;
+use fluent_syntax::ast;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use unic_langid::LanguageIdentifier;
+
+/// Where the text of a message came from, from `FluentErgo::origin_of`.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Origin {
+    /// A file, added with `add_from_file`, `add_from_dir`, or as a lazy resource
+    File(PathBuf),
+    /// A catalog which an `OtaClient` downloaded from this URL
+    Url(String),
+    /// A file inside an archive, added with `add_from_archive`
+    Archive { archive: PathBuf, entry: String },
+    /// Text which was added with `add_labeled_text`, and its label
+    Label(String),
+    /// Text which was added without a label
+    Text,
+    /// An override from `set_override`
+    Override,
+    /// An overlay for this environment, from `add_environment_text` or `add_environment_file`
+    Environment(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Url(url) => write!(f, "{}", url),
+            Origin::Archive { archive, entry } => write!(f, "{}!/{}", archive.display(), entry),
+            Origin::Label(label) => write!(f, "{}", label),
+            Origin::Text => write!(f, "text"),
+            Origin::Override => write!(f, "override"),
+            Origin::Environment(environment) => write!(f, "{} overlay", environment),
+        }
+    }
+}
+
+impl<K: BundleKind> BundleEntry<K> {
+    /// The definition that the bundle uses for `msgid`, or for a term if it starts with `-`, and
+    /// the hash of the resource it is in. Without `replace`, Fluent keeps the first definition of
+    /// a message, and resources added with it replace earlier ones.
+    pub(crate) fn definition(&self, msgid: &str) -> Option<(u64, &ast::Entry<&str>)> {
+        let (resource, position) = *self.definitions.get(msgid)?;
+        let (hash, resource) = &self.resources[resource];
+        Some((*hash, resource.get_entry(position)?))
+    }
+
+    /// The definition that the bundle uses for each message and term, by ID, as `definition`
+    /// finds them.
+    pub(crate) fn definitions(&self) -> impl Iterator<Item = (&str, &ast::Entry<&str>)> {
+        self.definitions.keys().filter_map(move |id| {
+            let (_, definition) = self.definition(id)?;
+            Some((id.as_str(), definition))
+        })
+    }
+
+    /// Record where the definitions of a resource which was just added to the end of the
+    /// resources are, for the messages and terms that the bundle will use them for.
+    pub(crate) fn index_definitions(&mut self, resource: &FluentResource, replace: bool) {
+        let index = self.resources.len() - 1;
+        let mut seen = HashSet::new();
+        for (position, entry) in resource.entries().enumerate() {
+            let id = match entry {
+                ast::Entry::Message(msg) => String::from(msg.id.name),
+                ast::Entry::Term(term) => format!("-{}", term.id.name),
+                _ => continue,
+            };
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if replace || !self.definitions.contains_key(&id) {
+                self.definitions.insert(id, (index, position));
+            }
+        }
+    }
+
+    /// Where the resource that the bundle uses for `msgid` came from.
+    pub(crate) fn origin_of(&self, msgid: &str) -> Option<Origin> {
+        self.definition(msgid)
+            .map(|(hash, _)| self.origins.get(&hash).cloned().unwrap_or(Origin::Text))
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Find where the text that `lang` uses for `msgid` came from, so that a wrong string seen in
+    /// production can be traced to the file that needs fixing. `None` means that `lang` has no
+    /// such message, although `tr` may still find it in a fallback language. Lazy resources
+    /// only count once they have been loaded.
+    ///
+    /// ```
+    /// use fluent_ergonomics::Origin;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_labeled_text(en_id.clone(), "billing", String::from("units = Units"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     fluent.origin_of("units", &en_id),
+    ///     Some(Origin::Label(String::from("billing")))
+    /// );
+    /// assert_eq!(fluent.origin_of("swimming", &en_id), None);
+    /// ```
+    pub fn origin_of(&self, msgid: &str, lang: &LanguageIdentifier) -> Option<Origin> {
+        let lang = canonicalize_language(lang);
+        let bundles = self.bundles.read().recovered();
+        let overrides = self.overrides.read().recovered();
+        if let Some(overridden) = overrides.get(&lang) {
+            if overridden.entry.origin_of(msgid).is_some() {
+                return Some(Origin::Override);
+            }
+        }
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.bundles.read().recovered())
+            .collect::<Vec<_>>();
+        std::iter::once(&*bundles)
+            .chain(layers.iter().map(|layer| &**layer))
+            .filter_map(|bundles| bundles.get(&lang))
+            .find_map(|entry| entry.read().recovered().origin_of(msgid))
+    }
+
+    /// Like `add_from_text`, but `origin_of` reports the messages in `text` as coming from
+    /// `label`, such as the name of the table or embedded file they came from.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `add_from_text` can return
+    ///
+    pub fn add_labeled_text(
+        &mut self,
+        lang: LanguageIdentifier,
+        label: &str,
+        text: String,
+    ) -> Result<(), Error> {
+        self.accept_languages(std::slice::from_ref(&lang))?;
+        let mut res =
+            parse_text(&self.options, text, None).map_err(|err| err.in_language(&lang))?;
+        res.origin = Origin::Label(String::from(label));
+        self.add_parsed(vec![(lang, res)])
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{parse_text, BundleKind, Error, FluentErgo, Origin, PoisonExt};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use unic_langid::LanguageIdentifier;
+
+/// Downloads updated catalogs from a translation service, so that copy fixes reach users without
+/// a new release of the application. Each language is one Fluent file, fetched from a URL in
+/// which `{lang}` is replaced by the language tag:
+///
+/// ```no_run
+/// use fluent_ergonomics::{FluentErgo, OtaClient};
+/// use std::time::Duration;
+///
+/// let fluent = FluentErgo::new(&["eo".parse().unwrap(), "en-US".parse().unwrap()]);
+/// let mut client = OtaClient::new("https://cdn.example.com/translations/{lang}.ftl");
+/// client.set_interval(Duration::from_secs(15 * 60));
+/// let handle = client.spawn(fluent.clone());
+/// ```
+///
+/// A downloaded file replaces everything that was loaded for its language. Every file is parsed
+/// before any of them are used, and if one of them is invalid, the translator keeps what it had.
+/// A language which the service does not have (a 404 response) is left alone.
+///
+/// This requires the `http` feature.
+#[derive(Clone, Debug)]
+pub struct OtaClient {
+    url: String,
+    interval: Duration,
+}
+
+impl OtaClient {
+    /// A client for the catalogs at `url`, which should contain `{lang}`. By default, it checks
+    /// for updates once an hour.
+    pub fn new(url: &str) -> OtaClient {
+        OtaClient {
+            url: String::from(url),
+            interval: Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// Set how long `spawn` waits between checks.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Download the catalog of every language that `fluent` searches, and swap in the ones which
+    /// have changed.
+    ///
+    /// # Errors
+    ///
+    /// * `DownloadError`
+    /// * `DuplicateMessages`
+    /// * `FluentParserError`
+    ///
+    pub fn pull<K: BundleKind>(&self, fluent: &FluentErgo<K>) -> Result<(), Error> {
+        let mut resources = Vec::new();
+        for lang in fluent.configured_languages() {
+            let text = match self.download(lang)? {
+                Some(text) => text,
+                None => continue,
+            };
+            let mut res =
+                parse_text(&fluent.options, text, None).map_err(|err| err.in_language(lang))?;
+            res.origin = Origin::Url(self.url.replace("{lang}", &lang.to_string()));
+            if !fluent.has_exactly(lang, res.hash) {
+                resources.push((lang.clone(), res));
+            }
+        }
+        if resources.is_empty() {
+            return Ok(());
+        }
+        fluent.swap_bundles(resources, false)
+    }
+
+    /// Call `pull` on a background thread every interval, starting straight away, until the
+    /// handle is stopped. Clones of `fluent` see the updates.
+    pub fn spawn<K: BundleKind>(self, fluent: FluentErgo<K>) -> OtaHandle
+    where
+        FluentErgo<K>: Send,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let last_error = Arc::new(Mutex::new(None));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            let last_error = Arc::clone(&last_error);
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    if let Err(err) = self.pull(&fluent) {
+                        *last_error.lock().recovered() = Some(err);
+                    }
+                    let next = Instant::now() + self.interval;
+                    while !stop.load(Ordering::SeqCst) && Instant::now() < next {
+                        thread::park_timeout(next.saturating_duration_since(Instant::now()));
+                    }
+                }
+            })
+        };
+        OtaHandle {
+            stop,
+            last_error,
+            thread,
+        }
+    }
+
+    fn download(&self, lang: &LanguageIdentifier) -> Result<Option<String>, Error> {
+        let url = self.url.replace("{lang}", &lang.to_string());
+        let download_error = |message: String| Error::DownloadError {
+            url: url.clone(),
+            message,
+        };
+        let response = match ureq::get(&url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(err) => return Err(download_error(err.to_string())),
+        };
+        let mut text = String::new();
+        response
+            .into_reader()
+            .read_to_string(&mut text)
+            .map_err(|err| download_error(err.to_string()))?;
+        Ok(Some(text))
+    }
+}
+
+/// The background thread started by `OtaClient::spawn`.
+#[derive(Debug)]
+pub struct OtaHandle {
+    stop: Arc<AtomicBool>,
+    last_error: Arc<Mutex<Option<Error>>>,
+    thread: JoinHandle<()>,
+}
+
+impl OtaHandle {
+    /// The most recent error from the background thread, if there has been one since the last
+    /// call.
+    pub fn take_error(&self) -> Option<Error> {
+        self.last_error.lock().recovered().take()
+    }
+
+    /// Stop checking for updates, and wait for the thread to finish. A download which is already
+    /// in progress is allowed to finish first.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.thread().unpark();
+        let _ = self.thread.join();
+    }
+}
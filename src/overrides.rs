@@ -0,0 +1,106 @@
+use crate::{
+    canonicalize_language, parse_text, BundleEntry, BundleKind, Error, FluentErgo, PoisonExt,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// The overridden messages of one language, and a bundle built from them.
+pub(crate) struct OverrideEntry<K: BundleKind> {
+    sources: BTreeMap<String, String>,
+    pub(crate) entry: BundleEntry<K>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Replace the text of one message in one language at runtime, without touching the loaded
+    /// resources, for copy experiments and emergency fixes. `text` is Fluent source for the
+    /// message's value, so it can use the same arguments as the original:
+    ///
+    /// ```
+    /// use fluent::FluentArgs;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("cta = Sign up, { $name }"))
+    ///     .unwrap();
+    /// fluent.set_override("cta", &en_id, "Join now, { $name }!").unwrap();
+    ///
+    /// let mut args = FluentArgs::new();
+    /// args.set("name", "Ana");
+    /// assert_eq!(fluent.tr("cta", Some(&args)).unwrap(), "Join now, Ana!");
+    /// ```
+    ///
+    /// An override is searched just before the bundle of its language, so overriding the English
+    /// copy does not hide a German translation from German users. Setting an override again
+    /// replaces it. Overrides are shared by every clone of this translator, and a translator
+    /// with overrides cannot be frozen.
+    ///
+    /// # Errors
+    ///
+    /// * FluentParserError -- `text` is not a valid Fluent pattern. The previous overrides are
+    ///   kept.
+    ///
+    pub fn set_override(
+        &mut self,
+        msgid: &str,
+        lang: &LanguageIdentifier,
+        text: &str,
+    ) -> Result<(), Error> {
+        let lang = canonicalize_language(lang);
+        let mut sources = self.override_sources(&lang)?;
+        sources.insert(String::from(msgid), String::from(text));
+        self.install_overrides(lang, sources)
+    }
+
+    /// Remove every override, in every language.
+    pub fn clear_overrides(&mut self) {
+        self.overrides.write().recovered().clear();
+        self.invalidate_missing();
+    }
+
+    /// The text of every overridden message in `lang`.
+    pub(crate) fn override_sources(
+        &self,
+        lang: &LanguageIdentifier,
+    ) -> Result<BTreeMap<String, String>, Error> {
+        Ok(self
+            .overrides
+            .read()
+            .or_poisoned()?
+            .get(lang)
+            .map(|overrides| overrides.sources.clone())
+            .unwrap_or_default())
+    }
+
+    /// Replace the overrides of `lang` with `sources`.
+    pub(crate) fn install_overrides(
+        &self,
+        lang: LanguageIdentifier,
+        sources: BTreeMap<String, String>,
+    ) -> Result<(), Error> {
+        if sources.is_empty() {
+            self.overrides.write().or_poisoned()?.remove(&lang);
+            self.invalidate_missing();
+            return Ok(());
+        }
+
+        // Continuation lines of a multi-line value must be indented.
+        let source = sources
+            .iter()
+            .map(|(id, text)| format!("{} = {}\n", id, text.replace('\n', "\n    ")))
+            .collect::<String>();
+        let res = parse_text(&self.options, source, None).map_err(|err| err.in_language(&lang))?;
+        let mut entry = BundleEntry::new(lang.clone(), &self.functions.read().or_poisoned()?);
+        entry
+            .add(res.hash, Arc::new(res.resource), false)
+            .map_err(|errors| Error::from(errors).in_language(&lang))?;
+
+        self.overrides
+            .write()
+            .or_poisoned()?
+            .insert(lang, OverrideEntry { sources, entry });
+        self.invalidate_missing();
+        Ok(())
+    }
+}
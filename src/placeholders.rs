@@ -0,0 +1,112 @@
+use crate::lint::pattern_variables;
+use crate::{canonicalize_language, BundleEntry, BundleKind, FluentErgo, PoisonExt};
+use fluent_syntax::ast;
+use std::collections::{BTreeMap, BTreeSet};
+use unic_langid::LanguageIdentifier;
+
+/// A translation which uses different variables than the base language, from
+/// `FluentErgo::placeholder_mismatches`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaceholderMismatch {
+    pub lang: LanguageIdentifier,
+    pub id: String,
+    /// Variables which the base language uses, but this translation does not. The value will be
+    /// left out of the translated text.
+    pub missing: Vec<String>,
+    /// Variables which this translation uses, but the base language does not. Callers will not
+    /// pass them, so they show up as `{$name}`.
+    pub extra: Vec<String>,
+}
+
+impl<K: BundleKind> BundleEntry<K> {
+    /// The variables of every message, in its value and attributes, from the definition which
+    /// the bundle uses.
+    pub(crate) fn message_variables(&self) -> BTreeMap<&str, BTreeSet<&str>> {
+        let mut messages = BTreeMap::new();
+        for (hash, resource) in &self.resources {
+            for entry in resource.entries() {
+                let msg = match entry {
+                    ast::Entry::Message(msg) => msg,
+                    _ => continue,
+                };
+                if messages.contains_key(msg.id.name) && !self.overriding.contains(hash) {
+                    continue;
+                }
+                let mut vars = BTreeSet::new();
+                for pattern in msg
+                    .value
+                    .iter()
+                    .chain(msg.attributes.iter().map(|attr| &attr.value))
+                {
+                    pattern_variables(pattern, &mut vars);
+                }
+                messages.insert(msg.id.name, vars);
+            }
+        }
+        messages
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Check that every translation of a message uses the same variables as `base`, usually the
+    /// language that the application is written in. This is the most common mistake in
+    /// translations, and otherwise only shows up when the message is displayed. Every loaded
+    /// language is checked, sorted by language and then by message ID; messages which `base`
+    /// does not have are left to `diff_languages`. Lazy resources only count once they have been
+    /// loaded.
+    ///
+    /// ```
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("hi = Hi, { $name }"))
+    ///     .unwrap();
+    /// fluent
+    ///     .add_from_text(de_id, String::from("hi = Hallo, { $nam }"))
+    ///     .unwrap();
+    ///
+    /// let mismatches = fluent.placeholder_mismatches(&en_id);
+    /// assert_eq!(mismatches[0].missing, vec!["name"]);
+    /// assert_eq!(mismatches[0].extra, vec!["nam"]);
+    /// ```
+    pub fn placeholder_mismatches(&self, base: &LanguageIdentifier) -> Vec<PlaceholderMismatch> {
+        let base = canonicalize_language(base);
+        let bundles = self.bundles.read().recovered();
+        let base_entry = match bundles.get(&base) {
+            Some(entry) => entry.read().recovered(),
+            None => return Vec::new(),
+        };
+        let expected = base_entry.message_variables();
+        let mut languages: Vec<&LanguageIdentifier> =
+            bundles.keys().filter(|lang| **lang != base).collect();
+        languages.sort_by_key(|lang| lang.to_string());
+
+        let mut mismatches = Vec::new();
+        for lang in languages {
+            let entry = bundles[lang].read().recovered();
+            for (id, found) in entry.message_variables() {
+                let expected = match expected.get(id) {
+                    Some(expected) => expected,
+                    None => continue,
+                };
+                if *expected == found {
+                    continue;
+                }
+                mismatches.push(PlaceholderMismatch {
+                    lang: lang.clone(),
+                    id: String::from(id),
+                    missing: expected
+                        .difference(&found)
+                        .map(|v| String::from(*v))
+                        .collect(),
+                    extra: found
+                        .difference(expected)
+                        .map(|v| String::from(*v))
+                        .collect(),
+                });
+            }
+        }
+        mismatches
+    }
+}
@@ -0,0 +1,22 @@
+use crate::Error;
+use std::sync::{LockResult, PoisonError};
+
+/// What to do with a lock which another thread panicked while holding.
+pub(crate) trait PoisonExt<G> {
+    /// The guard, or `Error::Poisoned`, for methods which can return an error.
+    fn or_poisoned(self) -> Result<G, Error>;
+
+    /// The guard even if the lock is poisoned, for methods which cannot return an error. They
+    /// answer from whatever state the panicking thread left behind.
+    fn recovered(self) -> G;
+}
+
+impl<G> PoisonExt<G> for LockResult<G> {
+    fn or_poisoned(self) -> Result<G, Error> {
+        self.map_err(|_| Error::Poisoned)
+    }
+
+    fn recovered(self) -> G {
+        self.unwrap_or_else(PoisonError::into_inner)
+    }
+}
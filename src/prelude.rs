@@ -0,0 +1,114 @@
+//! Everything a small tool needs to start translating, with one import:
+//!
+//! ```
+//! use fluent_ergonomics::prelude::*;
+//!
+//! let mut fluent = SimpleFluent::new(&["eo", "en-US"]);
+//! fluent.add_text("en-US", "hello = Hello, { $name }");
+//!
+//! let mut args = FluentArgs::new();
+//! args.set_value("name", "Ana");
+//! assert_eq!(fluent.tr_args("hello", &args), "Hello, Ana");
+//! assert_eq!(fluent.tr("swimming"), "swimming");
+//! ```
+pub use crate::{Error, FluentArgsExt, FluentErgo};
+pub use fluent::FluentArgs;
+pub use unic_langid::LanguageIdentifier;
+
+use crate::language::parse_language;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::Arc;
+
+/// A translator for applications which would rather show a message identifier than handle an
+/// error. Nothing here returns a `Result`: a message which cannot be translated comes out as its
+/// identifier, and every error is passed to the error handler, which writes it to standard error
+/// unless `on_error` replaces it.
+///
+/// Applications which outgrow this can take the `FluentErgo` underneath with `translator`, or
+/// switch to it wholesale, since both load the same resources.
+#[derive(Clone)]
+pub struct SimpleFluent {
+    fluent: FluentErgo,
+    on_error: Arc<dyn Fn(&Error) + Send + Sync>,
+}
+
+impl SimpleFluent {
+    /// Construct a translator for a list of language tags, in the order in which they are
+    /// searched. Tags which are not language identifiers are reported and skipped.
+    pub fn new(tags: &[&str]) -> SimpleFluent {
+        let mut simple = SimpleFluent::from(FluentErgo::new(&[]));
+        let languages: Vec<LanguageIdentifier> = tags
+            .iter()
+            .filter_map(|tag| simple.report(parse_language(tag)))
+            .collect();
+        simple.fluent = FluentErgo::new(&languages);
+        simple
+    }
+
+    /// Handle errors with `handler` instead of writing them to standard error.
+    pub fn on_error<F>(&mut self, handler: F)
+    where
+        F: Fn(&Error) + Send + Sync + 'static,
+    {
+        self.on_error = Arc::new(handler);
+    }
+
+    /// Add translations for the language with the tag `tag`. If they cannot be added, the error is
+    /// reported and the translator is left as it was.
+    pub fn add_text(&mut self, tag: &str, text: &str) {
+        let added = parse_language(tag)
+            .and_then(|lang| self.fluent.add_from_text(lang, String::from(text)));
+        self.report(added);
+    }
+
+    /// Add a directory of translations, laid out as `FluentErgo::add_from_dir` describes. If
+    /// they cannot be added, the error is reported and the translator is left as it was.
+    #[cfg(feature = "fs")]
+    pub fn add_dir<P: AsRef<Path>>(&mut self, path: P) {
+        let added = self.fluent.add_from_dir(path.as_ref());
+        self.report(added);
+    }
+
+    /// Translate a message without arguments, or return the message identifier.
+    pub fn tr(&self, msgid: &str) -> String {
+        self.translate(msgid, None)
+    }
+
+    /// Translate a message with arguments, or return the message identifier.
+    pub fn tr_args(&self, msgid: &str, args: &FluentArgs) -> String {
+        self.translate(msgid, Some(args))
+    }
+
+    /// The translator underneath, for everything else.
+    pub fn translator(&self) -> &FluentErgo {
+        &self.fluent
+    }
+
+    fn translate(&self, msgid: &str, args: Option<&FluentArgs>) -> String {
+        self.report(self.fluent.tr(msgid, args))
+            .unwrap_or_else(|| String::from(msgid))
+    }
+
+    fn report<T>(&self, result: Result<T, Error>) -> Option<T> {
+        result.map_err(|err| (self.on_error)(&err)).ok()
+    }
+}
+
+impl fmt::Debug for SimpleFluent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimpleFluent")
+            .field("fluent", &self.fluent)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<FluentErgo> for SimpleFluent {
+    fn from(fluent: FluentErgo) -> SimpleFluent {
+        SimpleFluent {
+            fluent,
+            on_error: Arc::new(|err| eprintln!("{}", err)),
+        }
+    }
+}
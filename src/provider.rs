@@ -0,0 +1,167 @@
+use crate::{canonicalize_language, BundleKind, Error, FluentErgo, PoisonExt, UnparsedResource};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use unic_langid::LanguageIdentifier;
+
+/// A source of translations outside the file system, such as a database table or an object
+/// store. The translator asks it for each language the first time the language is needed.
+///
+/// ```
+/// use fluent_ergonomics::{Error, FluentErgo, ResourceProvider};
+/// use unic_langid::LanguageIdentifier;
+///
+/// struct Table;
+///
+/// impl ResourceProvider for Table {
+///     fn fetch(&self, lang: &LanguageIdentifier) -> Result<Vec<String>, Error> {
+///         Ok(match lang.language.as_str() {
+///             "eo" => vec![String::from("hello = Saluton")],
+///             _ => Vec::new(),
+///         })
+///     }
+/// }
+///
+/// let mut fluent = FluentErgo::new(&["eo".parse().unwrap()]);
+/// fluent.set_resource_provider(Table);
+/// assert_eq!(fluent.tr("hello", None).unwrap(), "Saluton");
+/// ```
+pub trait ResourceProvider: Send + Sync {
+    /// The Fluent source of every resource for `lang`. A language with no translations should
+    /// return an empty list rather than an error.
+    fn fetch(&self, lang: &LanguageIdentifier) -> Result<Vec<String>, Error>;
+}
+
+/// A provider, and the languages which it has already been asked for.
+pub(crate) struct Provider {
+    provider: Box<dyn ResourceProvider>,
+    fetches: Mutex<Fetches>,
+    /// Signalled whenever a fetch finishes, for the threads which wait on one.
+    finished: Condvar,
+    /// The languages that the translator searched when it was given the provider, and how many
+    /// of them are still to be fetched, so that translations do not lock anything once all of
+    /// them are.
+    languages: Vec<LanguageIdentifier>,
+    unfetched: AtomicUsize,
+}
+
+#[derive(Default)]
+struct Fetches {
+    done: HashSet<LanguageIdentifier>,
+    /// Languages which some thread is fetching right now
+    running: HashSet<LanguageIdentifier>,
+    /// Whether the provider panicked, which poisons it as a panic holding the lock would have
+    panicked: bool,
+}
+
+impl Provider {
+    /// Whether any of `languages` has not been fetched yet.
+    pub(crate) fn pending(&self, languages: &[LanguageIdentifier]) -> bool {
+        let fetches = self.fetches.lock().recovered();
+        languages.iter().any(|lang| !fetches.done.contains(lang))
+    }
+
+    /// Whether all of `languages` are known to be fetched, without taking the lock.
+    fn settled(&self, languages: &[LanguageIdentifier]) -> bool {
+        self.unfetched.load(Ordering::Acquire) == 0
+            && languages.iter().all(|lang| self.languages.contains(lang))
+    }
+}
+
+/// Marks a language as being fetched, until it is dropped, so that nothing waits forever on a
+/// provider which panicked.
+struct Running<'a> {
+    provider: &'a Provider,
+    lang: &'a LanguageIdentifier,
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        let mut fetches = self.provider.fetches.lock().recovered();
+        fetches.running.remove(self.lang);
+        fetches.panicked |= std::thread::panicking();
+        self.provider.finished.notify_all();
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Pull the resources of each language from `provider` the first time the language is
+    /// needed, as if they had been added with `add_lazy_from_text`. The provider is shared by
+    /// every clone of this translator which is created after this call, and each language is
+    /// fetched only once between them.
+    ///
+    /// If fetching a language fails, the error is returned from the `tr` call that needed it, and
+    /// the language will be fetched again next time. Errors in the fetched text are reported once,
+    /// like those of any lazy resource.
+    pub fn set_resource_provider<P>(&mut self, provider: P)
+    where
+        P: ResourceProvider + 'static,
+    {
+        let mut languages: Vec<LanguageIdentifier> = Vec::new();
+        for lang in &self.languages {
+            if !languages.contains(lang) {
+                languages.push(lang.clone());
+            }
+        }
+        self.provider = Some(Arc::new(Provider {
+            provider: Box::new(provider),
+            fetches: Mutex::new(Fetches::default()),
+            finished: Condvar::new(),
+            unfetched: AtomicUsize::new(languages.len()),
+            languages,
+        }));
+        self.invalidate_missing();
+    }
+
+    /// Fetch any of `languages` which the provider has not been asked for yet, and queue the
+    /// results as lazy resources. The provider's lock is not held while fetching, so a slow
+    /// language only holds up the translations which need that language; they wait for it, so
+    /// that every language is only fetched once.
+    pub(crate) fn fetch_provided(&self, languages: &[LanguageIdentifier]) -> Result<(), Error> {
+        let provider = match &self.provider {
+            Some(provider) => provider,
+            None => return Ok(()),
+        };
+        if provider.settled(languages) {
+            return Ok(());
+        }
+        for lang in languages {
+            let lang = canonicalize_language(lang);
+            let mut fetches = provider.fetches.lock().or_poisoned()?;
+            while fetches.running.contains(&lang) {
+                fetches = provider.finished.wait(fetches).or_poisoned()?;
+            }
+            if fetches.panicked {
+                return Err(Error::Poisoned);
+            }
+            if fetches.done.contains(&lang) {
+                continue;
+            }
+            fetches.running.insert(lang.clone());
+            drop(fetches);
+
+            let running = Running {
+                provider,
+                lang: &lang,
+            };
+            let texts = provider
+                .provider
+                .fetch(&lang)
+                .map_err(|err| err.in_language(&lang))?;
+            let mut fetches = provider.fetches.lock().or_poisoned()?;
+            if !texts.is_empty() {
+                let mut lazy = self.lazy.lock().or_poisoned()?;
+                lazy.entry(lang.clone())
+                    .or_default()
+                    .extend(texts.into_iter().map(UnparsedResource::Text));
+                self.lazy.update(&lazy);
+            }
+            if fetches.done.insert(lang.clone()) && provider.languages.contains(&lang) {
+                provider.unfetched.fetch_sub(1, Ordering::Release);
+            }
+            drop(fetches);
+            drop(running);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{parse_all, BundleEntry, BundleKind, Error, FluentErgo, PoisonExt, UnparsedResource};
+use fluent_syntax::ast;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+type ValidatorFn = dyn Fn(&CatalogDiff) -> Result<(), String> + Send + Sync;
+
+/// The callback from `FluentErgo::set_reload_validator`.
+pub(crate) struct ReloadValidator(Box<ValidatorFn>);
+
+impl fmt::Debug for ReloadValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ReloadValidator")
+    }
+}
+
+/// The messages which a reload adds, removes and changes, from `FluentErgo::reload_diff` and
+/// given to the validator from `FluentErgo::set_reload_validator`. Each list is sorted by
+/// language and then by message ID.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CatalogDiff {
+    /// Messages which only the new catalog has
+    pub added: Vec<(LanguageIdentifier, String)>,
+    /// Messages which only the old catalog has
+    pub removed: Vec<(LanguageIdentifier, String)>,
+    /// Messages whose value or attributes are different in the new catalog. Comments do not
+    /// count.
+    pub changed: Vec<(LanguageIdentifier, String)>,
+}
+
+impl CatalogDiff {
+    /// Whether the reload would leave every message as it is.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Compare the catalog that `replace_all` would load from `resources` against the one in use,
+    /// without changing anything, so that a tool can show what an edit does before it goes live.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("units = Units\nsave = Save"))
+    ///     .unwrap();
+    ///
+    /// let diff = fluent
+    ///     .reload_diff(vec![(en_id.clone(), String::from("units = Measures\nopen = Open"))])
+    ///     .unwrap();
+    /// assert_eq!(diff.added, vec![(en_id.clone(), String::from("open"))]);
+    /// assert_eq!(diff.removed, vec![(en_id.clone(), String::from("save"))]);
+    /// assert_eq!(diff.changed, vec![(en_id, String::from("units"))]);
+    /// ```
+    ///
+    /// Lazy resources which have not been loaded yet are not compared.
+    ///
+    /// # Errors
+    ///
+    /// All of the errors that `replace_all` can return, other than a veto.
+    ///
+    pub fn reload_diff<I>(&self, resources: I) -> Result<CatalogDiff, Error>
+    where
+        I: IntoIterator<Item = (LanguageIdentifier, String)>,
+    {
+        let resources = resources
+            .into_iter()
+            .map(|(lang, text)| (lang, UnparsedResource::Text(text)))
+            .collect();
+        let entries = self.build_entries(parse_all(&self.options, resources)?)?;
+        self.diff_entries(&entries, true)
+    }
+
+    /// Call `validator` before every reload from `replace_all` or an `OtaClient`, with what the
+    /// reload would change, so that an application can refuse a catalog which parses but is
+    /// still wrong, such as one which has lost most of its messages. If the validator returns an
+    /// error, the reload fails with `Error::ReloadVetoed` and the translator keeps the catalog it
+    /// had.
+    ///
+    /// ```
+    /// use fluent_ergonomics::Error;
+    ///
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("units = Units"))
+    ///     .unwrap();
+    /// fluent.set_reload_validator(|diff| match diff.removed.is_empty() {
+    ///     true => Ok(()),
+    ///     false => Err(String::from("messages would be removed")),
+    /// });
+    ///
+    /// match fluent.replace_all(vec![(en_id, String::from("save = Save"))]) {
+    ///     Err(Error::ReloadVetoed { reason }) => assert_eq!(reason, "messages would be removed"),
+    ///     _ => panic!("expected a veto"),
+    /// }
+    /// assert_eq!(fluent.tr("units", None).unwrap(), "Units");
+    /// ```
+    ///
+    /// The validator runs on the thread that reloads, before any lock is taken for the swap,
+    /// so it may translate with this translator. It is shared by every clone of this translator
+    /// which is created after this call.
+    pub fn set_reload_validator<F>(&mut self, validator: F)
+    where
+        F: Fn(&CatalogDiff) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.options).reload_validator =
+            Some(Arc::new(ReloadValidator(Box::new(validator))));
+    }
+
+    /// Ask the reload validator, if there is one, whether `entries` may be swapped in.
+    pub(crate) fn validate_reload(
+        &self,
+        entries: &HashMap<LanguageIdentifier, BundleEntry<K>>,
+        everything: bool,
+    ) -> Result<(), Error> {
+        let validator = match &self.options.reload_validator {
+            Some(validator) => validator,
+            None => return Ok(()),
+        };
+        let diff = self.diff_entries(entries, everything)?;
+        (validator.0)(&diff).map_err(|reason| Error::ReloadVetoed { reason })
+    }
+
+    /// Compare `entries` against the bundles in use. With `everything`, languages which
+    /// `entries` does not have count as removed.
+    fn diff_entries(
+        &self,
+        entries: &HashMap<LanguageIdentifier, BundleEntry<K>>,
+        everything: bool,
+    ) -> Result<CatalogDiff, Error> {
+        let bundles = self.bundles.read().or_poisoned()?;
+        let mut languages: Vec<&LanguageIdentifier> = entries.keys().collect();
+        if everything {
+            languages.extend(bundles.keys().filter(|lang| !entries.contains_key(lang)));
+        }
+        languages.sort_by_key(|lang| lang.to_string());
+
+        let mut diff = CatalogDiff::default();
+        for lang in languages {
+            let previous = match bundles.get(lang) {
+                Some(previous) => Some(previous.read().or_poisoned()?),
+                None => None,
+            };
+            let old = previous.as_deref().map(messages).unwrap_or_default();
+            let new = entries.get(lang).map(messages).unwrap_or_default();
+            for (id, message) in &new {
+                match old.get(id) {
+                    None => diff.added.push((lang.clone(), String::from(*id))),
+                    Some(previous) if previous != message => {
+                        diff.changed.push((lang.clone(), String::from(*id)))
+                    }
+                    Some(_) => {}
+                }
+            }
+            for id in old.keys().filter(|id| !new.contains_key(*id)) {
+                diff.removed.push((lang.clone(), String::from(*id)));
+            }
+        }
+        Ok(diff)
+    }
+}
+
+/// The value and attributes of a message.
+type Definition<'a> = (
+    &'a Option<ast::Pattern<&'a str>>,
+    &'a [ast::Attribute<&'a str>],
+);
+
+/// The definition of each message that the bundle uses, by ID.
+fn messages<K: BundleKind>(entry: &BundleEntry<K>) -> BTreeMap<&str, Definition<'_>> {
+    entry
+        .definitions()
+        .filter_map(|(id, definition)| match definition {
+            ast::Entry::Message(msg) => Some((id, (&msg.value, msg.attributes.as_slice()))),
+            _ => None,
+        })
+        .collect()
+}
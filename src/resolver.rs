@@ -0,0 +1,124 @@
+use crate::format::format_message;
+use crate::{
+    canonicalize_language, parse_text, BundleEntry, BundleKind, Error, FluentErgo, PoisonExt,
+    TrOptions,
+};
+use fluent::FluentArgs;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// Something to ask when no bundle has a message: a machine translation service, a database, or
+/// a generator which turns `settings-title` into "Settings title" so that untranslated builds are
+/// still usable.
+///
+/// ```
+/// use fluent::FluentArgs;
+/// use fluent_ergonomics::{FluentErgo, MissingMessageResolver, Resolution};
+/// use unic_langid::LanguageIdentifier;
+///
+/// struct ShowId;
+///
+/// impl MissingMessageResolver for ShowId {
+///     fn resolve(
+///         &self,
+///         msgid: &str,
+///         _args: Option<&FluentArgs>,
+///         _languages: &[LanguageIdentifier],
+///     ) -> Option<Resolution> {
+///         Some(Resolution::Text(format!("[{}]", msgid)))
+///     }
+/// }
+///
+/// let mut fluent = FluentErgo::new(&["en-US".parse().unwrap()]);
+/// fluent.set_missing_message_resolver(ShowId);
+/// assert_eq!(fluent.tr("untranslated", None).unwrap(), "[untranslated]");
+/// ```
+pub trait MissingMessageResolver: Send + Sync {
+    /// Produce a translation for `msgid`, which no bundle in `languages` has. Returning `None`
+    /// lets the translator report the message as missing.
+    fn resolve(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        languages: &[LanguageIdentifier],
+    ) -> Option<Resolution>;
+}
+
+/// What a `MissingMessageResolver` found.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resolution {
+    /// The finished translation, used exactly as it is. The resolver will be asked again the next
+    /// time the message is needed.
+    Text(String),
+    /// Fluent source for the message's value, such as `Hello, { $name }`, in the language `lang`.
+    /// It is formatted with the arguments like any other message, and is kept in a synthetic
+    /// bundle so that the resolver is not asked for this message again.
+    Pattern {
+        lang: LanguageIdentifier,
+        source: String,
+    },
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Ask `resolver` for messages that none of the bundles have, instead of failing straight
+    /// away. The resolver is shared by every clone of this translator which is created after this
+    /// call.
+    pub fn set_missing_message_resolver<R>(&mut self, resolver: R)
+    where
+        R: MissingMessageResolver + 'static,
+    {
+        self.resolver = Some(Arc::new(resolver));
+    }
+
+    /// Look for a message that the real bundles do not have, first among the messages that the
+    /// resolver has already produced, and then from the resolver itself.
+    pub(crate) fn resolve_missing(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        opts: &TrOptions,
+    ) -> Result<Option<String>, Error> {
+        let resolver = match &self.resolver {
+            Some(resolver) => resolver,
+            None => return Ok(None),
+        };
+
+        {
+            let synthetic = self.synthetic.read().or_poisoned()?;
+            let in_chain = self
+                .languages
+                .iter()
+                .filter_map(|lang| synthetic.get_key_value(lang));
+            let others = synthetic
+                .iter()
+                .filter(|(lang, _)| !self.languages.contains(lang));
+            for (lang, entry) in in_chain.chain(others) {
+                let result =
+                    format_message::<K>(&self.options, opts, lang, &entry.bundle, msgid, args)?;
+                if result.is_some() {
+                    return Ok(result);
+                }
+            }
+        }
+
+        match resolver.resolve(msgid, args, &self.languages) {
+            None => Ok(None),
+            Some(Resolution::Text(text)) => Ok(Some(text)),
+            Some(Resolution::Pattern { lang, source }) => {
+                let lang = canonicalize_language(&lang);
+                // Continuation lines of a multi-line value must be indented.
+                let text = format!("{} = {}", msgid, source.replace('\n', "\n    "));
+                let res =
+                    parse_text(&self.options, text, None).map_err(|err| err.in_language(&lang))?;
+                let mut synthetic = self.synthetic.write().or_poisoned()?;
+                let functions = self.functions.read().or_poisoned()?;
+                let entry = synthetic
+                    .entry(lang.clone())
+                    .or_insert_with(|| BundleEntry::new(lang.clone(), &functions));
+                // If another thread resolved the same message first, its message is kept.
+                let _ = entry.add(res.hash, Arc::new(res.resource), false);
+                format_message::<K>(&self.options, opts, &lang, &entry.bundle, msgid, args)
+            }
+        }
+    }
+}
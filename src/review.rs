@@ -0,0 +1,438 @@
+use crate::lint::pattern_variables;
+use crate::suggest::pattern_source;
+use crate::{
+    canonicalize_language, BundleEntry, BundleKind, Error, FluentErgo, LanguageDelta,
+    PlaceholderMismatch, PoisonExt,
+};
+use fluent_syntax::{ast, parser, serializer};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+use unic_langid::LanguageIdentifier;
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// A spreadsheet of every message of `reference_lang` beside its translation in
+    /// `target_lang`, as CSV, for reviewers who would rather not read Fluent files. There is one
+    /// row for the value and one for each attribute of every message and term, in the order of
+    /// the reference language, and the columns are:
+    ///
+    /// * `id` -- the message ID, with `.attribute` for an attribute
+    /// * `comment` -- the comment above the message in the reference language
+    /// * `source` and `target` -- the two texts, as Fluent source
+    /// * `status` -- `translated`; `unchanged` if the target is the same as the source; `missing`
+    ///   if the target language does not have it; or `obsolete` for a target message which the
+    ///   reference language no longer has, which come last
+    ///
+    /// ```
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id.clone(),
+    ///         String::from("# On the toolbar\nsave = Save, then quit\nquit = Quit"),
+    ///     )
+    ///     .unwrap();
+    /// fluent
+    ///     .add_from_text(de_id.clone(), String::from("save = Speichern, dann beenden"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     fluent.export_review(&en_id, &de_id).unwrap(),
+    ///     "id,comment,source,target,status\n\
+    ///      save,On the toolbar,\"Save, then quit\",\"Speichern, dann beenden\",translated\n\
+    ///      quit,,Quit,,missing\n"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * Any error from loading a lazy resource of either language, as for `tr`
+    ///
+    pub fn export_review(
+        &self,
+        reference_lang: &LanguageIdentifier,
+        target_lang: &LanguageIdentifier,
+    ) -> Result<String, Error> {
+        let reference_lang = canonicalize_language(reference_lang);
+        let target_lang = canonicalize_language(target_lang);
+        self.load_lazy(&[reference_lang.clone(), target_lang.clone()])?;
+        let bundles = self.bundles.read().or_poisoned()?;
+        let reference = bundles
+            .get(&reference_lang)
+            .map(|entry| entry.read().or_poisoned())
+            .transpose()?;
+        let target = bundles
+            .get(&target_lang)
+            .map(|entry| entry.read().or_poisoned())
+            .transpose()?;
+        let reference = reference.as_deref();
+        let target = target.as_deref();
+
+        let comments = reference.map(comments).unwrap_or_default();
+        let mut out = String::from("id,comment,source,target,status\n");
+        let reference_ids = reference.map(entry_ids).unwrap_or_default();
+        for id in &reference_ids {
+            let source = reference.and_then(|entry| entry.definition(id));
+            let translated = target.and_then(|entry| entry.definition(id));
+            let comment = comments.get(id.as_str()).map_or("", String::as_str);
+            for (key, source) in source.map(|(_, entry)| patterns(entry)).unwrap_or_default() {
+                let source = pattern_source(&key, source);
+                let target = translated
+                    .and_then(|(_, entry)| patterns(entry).into_iter().find(|(k, _)| *k == key))
+                    .map(|(key, pattern)| pattern_source(&key, pattern));
+                let status = match &target {
+                    None => "missing",
+                    Some(target) if *target == source => "unchanged",
+                    Some(_) => "translated",
+                };
+                let target = target.unwrap_or_default();
+                write_row(&mut out, &[&key, comment, &source, &target, status]);
+            }
+        }
+
+        let reference_ids: HashSet<&String> = reference_ids.iter().collect();
+        for id in target.map(entry_ids).unwrap_or_default() {
+            if reference_ids.contains(&id) {
+                continue;
+            }
+            let translated = target.and_then(|entry| entry.definition(&id));
+            for (key, pattern) in translated
+                .map(|(_, entry)| patterns(entry))
+                .unwrap_or_default()
+            {
+                let target = pattern_source(&key, pattern);
+                write_row(&mut out, &[&key, "", "", &target, "obsolete"]);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Apply the `target` column of a spreadsheet from `export_review` back to `target_lang`,
+    /// once reviewers have corrected it. Columns are found by their names in the first row, so
+    /// they may be reordered, and other columns are ignored. Blank targets, unchanged messages
+    /// and `obsolete` rows change nothing, and nothing is ever removed.
+    ///
+    /// A message whose new text uses different variables than `reference_lang` is not applied,
+    /// and is returned instead, so that the rest of a large spreadsheet still goes in. Everything
+    /// else is applied at once, as with `apply_delta`.
+    ///
+    /// ```
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(en_id.clone(), String::from("hi = Hi, { $name }\nquit = Quit"))
+    ///     .unwrap();
+    ///
+    /// let csv = "id,target\nhi,\"Hallo, { $nam }\"\nquit,Beenden\n";
+    /// let rejected = fluent.import_review(&en_id, &de_id, csv).unwrap();
+    /// assert_eq!(rejected[0].id, "hi");
+    /// assert_eq!(fluent.tr("quit", None).unwrap(), "Beenden");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * `IOError` -- the text is not CSV, or it has no `id` or `target` column
+    /// * `FluentParserError` -- a target is not valid Fluent. Nothing is applied.
+    /// * All of the errors that `apply_delta` can return
+    ///
+    pub fn import_review(
+        &mut self,
+        reference_lang: &LanguageIdentifier,
+        target_lang: &LanguageIdentifier,
+        csv: &str,
+    ) -> Result<Vec<PlaceholderMismatch>, Error> {
+        let reference_lang = canonicalize_language(reference_lang);
+        let target_lang = canonicalize_language(target_lang);
+        let mut rows = parse_csv(csv)?.into_iter();
+        let header = rows.next().unwrap_or_default();
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|column| column == name)
+                .ok_or_else(|| invalid(&format!("no {} column", name)))
+        };
+        let (id_column, target_column) = (column("id")?, column("target")?);
+        let status_column = column("status").ok();
+
+        // Gather the rows of each message: its value, and then its attributes.
+        let mut messages: Vec<Imported> = Vec::new();
+        for row in rows {
+            let status = status_column.and_then(|column| row.get(column));
+            if status.is_some_and(|status| status == "obsolete") {
+                continue;
+            }
+            let (key, target) = match (row.get(id_column), row.get(target_column)) {
+                (Some(key), Some(target)) if !target.is_empty() => (key, target),
+                _ => continue,
+            };
+            let (id, attribute) = match key.split_once('.') {
+                Some((id, attribute)) => (id, Some(attribute)),
+                None => (key.as_str(), None),
+            };
+            let index = match messages.iter().position(|message| message.id == id) {
+                Some(index) => index,
+                None => {
+                    messages.push(Imported {
+                        id: String::from(id),
+                        value: None,
+                        attributes: Vec::new(),
+                    });
+                    messages.len() - 1
+                }
+            };
+            match attribute {
+                Some(attribute) => messages[index]
+                    .attributes
+                    .push((String::from(attribute), target.clone())),
+                None => messages[index].value = Some(target.clone()),
+            }
+        }
+
+        self.load_lazy(&[reference_lang.clone(), target_lang.clone()])?;
+        let mut changed = String::new();
+        let mut mismatches = Vec::new();
+        {
+            let bundles = self.bundles.read().or_poisoned()?;
+            let reference = bundles
+                .get(&reference_lang)
+                .map(|entry| entry.read().or_poisoned())
+                .transpose()?;
+            let target = bundles
+                .get(&target_lang)
+                .map(|entry| entry.read().or_poisoned())
+                .transpose()?;
+            let expected = reference
+                .as_deref()
+                .map(BundleEntry::message_variables)
+                .unwrap_or_default();
+            for Imported {
+                id,
+                value,
+                attributes,
+            } in &messages
+            {
+                // Continuation lines of a multi-line value must be indented.
+                let mut source = format!("{} =", id);
+                for (prefix, text) in value
+                    .iter()
+                    .map(|value| ("", value))
+                    .chain(attributes.iter().map(|(name, text)| (name.as_str(), text)))
+                {
+                    if !prefix.is_empty() {
+                        source.push_str(&format!("\n    .{} =", prefix));
+                    }
+                    source.push(' ');
+                    source.push_str(&text.replace('\n', "\n    "));
+                }
+                source.push('\n');
+
+                let parsed = match parser::parse_runtime(source.as_str()) {
+                    Ok(parsed) => parsed,
+                    // `apply_delta` reports the error.
+                    Err(_) => {
+                        changed.push_str(&source);
+                        continue;
+                    }
+                };
+                let current = target
+                    .as_deref()
+                    .and_then(|entry| entry.definition(id))
+                    .map(|(_, entry)| definition_source(entry));
+                if current.as_deref() == Some(&serializer::serialize(&parsed)) {
+                    continue;
+                }
+                let found = match parsed.body.first() {
+                    Some(ast::Entry::Message(msg)) => {
+                        let mut vars = BTreeSet::new();
+                        for pattern in msg
+                            .value
+                            .iter()
+                            .chain(msg.attributes.iter().map(|attr| &attr.value))
+                        {
+                            pattern_variables(pattern, &mut vars);
+                        }
+                        Some(vars)
+                    }
+                    _ => None,
+                };
+                if let (Some(expected), Some(found)) = (expected.get(id.as_str()), found) {
+                    if *expected != found {
+                        mismatches.push(PlaceholderMismatch {
+                            lang: target_lang.clone(),
+                            id: id.clone(),
+                            missing: expected
+                                .difference(&found)
+                                .map(|v| String::from(*v))
+                                .collect(),
+                            extra: found
+                                .difference(expected)
+                                .map(|v| String::from(*v))
+                                .collect(),
+                        });
+                        continue;
+                    }
+                }
+                changed.push_str(&source);
+            }
+        }
+        if !changed.is_empty() {
+            self.apply_delta(vec![LanguageDelta {
+                lang: target_lang,
+                changed,
+                removed: Vec::new(),
+            }])?;
+        }
+        Ok(mismatches)
+    }
+}
+
+/// The rows of one message in a review spreadsheet.
+struct Imported {
+    id: String,
+    value: Option<String>,
+    attributes: Vec<(String, String)>,
+}
+
+/// A definition as Fluent source, without its comment, to compare with an imported one.
+fn definition_source(entry: &ast::Entry<&str>) -> String {
+    let entry = match entry {
+        ast::Entry::Message(msg) => ast::Entry::Message(ast::Message {
+            comment: None,
+            ..msg.clone()
+        }),
+        ast::Entry::Term(term) => ast::Entry::Term(ast::Term {
+            comment: None,
+            ..term.clone()
+        }),
+        _ => entry.clone(),
+    };
+    serializer::serialize(&ast::Resource { body: vec![entry] })
+}
+
+/// The ID of every message and term in an entry, in the order they were first defined.
+fn entry_ids<K: BundleKind>(entry: &BundleEntry<K>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    entry
+        .resources
+        .iter()
+        .flat_map(|(_, resource)| resource.entries())
+        .filter_map(|entry| match entry {
+            ast::Entry::Message(msg) => Some(String::from(msg.id.name)),
+            ast::Entry::Term(term) => Some(format!("-{}", term.id.name)),
+            _ => None,
+        })
+        .filter(|id| seen.insert(id.clone()))
+        .collect()
+}
+
+/// The value and attributes of a message or term, keyed as they appear in the `id` column.
+fn patterns<'e, 's>(entry: &'e ast::Entry<&'s str>) -> Vec<(String, &'e ast::Pattern<&'s str>)> {
+    let (id, value, attributes) = match entry {
+        ast::Entry::Message(msg) => (
+            String::from(msg.id.name),
+            msg.value.as_ref(),
+            &msg.attributes,
+        ),
+        ast::Entry::Term(term) => (
+            format!("-{}", term.id.name),
+            Some(&term.value),
+            &term.attributes,
+        ),
+        _ => return Vec::new(),
+    };
+    let attributes = attributes
+        .iter()
+        .map(|attr| (format!("{}.{}", id, attr.id.name), &attr.value));
+    value
+        .map(|value| (id.clone(), value))
+        .into_iter()
+        .chain(attributes)
+        .collect()
+}
+
+/// The comment above each message and term. Bundles do not keep comments, so the resources are
+/// parsed again with them.
+fn comments<K: BundleKind>(entry: &BundleEntry<K>) -> HashMap<String, String> {
+    let mut comments = HashMap::new();
+    for (_, resource) in &entry.resources {
+        let parsed = match parser::parse(resource.source()) {
+            Ok(parsed) | Err((parsed, _)) => parsed,
+        };
+        for definition in parsed.body {
+            let (id, comment) = match definition {
+                ast::Entry::Message(msg) => (String::from(msg.id.name), msg.comment),
+                ast::Entry::Term(term) => (format!("-{}", term.id.name), term.comment),
+                _ => continue,
+            };
+            if let Some(comment) = comment {
+                comments
+                    .entry(id)
+                    .or_insert_with(|| comment.content.join("\n"));
+            }
+        }
+    }
+    comments
+}
+
+/// Append one CSV row, quoting the fields which need it.
+fn write_row(out: &mut String, fields: &[&str]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+/// Split CSV into rows of fields, as RFC 4180 describes it.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = text
+        .strip_prefix('\u{feff}')
+        .unwrap_or(text)
+        .chars()
+        .peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(invalid("unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::from(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("review CSV: {}", reason),
+    ))
+}
@@ -0,0 +1,143 @@
+use crate::{BundleKind, FluentErgo, FrozenFluentErgo, PoisonExt};
+use fluent_syntax::ast;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use unic_langid::LanguageIdentifier;
+
+/// What a translator has loaded, from `FluentErgo::stats`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// The languages that `tr` searches, in order
+    pub languages: Vec<LanguageIdentifier>,
+    /// Every language with resources, sorted by language tag, whether or not it is searched
+    pub bundles: Vec<BundleStats>,
+    /// The names of the catalogs added with `with_layer`, in the order they are searched
+    pub layers: Vec<String>,
+}
+
+/// What one language has loaded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BundleStats {
+    /// The language
+    pub lang: LanguageIdentifier,
+    /// How many resources have been parsed
+    pub resources: usize,
+    /// How many different messages the parsed resources define
+    pub messages: usize,
+    /// How many lazy resources have not been parsed yet
+    pub pending: usize,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Count what the translator has loaded, for answering "why is my string missing" in tests
+    /// or a debug console. The `Debug` output of the translator shows the same thing.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let eo_id = "eo".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[eo_id.clone(), en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("units = Units")).unwrap();
+    ///
+    /// let stats = fluent.stats();
+    /// assert_eq!(stats.languages.len(), 2);
+    /// assert_eq!(stats.bundles.len(), 1);
+    /// assert_eq!(stats.bundles[0].messages, 1);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut bundles: BTreeMap<String, BundleStats> = BTreeMap::new();
+        for (lang, resources) in self.lazy.lock().recovered().iter() {
+            bundles
+                .entry(lang.to_string())
+                .or_insert_with(|| BundleStats {
+                    lang: lang.clone(),
+                    ..BundleStats::default()
+                })
+                .pending = resources.len();
+        }
+        for (lang, entry) in self.bundles.read().recovered().iter() {
+            let entry = entry.read().recovered();
+            let stats = bundles
+                .entry(lang.to_string())
+                .or_insert_with(|| BundleStats {
+                    lang: lang.clone(),
+                    ..BundleStats::default()
+                });
+            stats.resources = entry.resources.len();
+            stats.messages = entry
+                .resources
+                .iter()
+                .flat_map(|(_, resource)| resource.entries())
+                .filter_map(|entry| match entry {
+                    ast::Entry::Message(message) => Some(message.id.name),
+                    _ => None,
+                })
+                .collect::<HashSet<&str>>()
+                .len();
+        }
+        Stats {
+            languages: self.languages.clone(),
+            bundles: bundles.into_values().collect(),
+            layers: self.layer_names().into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+impl<K: BundleKind> fmt::Debug for FluentErgo<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = self.stats();
+        let mut out = f.debug_struct("FluentErgo");
+        out.field("languages", &Tags(&stats.languages))
+            .field("bundles", &Bundles(&stats.bundles));
+        if !stats.layers.is_empty() {
+            out.field("layers", &stats.layers);
+        }
+        out.finish()
+    }
+}
+
+impl<K: BundleKind> fmt::Debug for FrozenFluentErgo<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut loaded: Vec<&LanguageIdentifier> = self.bundles.keys().collect();
+        loaded.sort_by_key(|lang| lang.to_string());
+        f.debug_struct("FrozenFluentErgo")
+            .field("languages", &Tags(&self.languages))
+            .field("loaded", &Tags(&loaded))
+            .finish()
+    }
+}
+
+/// Language tags, without the subtag structure that `LanguageIdentifier` has for `Debug`.
+struct Tags<'a, L: fmt::Display>(&'a [L]);
+
+impl<'a, L: fmt::Display> fmt::Debug for Tags<'a, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter().map(Plain)).finish()
+    }
+}
+
+struct Bundles<'a>(&'a [BundleStats]);
+
+impl<'a> fmt::Debug for Bundles<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|stats| {
+                (
+                    Plain(&stats.lang),
+                    Plain(format!(
+                        "{} messages in {} resources, {} pending",
+                        stats.messages, stats.resources, stats.pending
+                    )),
+                )
+            }))
+            .finish()
+    }
+}
+
+/// Shows a value as it is displayed, without quotes.
+struct Plain<T: fmt::Display>(T);
+
+impl<T: fmt::Display> fmt::Debug for Plain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
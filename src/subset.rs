@@ -0,0 +1,212 @@
+use crate::context_id;
+#[cfg(feature = "fs")]
+use crate::read_file;
+use crate::Error;
+use fluent::FluentResource;
+use fluent_syntax::{ast, serializer};
+use std::collections::BTreeSet;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Strip a resource down to the messages that an application uses, usually the IDs from
+/// `extract_from_dir`, so that builds for mobile or the web only carry the translations they can
+/// show. Messages and terms which a used message refers to are kept, and so are the messages of
+/// a used ID with a context, such as `open--verb` for `open`, which `tr_ctx` looks up. Everything
+/// else is dropped, comments included.
+///
+/// ```
+/// let source = "-brand = Foo\nhello = Hello from { -brand }\nunused = Never shown\n";
+/// let used = vec![String::from("hello")];
+/// assert_eq!(
+///     fluent_ergonomics::subset_resource(source, &used).unwrap(),
+///     "-brand = Foo\nhello = Hello from { -brand }\n"
+/// );
+/// ```
+///
+/// A message that only another resource of the same language refers to is dropped by this, so
+/// for whole catalogs use `subset_dir`, which looks at every resource of a language together.
+///
+/// # Errors
+///
+/// * `FluentParserError`
+///
+pub fn subset_resource(source: &str, used: &[String]) -> Result<String, Error> {
+    let resource = FluentResource::try_new(String::from(source)).map_err(Error::from)?;
+    let kept = kept_ids(&[&resource], used);
+    Ok(subset(&resource, &kept))
+}
+
+/// Copy a directory of translations, laid out as `FluentErgo::add_from_dir` reads them, from
+/// `from` to `to`, with each `.ftl` file stripped down as `subset_resource` describes. This is
+/// meant for build scripts, so that the application embeds or ships the stripped copy:
+///
+/// ```no_run
+/// // build.rs
+/// use std::path::Path;
+///
+/// let used = fluent_ergonomics::extract_from_dir(Path::new("src")).unwrap();
+/// let out_dir = std::env::var("OUT_DIR").unwrap();
+/// fluent_ergonomics::subset_dir(Path::new("locales"), &Path::new(&out_dir).join("locales"), &used)
+///     .unwrap();
+/// println!("cargo:rerun-if-changed=src");
+/// println!("cargo:rerun-if-changed=locales");
+/// ```
+///
+/// The messages and terms that a used message refers to are kept from every file of its
+/// language. Files which end up empty are still written, and other files are not copied.
+///
+/// # Errors
+///
+/// * `IOError`
+/// * `FileEncodingError`
+/// * `FluentParserError`
+///
+#[cfg(feature = "fs")]
+pub fn subset_dir(from: &Path, to: &Path, used: &[String]) -> Result<(), Error> {
+    let mut dirs = vec![(from.to_path_buf(), to.to_path_buf())];
+    while let Some((from, to)) = dirs.pop() {
+        let mut files = Vec::new();
+        for entry in from
+            .read_dir()
+            .map_err(|err| Error::from(err).in_file(&from))?
+        {
+            let path = entry.map_err(|err| Error::from(err).in_file(&from))?.path();
+            let name = match path.file_name() {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+            if path.is_dir() {
+                dirs.push((path, to.join(name)));
+            } else if path.extension().is_some_and(|ext| ext == "ftl") {
+                let resource = FluentResource::try_new(read_file(&path)?)
+                    .map_err(|err| Error::from(err).in_file(&path))?;
+                files.push((to.join(name), resource));
+            }
+        }
+        if files.is_empty() {
+            continue;
+        }
+
+        std::fs::create_dir_all(&to).map_err(|err| Error::from(err).in_file(&to))?;
+        let resources: Vec<&FluentResource> = files.iter().map(|(_, resource)| resource).collect();
+        let kept = kept_ids(&resources, used);
+        for (path, resource) in &files {
+            std::fs::write(path, subset(resource, &kept))
+                .map_err(|err| Error::from(err).in_file(path))?;
+        }
+    }
+    Ok(())
+}
+
+/// The IDs of every message and term in `resources` which `used` needs, terms with their `-`.
+fn kept_ids(resources: &[&FluentResource], used: &[String]) -> BTreeSet<String> {
+    let entries = || resources.iter().flat_map(|resource| resource.entries());
+    let mut wanted: Vec<String> = used.to_vec();
+    for entry in entries() {
+        if let ast::Entry::Message(msg) = entry {
+            let contexted = used
+                .iter()
+                .any(|id| msg.id.name.starts_with(&context_id(id, "")));
+            if contexted {
+                wanted.push(String::from(msg.id.name));
+            }
+        }
+    }
+
+    let mut kept = BTreeSet::new();
+    while let Some(id) = wanted.pop() {
+        if !kept.insert(id.clone()) {
+            continue;
+        }
+        for entry in entries() {
+            let patterns: Vec<&ast::Pattern<&str>> = match entry {
+                ast::Entry::Message(msg) if msg.id.name == id => msg
+                    .value
+                    .iter()
+                    .chain(msg.attributes.iter().map(|attr| &attr.value))
+                    .collect(),
+                ast::Entry::Term(term) if id.strip_prefix('-') == Some(term.id.name) => {
+                    std::iter::once(&term.value)
+                        .chain(term.attributes.iter().map(|attr| &attr.value))
+                        .collect()
+                }
+                _ => continue,
+            };
+            for pattern in patterns {
+                pattern_references(pattern, &mut wanted);
+            }
+        }
+    }
+    kept
+}
+
+/// The Fluent source of the messages and terms of `resource` in `kept`, without comments.
+fn subset(resource: &FluentResource, kept: &BTreeSet<String>) -> String {
+    let body = resource
+        .entries()
+        .filter_map(|entry| match entry {
+            ast::Entry::Message(msg) if kept.contains(msg.id.name) => {
+                Some(ast::Entry::Message(ast::Message {
+                    comment: None,
+                    ..msg.clone()
+                }))
+            }
+            ast::Entry::Term(term) if kept.contains(&format!("-{}", term.id.name)) => {
+                Some(ast::Entry::Term(ast::Term {
+                    comment: None,
+                    ..term.clone()
+                }))
+            }
+            _ => None,
+        })
+        .collect();
+    serializer::serialize(&ast::Resource { body })
+}
+
+/// Add the messages and terms that a pattern refers to to `ids`, terms with their `-`.
+fn pattern_references(pattern: &ast::Pattern<&str>, ids: &mut Vec<String>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_references(expression, ids);
+        }
+    }
+}
+
+fn expression_references(expression: &ast::Expression<&str>, ids: &mut Vec<String>) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            inline_references(selector, ids);
+            for variant in variants {
+                pattern_references(&variant.value, ids);
+            }
+        }
+        ast::Expression::Inline(inline) => inline_references(inline, ids),
+    }
+}
+
+fn inline_references(inline: &ast::InlineExpression<&str>, ids: &mut Vec<String>) {
+    let arguments = match inline {
+        ast::InlineExpression::MessageReference { id, .. } => {
+            ids.push(String::from(id.name));
+            None
+        }
+        ast::InlineExpression::TermReference { id, arguments, .. } => {
+            ids.push(format!("-{}", id.name));
+            arguments.as_ref()
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => Some(arguments),
+        ast::InlineExpression::Placeable { expression } => {
+            expression_references(expression, ids);
+            None
+        }
+        _ => None,
+    };
+    if let Some(arguments) = arguments {
+        for argument in &arguments.positional {
+            inline_references(argument, ids);
+        }
+        for argument in &arguments.named {
+            inline_references(&argument.value, ids);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{canonicalize_language, BundleKind, FluentErgo, PoisonExt};
+use fluent_syntax::{ast, serializer};
+use std::collections::HashMap;
+use unic_langid::LanguageIdentifier;
+
+/// How similar two source texts must be for `FluentErgo::suggest` to offer one for the other.
+const MIN_SCORE: f64 = 0.5;
+
+/// A translation of a similar message, from `FluentErgo::suggest`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    /// The message whose source text is similar
+    pub id: String,
+    /// Its source text, as Fluent source
+    pub source: String,
+    /// Its translation in the target language, as Fluent source
+    pub translation: String,
+    /// How similar the source texts are, from 0 to 1. A 1 means that they only differ in case,
+    /// punctuation, spacing and placeables.
+    pub score: f64,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Find translations in `target_lang` of messages which say nearly the same thing as `msgid`,
+    /// for an in-app translation editor to offer while someone translates `msgid`. The source
+    /// text of a message is the one that would be shown if `target_lang` did not have it: the
+    /// first other language in the fallback chain which has it.
+    ///
+    /// Source texts are compared by their words, ignoring case, punctuation and placeables, and
+    /// messages which share at least half of their words are suggested, best matches first.
+    /// Suggestions are Fluent source, so that placeables can be copied into the translation.
+    /// Lazy resources only count once they have been loaded.
+    ///
+    /// ```
+    /// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let de_id = "de".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[de_id.clone(), en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from("save = Save settings\nsave-all = Save all settings!\nquit = Quit"),
+    ///     )
+    ///     .unwrap();
+    /// fluent
+    ///     .add_from_text(de_id.clone(), String::from("save = Einstellungen speichern"))
+    ///     .unwrap();
+    ///
+    /// let suggestions = fluent.suggest("save-all", &de_id);
+    /// assert_eq!(suggestions[0].id, "save");
+    /// assert_eq!(suggestions[0].translation, "Einstellungen speichern");
+    /// assert_eq!(suggestions.len(), 1);
+    /// ```
+    pub fn suggest(&self, msgid: &str, target_lang: &LanguageIdentifier) -> Vec<Suggestion> {
+        let target_lang = canonicalize_language(target_lang);
+        let bundles = self.bundles.read().recovered();
+        let target = match bundles.get(&target_lang) {
+            Some(entry) => entry.read().recovered(),
+            None => return Vec::new(),
+        };
+        let sources = self
+            .languages
+            .iter()
+            .filter(|lang| **lang != target_lang)
+            .filter_map(|lang| bundles.get(lang))
+            .map(|entry| entry.read().recovered())
+            .collect::<Vec<_>>();
+        let source_of = |id: &str| {
+            sources
+                .iter()
+                .find_map(|entry| value_of(entry.definition(id)?.1))
+        };
+        let wanted = match source_of(msgid) {
+            Some(value) => words(value),
+            None => return Vec::new(),
+        };
+        if wanted.is_empty() {
+            return Vec::new();
+        }
+
+        let mut suggestions = Vec::new();
+        for id in target.message_ids() {
+            if id == msgid {
+                continue;
+            }
+            let (source, translation) = match (source_of(id), target.definition(id)) {
+                (Some(source), Some((_, translation))) => (source, translation),
+                _ => continue,
+            };
+            let score = similarity(&wanted, &words(source));
+            if score < MIN_SCORE {
+                continue;
+            }
+            if let Some(translation) = value_of(translation) {
+                suggestions.push(Suggestion {
+                    id: String::from(id),
+                    source: pattern_source(id, source),
+                    translation: pattern_source(id, translation),
+                    score,
+                });
+            }
+        }
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        suggestions
+    }
+}
+
+fn value_of<'e, 's>(entry: &'e ast::Entry<&'s str>) -> Option<&'e ast::Pattern<&'s str>> {
+    match entry {
+        ast::Entry::Message(msg) => msg.value.as_ref(),
+        _ => None,
+    }
+}
+
+/// The words of the text in a pattern, in lower case, with how often each one appears.
+fn words(pattern: &ast::Pattern<&str>) -> HashMap<String, usize> {
+    let mut words = HashMap::new();
+    for element in &pattern.elements {
+        if let ast::PatternElement::TextElement { value } = element {
+            for word in value.split(|c: char| !c.is_alphanumeric()) {
+                if !word.is_empty() {
+                    *words.entry(word.to_lowercase()).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    words
+}
+
+/// The share of words that two texts have in common.
+fn similarity(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f64 {
+    let common: usize = a
+        .iter()
+        .map(|(word, count)| (*count).min(b.get(word).copied().unwrap_or(0)))
+        .sum();
+    let total: usize = a.values().chain(b.values()).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    2.0 * common as f64 / total as f64
+}
+
+/// A message's value as Fluent source, without the ID.
+pub(crate) fn pattern_source(id: &str, pattern: &ast::Pattern<&str>) -> String {
+    let message = ast::Message {
+        id: ast::Identifier { name: id },
+        value: Some(pattern.clone()),
+        attributes: Vec::new(),
+        comment: None,
+    };
+    let text = serializer::serialize(&ast::Resource {
+        body: vec![ast::Entry::Message(message)],
+    });
+    let value = text
+        .split_once('=')
+        .map_or(text.as_str(), |(_, value)| value);
+    String::from(value.trim())
+}
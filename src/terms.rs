@@ -0,0 +1,192 @@
+use crate::format::format_pattern;
+use crate::{
+    canonicalize_language, BundleEntry, BundleKind, Error, FluentErgo, MissingMessage, Options,
+    PoisonExt,
+};
+use fluent::{FluentArgs, FluentResource};
+use fluent_syntax::ast;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Format a term, such as `-brand-name`, directly. Messages are the usual way to use terms,
+    /// but some UI chrome needs the term itself. The leading `-` is optional. `args` become the
+    /// term's parameters, as in `{ -brand-name(case: "genitive") }`.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent.add_from_text(en_id, String::from("-brand-name = Firefox")).unwrap();
+    /// assert_eq!(fluent.term("brand-name", None).unwrap(), "Firefox");
+    /// ```
+    ///
+    /// As with the default `TrOptions` of `tr`, a term which Fluent cannot format completely, such
+    /// as one which selects on a parameter that is not given, is formatted as well as it can be.
+    /// With the `tracing` feature, the errors are recorded on the `term` span. Terms are wrapped
+    /// in isolation marks as `set_isolation` says, and the length limit of
+    /// `set_evaluation_limits` applies to them.
+    ///
+    /// # Errors
+    ///
+    /// * NoMatchingMessage -- no bundle has the term
+    /// * LimitExceeded -- the term is longer than the length limit
+    /// * Any error from loading a lazy resource, as for `tr`
+    ///
+    pub fn term(&self, term_id: &str, args: Option<&FluentArgs>) -> Result<String, Error> {
+        let id = term_id.trim_start_matches('-');
+        trace_span!("term", id = id, errors = tracing::field::Empty);
+        self.load_lazy(&self.languages)?;
+        let bundles = self.bundles.read().or_poisoned()?;
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.bundles.read().or_poisoned())
+            .collect::<Result<Vec<_>, _>>()?;
+        for lang in &self.languages {
+            let entries = std::iter::once(&*bundles)
+                .chain(layers.iter().map(|layer| &**layer))
+                .filter_map(|bundles| bundles.get(lang));
+            for entry in entries {
+                let entry = entry.read().or_poisoned()?;
+                if let Some(result) = entry.format_term(&self.options, lang, id, args) {
+                    return result;
+                }
+            }
+        }
+        let missing = MissingMessage::new(&format!("-{}", id), &self.languages, |lang| {
+            bundles.get(lang).map(|_| false)
+        });
+        Err(Error::NoMatchingMessage(missing))
+    }
+
+    /// Check resources as they are loaded for references to terms which their language does not
+    /// define, and report them as an `UnknownTerms` error. The resources are still added. Because
+    /// a term may be defined in a file which has not been loaded yet, this is most useful with
+    /// `add_many` and `add_from_dir`, which check after the whole batch has been added.
+    ///
+    /// This is off by default. Copied, not shared, between clones of the translator.
+    pub fn set_term_validation(&mut self, enabled: bool) {
+        Arc::make_mut(&mut self.options).validate_terms = enabled;
+    }
+
+    /// The first `UnknownTerms` error among `languages`, if term validation is on.
+    pub(crate) fn validate_terms(&self, languages: &[LanguageIdentifier]) -> Result<(), Error> {
+        if !self.options.validate_terms {
+            return Ok(());
+        }
+        let bundles = self.bundles.read().or_poisoned()?;
+        for lang in languages {
+            let lang = canonicalize_language(lang);
+            if let Some(entry) = bundles.get(&lang) {
+                let terms = unknown_terms(&entry.read().or_poisoned()?.resources);
+                if !terms.is_empty() {
+                    return Err(Error::UnknownTerms { lang, terms });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K: BundleKind> BundleEntry<K> {
+    /// Format a term from this entry's resources, or return `None` if it has no such term.
+    fn format_term(
+        &self,
+        options: &Options,
+        lang: &LanguageIdentifier,
+        id: &str,
+        args: Option<&FluentArgs>,
+    ) -> Option<Result<String, Error>> {
+        let id = format!("-{}", id);
+        let term = match self.definition(&id)? {
+            (_, ast::Entry::Term(term)) => term,
+            _ => return None,
+        };
+        let mut errors = Vec::new();
+        let result = format_pattern::<K>(
+            options,
+            lang,
+            &id,
+            &self.bundle,
+            &term.value,
+            args,
+            &mut errors,
+        );
+        if !errors.is_empty() {
+            trace_record!("errors", tracing::field::debug(&errors));
+        }
+        Some(result)
+    }
+}
+
+/// The terms which the resources refer to but do not define, with their leading `-`.
+fn unknown_terms(resources: &[(u64, Arc<FluentResource>)]) -> Vec<String> {
+    let mut defined = BTreeSet::new();
+    let mut referenced = BTreeSet::new();
+    for (_, resource) in resources {
+        for entry in resource.entries() {
+            let (value, attributes) = match entry {
+                ast::Entry::Message(msg) => (msg.value.as_ref(), &msg.attributes),
+                ast::Entry::Term(term) => {
+                    defined.insert(term.id.name);
+                    (Some(&term.value), &term.attributes)
+                }
+                _ => continue,
+            };
+            for pattern in value
+                .into_iter()
+                .chain(attributes.iter().map(|attr| &attr.value))
+            {
+                pattern_terms(pattern, &mut referenced);
+            }
+        }
+    }
+    referenced
+        .difference(&defined)
+        .map(|id| format!("-{}", id))
+        .collect()
+}
+
+fn pattern_terms<'r>(pattern: &ast::Pattern<&'r str>, terms: &mut BTreeSet<&'r str>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_terms(expression, terms);
+        }
+    }
+}
+
+fn expression_terms<'r>(expression: &ast::Expression<&'r str>, terms: &mut BTreeSet<&'r str>) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            inline_terms(selector, terms);
+            for variant in variants {
+                pattern_terms(&variant.value, terms);
+            }
+        }
+        ast::Expression::Inline(inline) => inline_terms(inline, terms),
+    }
+}
+
+fn inline_terms<'r>(inline: &ast::InlineExpression<&'r str>, terms: &mut BTreeSet<&'r str>) {
+    let arguments = match inline {
+        ast::InlineExpression::TermReference { id, arguments, .. } => {
+            terms.insert(id.name);
+            arguments.as_ref()
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => Some(arguments),
+        ast::InlineExpression::Placeable { expression } => {
+            expression_terms(expression, terms);
+            None
+        }
+        _ => None,
+    };
+    if let Some(arguments) = arguments {
+        for argument in &arguments.positional {
+            inline_terms(argument, terms);
+        }
+        for argument in &arguments.named {
+            inline_terms(&argument.value, terms);
+        }
+    }
+}
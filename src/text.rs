@@ -0,0 +1,244 @@
+use crate::{BundleKind, Error, FluentErgo};
+use fluent::{FluentArgs, FluentValue};
+use unic_langid::LanguageIdentifier;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How `tr_opts` changes the capitalization of a translation, so that the same message can be
+/// used in a title bar and in running text.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Case {
+    /// Leave the translation as the translator wrote it.
+    #[default]
+    AsWritten,
+    /// Capitalize the first letter of every word. See `to_title_case`.
+    Title,
+    /// Capitalize the first word, and lower-case the others. See `to_sentence_case`.
+    Sentence,
+}
+
+impl Case {
+    pub(crate) fn apply(self, text: String, lang: &LanguageIdentifier) -> String {
+        match self {
+            Case::AsWritten => text,
+            Case::Title => to_title_case(&text, lang),
+            Case::Sentence => to_sentence_case(&text, lang),
+        }
+    }
+}
+
+/// Capitalize the first letter of every word, following the rules of `lang`: Turkish and
+/// Azerbaijani `i` becomes `İ`, Dutch `ij` becomes `IJ`, and digraphs such as `ǆ` become their
+/// title case forms. The rest of each word is left alone, and text in scripts without case is
+/// unchanged.
+///
+/// ```
+/// use fluent_ergonomics::to_title_case;
+///
+/// let tr_id = "tr".parse::<unic_langid::LanguageIdentifier>().unwrap();
+/// assert_eq!(to_title_case("iyi bilgi", &tr_id), "İyi Bilgi");
+/// ```
+pub fn to_title_case(text: &str, lang: &LanguageIdentifier) -> String {
+    text.split_word_bounds()
+        .map(|word| capitalize(word, lang))
+        .collect()
+}
+
+/// Capitalize the first letter of the text, the way `to_title_case` does, and lower-case every
+/// other word which only starts with a capital. Words with capitals elsewhere, such as `PDF` or
+/// `iPhone`, are assumed to be names and are left alone.
+///
+/// ```
+/// use fluent_ergonomics::to_sentence_case;
+///
+/// let en_id = "en".parse::<unic_langid::LanguageIdentifier>().unwrap();
+/// assert_eq!(to_sentence_case("export as PDF File", &en_id), "Export as PDF file");
+/// ```
+pub fn to_sentence_case(text: &str, lang: &LanguageIdentifier) -> String {
+    let mut first = true;
+    text.split_word_bounds()
+        .map(|word| {
+            if !word.chars().next().is_some_and(char::is_alphabetic) {
+                String::from(word)
+            } else if first {
+                first = false;
+                capitalize(word, lang)
+            } else {
+                decapitalize(word, lang)
+            }
+        })
+        .collect()
+}
+
+fn is_turkic(lang: &LanguageIdentifier) -> bool {
+    matches!(lang.language.as_str(), "tr" | "az")
+}
+
+fn capitalize(word: &str, lang: &LanguageIdentifier) -> String {
+    let mut chars = word.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    if lang.language.as_str() == "nl" {
+        if let Some(rest) = word.strip_prefix("ij") {
+            return format!("IJ{}", rest);
+        }
+    }
+    let mut capitalized = match first {
+        'i' if is_turkic(lang) => String::from('İ'),
+        'Ǆ' | 'ǅ' | 'ǆ' => String::from('ǅ'),
+        'Ǉ' | 'ǈ' | 'ǉ' => String::from('ǈ'),
+        'Ǌ' | 'ǋ' | 'ǌ' => String::from('ǋ'),
+        'Ǳ' | 'ǲ' | 'ǳ' => String::from('ǲ'),
+        _ => first.to_uppercase().collect(),
+    };
+    capitalized.push_str(chars.as_str());
+    capitalized
+}
+
+fn decapitalize(word: &str, lang: &LanguageIdentifier) -> String {
+    let mut chars = word.chars();
+    let first = match chars.next() {
+        Some(first) if first.is_uppercase() || matches!(first, 'ǅ' | 'ǈ' | 'ǋ' | 'ǲ') => first,
+        _ => return String::from(word),
+    };
+    if chars.clone().any(char::is_uppercase) {
+        return String::from(word);
+    }
+    let mut decapitalized = match first {
+        'I' if is_turkic(lang) => String::from('ı'),
+        'İ' if is_turkic(lang) => String::from('i'),
+        _ => first.to_lowercase().collect(),
+    };
+    decapitalized.push_str(chars.as_str());
+    decapitalized
+}
+
+/// The ellipsis used when the translator has no message for one.
+const DEFAULT_ELLIPSIS: &str = "…";
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Translate a message, and shorten it to at most `max_graphemes` user-perceived characters,
+    /// for badges, notifications, and other places with a hard length limit. A shortened message
+    /// ends with the translation of `ellipsis_msgid`, which counts towards the limit, so that
+    /// each language can use its own ellipsis; if no bundle has that message, `…` is used.
+    ///
+    /// The message is only ever cut between grapheme clusters, so accents, emoji sequences, and
+    /// Hangul syllables are never split. Whitespace before the ellipsis is removed.
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from("new-messages = You have new messages\nellipsis = ..."),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     fluent.tr_truncated("new-messages", None, 12, "ellipsis").unwrap(),
+    ///     "You have..."
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return, for `msgid`
+    ///
+    pub fn tr_truncated(
+        &self,
+        msgid: &str,
+        args: Option<&FluentArgs>,
+        max_graphemes: usize,
+        ellipsis_msgid: &str,
+    ) -> Result<String, Error> {
+        let text = self.tr(msgid, args)?;
+        if text.graphemes(true).count() <= max_graphemes {
+            return Ok(text);
+        }
+        let ellipsis = self
+            .tr_opt(ellipsis_msgid, None)
+            .unwrap_or_else(|| String::from(DEFAULT_ELLIPSIS));
+        Ok(cut(&text, max_graphemes, &ellipsis))
+    }
+}
+
+/// The first graphemes of `text`, followed by `ellipsis`, so that there are `max_graphemes` in
+/// all. `text` must be longer than that.
+fn cut(text: &str, max_graphemes: usize, ellipsis: &str) -> String {
+    let keep = max_graphemes.saturating_sub(ellipsis.graphemes(true).count());
+    let end = text
+        .grapheme_indices(true)
+        .nth(keep)
+        .map_or(text.len(), |(index, _)| index);
+    let mut truncated = String::from(text[..end].trim_end());
+    truncated.push_str(ellipsis);
+    truncated
+}
+
+/// The quotation marks of `lang`, from the CLDR.
+pub(crate) fn quotation_marks(lang: &LanguageIdentifier) -> (&'static str, &'static str) {
+    let mut full = lang.clone();
+    full.maximize();
+    let region = full.region.as_ref().map(|region| region.as_str());
+    let script = full.script.as_ref().map(|script| script.as_str());
+    match (full.language.as_str(), script, region) {
+        ("de", _, Some("CH")) | ("de", _, Some("LI")) | ("pt", _, Some("PT")) => ("«", "»"),
+        ("zh", Some("Hant"), _) | ("ja", _, _) => ("「", "」"),
+        (
+            "bg" | "cs" | "de" | "et" | "is" | "ka" | "lt" | "sk" | "sl" | "sq" | "sr" | "hr"
+            | "bs" | "mk",
+            _,
+            _,
+        ) => ("„", "“"),
+        ("hu" | "pl" | "ro", _, _) => ("„", "”"),
+        ("fi" | "he" | "sv", _, _) => ("”", "”"),
+        (
+            "be" | "ca" | "el" | "es" | "eu" | "fa" | "fr" | "hy" | "it" | "kk" | "nb" | "nn"
+            | "no" | "ru" | "uk",
+            _,
+            _,
+        ) => ("«", "»"),
+        _ => ("“", "”"),
+    }
+}
+
+/// `QUOTE($text)`, which puts its argument in `open` and `close`. See
+/// `FluentErgo::add_function`.
+pub(crate) fn quote_function<'a>(
+    positional: &[FluentValue<'a>],
+    open: &str,
+    close: &str,
+) -> FluentValue<'a> {
+    let text = match positional.first() {
+        Some(FluentValue::String(text)) => text.clone(),
+        Some(FluentValue::Number(number)) => number.as_string(),
+        _ => return FluentValue::Error,
+    };
+    FluentValue::from(format!("{}{}{}", open, text, close))
+}
+
+/// `TRUNCATE($text, max)`, which a translator can use to bound user content in a message. See
+/// `FluentErgo::add_function`.
+pub(crate) fn truncate_function<'a>(
+    positional: &[FluentValue<'a>],
+    named: &FluentArgs,
+) -> FluentValue<'a> {
+    let text = match positional.first() {
+        Some(FluentValue::String(text)) => text.clone(),
+        Some(FluentValue::Number(number)) => number.as_string(),
+        _ => return FluentValue::Error,
+    };
+    let max_graphemes = match positional.get(1) {
+        Some(FluentValue::Number(max)) if max.value >= 0.0 => max.value as usize,
+        _ => return FluentValue::Error,
+    };
+    if text.graphemes(true).count() <= max_graphemes {
+        return FluentValue::String(text);
+    }
+    let ellipsis = match named.get("ellipsis") {
+        Some(FluentValue::String(ellipsis)) => ellipsis.as_ref(),
+        _ => "",
+    };
+    FluentValue::from(cut(&text, max_graphemes, ellipsis))
+}
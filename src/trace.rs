@@ -0,0 +1,19 @@
+//! With the `tracing` feature, loading and formatting happen inside `tracing` spans at the debug
+//! level, so that their cost shows up in an application's existing traces. Without it, these
+//! macros expand to nothing.
+
+/// Enter a span until the end of the enclosing block.
+macro_rules! trace_span {
+    ($($span:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($span)*).entered();
+    };
+}
+
+/// Fill in a field of the current span which was declared as `tracing::field::Empty`.
+macro_rules! trace_record {
+    ($field:literal, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record($field, $value);
+    };
+}
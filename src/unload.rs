@@ -0,0 +1,122 @@
+use crate::{BundleKind, FluentErgo, PoisonExt, UnparsedResource};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use unic_langid::LanguageIdentifier;
+
+/// When each language was last used, for unloading the ones that have not been.
+#[derive(Debug)]
+pub(crate) struct IdleTracker {
+    after: Duration,
+    last_used: HashMap<LanguageIdentifier, Instant>,
+    last_sweep: Instant,
+}
+
+impl IdleTracker {
+    /// Record a use of `lang`, and return whether it is time to look for idle languages. Looking
+    /// takes a pass over every language, so it happens at most four times per `after`.
+    pub(crate) fn touch(&mut self, lang: &LanguageIdentifier) -> bool {
+        let now = Instant::now();
+        match self.last_used.get_mut(lang) {
+            Some(last_used) => *last_used = now,
+            None => {
+                self.last_used.insert(lang.clone(), now);
+            }
+        }
+        now.duration_since(self.last_sweep) >= self.after / 4
+    }
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Unload the bundles of languages which have not formatted a message for `after`, for
+    /// applications with many fallback languages and little memory. An unloaded language keeps
+    /// only the text of its resources, or the paths of the files they came from, and is parsed
+    /// again as a lazy resource the next time it is needed. Files are read again at that point,
+    /// so they should not be changed in the meantime. `None`, the default, never unloads
+    /// anything.
+    ///
+    /// Languages are checked for idleness as messages are translated, or whenever
+    /// `unload_idle_languages` is called. A language that has not been used since this policy was
+    /// set counts as used at the first check. Languages with resources that were merged with
+    /// `MergeConflicts::Replace` are never unloaded, since they could not be put back together
+    /// the same way. A catalog that other translators use through `with_layer` should not use
+    /// this, because the views on top of it only see its loaded bundles.
+    ///
+    /// Clones of this translator share their bundles, and so they share this policy as well.
+    pub fn set_unload_after(&mut self, after: Option<Duration>) {
+        let mut cache = self.intl.lock().recovered();
+        cache.idle = after.map(|after| IdleTracker {
+            after,
+            last_used: HashMap::new(),
+            last_sweep: Instant::now(),
+        });
+        self.intl.update(&cache);
+    }
+
+    /// Unload every language which has been idle for longer than `set_unload_after` allows right
+    /// away, and return them.
+    pub fn unload_idle_languages(&self) -> Vec<LanguageIdentifier> {
+        let loaded: Vec<LanguageIdentifier> =
+            self.bundles.read().recovered().keys().cloned().collect();
+        let idle = {
+            let mut cache = self.intl.lock().recovered();
+            let tracker = match &mut cache.idle {
+                Some(tracker) => tracker,
+                None => return Vec::new(),
+            };
+            let now = Instant::now();
+            tracker.last_sweep = now;
+            for lang in loaded {
+                tracker.last_used.entry(lang).or_insert(now);
+            }
+            let after = tracker.after;
+            let idle: Vec<LanguageIdentifier> = tracker
+                .last_used
+                .iter()
+                .filter(|(_, last_used)| now.duration_since(**last_used) >= after)
+                .map(|(lang, _)| lang.clone())
+                .collect();
+            tracker.last_used.retain(|lang, _| !idle.contains(lang));
+            cache.recent.retain(|lang| !idle.contains(lang));
+            idle
+        };
+        self.unload(&idle)
+    }
+
+    /// Turn the bundles of `languages` back into lazy resources, and return the languages which
+    /// were unloaded.
+    fn unload(&self, languages: &[LanguageIdentifier]) -> Vec<LanguageIdentifier> {
+        let mut lazy = self.lazy.lock().recovered();
+        let mut bundles = self.bundles.write().recovered();
+        let mut unloaded = Vec::new();
+        for lang in languages {
+            let resources: Vec<UnparsedResource> = match bundles.get(lang) {
+                Some(entry) => {
+                    let entry = entry.read().recovered();
+                    if !entry.overriding.is_empty() {
+                        continue;
+                    }
+                    entry
+                        .resources
+                        .iter()
+                        .map(|(_hash, resource)| {
+                            #[cfg(feature = "fs")]
+                            if let Some(crate::Origin::File(path)) = entry.origins.get(_hash) {
+                                return UnparsedResource::File(path.clone());
+                            }
+                            UnparsedResource::Text(String::from(resource.source()))
+                        })
+                        .collect()
+                }
+                None => continue,
+            };
+            // These were loaded before anything still waiting, so they go first.
+            lazy.entry(lang.clone())
+                .or_default()
+                .splice(0..0, resources);
+            bundles.remove(lang);
+            unloaded.push(lang.clone());
+        }
+        self.lazy.update(&lazy);
+        unloaded
+    }
+}
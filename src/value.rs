@@ -0,0 +1,144 @@
+use fluent::types::FluentType;
+use fluent::{FluentArgs, FluentValue};
+use intl_memoizer::Memoizable;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use unic_langid::LanguageIdentifier;
+
+/// An argument which formats itself differently in each language, such as an enum of account
+/// roles or a domain object with a display name. Wrap it in a message argument with
+/// `IntoFluentValue`, and it is formatted in the language of whichever bundle has the message.
+///
+/// ```
+/// use fluent::FluentArgs;
+/// use fluent_ergonomics::{FluentArgsExt, LocalizedValue};
+/// use unic_langid::LanguageIdentifier;
+///
+/// #[derive(Debug)]
+/// enum Role {
+///     Admin,
+///     Guest,
+/// }
+///
+/// impl LocalizedValue for Role {
+///     fn format(&self, lang: &LanguageIdentifier) -> String {
+///         let name = match (self, lang.language.as_str()) {
+///             (Role::Admin, "eo") => "administranto",
+///             (Role::Admin, _) => "administrator",
+///             (Role::Guest, "eo") => "gasto",
+///             (Role::Guest, _) => "guest",
+///         };
+///         String::from(name)
+///     }
+/// }
+///
+/// let eo_id = "eo".parse::<LanguageIdentifier>().unwrap();
+/// let mut fluent = fluent_ergonomics::FluentErgo::new(&[eo_id.clone()]);
+/// fluent
+///     .add_from_text(eo_id, String::from("signed-in = Vi estas { $role }"))
+///     .unwrap();
+///
+/// let mut args = FluentArgs::new();
+/// args.set_value("role", Role::Guest);
+/// assert_eq!(fluent.tr("signed-in", Some(&args)).unwrap(), "Vi estas gasto");
+/// ```
+pub trait LocalizedValue: fmt::Debug + Send + Sync + 'static {
+    /// Format the value for `lang`.
+    fn format(&self, lang: &LanguageIdentifier) -> String;
+}
+
+/// Anything which can be passed to a message as an argument: strings, numbers, Fluent's own
+/// values, and every `LocalizedValue`.
+pub trait IntoFluentValue<'a> {
+    fn into_fluent_value(self) -> FluentValue<'a>;
+}
+
+impl<'a, T: LocalizedValue> IntoFluentValue<'a> for T {
+    fn into_fluent_value(self) -> FluentValue<'a> {
+        FluentValue::Custom(Box::new(Localized(Arc::new(self))))
+    }
+}
+
+impl<'a> IntoFluentValue<'a> for FluentValue<'a> {
+    fn into_fluent_value(self) -> FluentValue<'a> {
+        self
+    }
+}
+
+macro_rules! into_fluent_value {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> IntoFluentValue<'a> for $ty {
+                fn into_fluent_value(self) -> FluentValue<'a> {
+                    FluentValue::from(self)
+                }
+            }
+        )*
+    };
+}
+
+into_fluent_value!(&'a str, String, Cow<'a, str>);
+into_fluent_value!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// Set arguments from anything that implements `IntoFluentValue`.
+pub trait FluentArgsExt<'a> {
+    fn set_value<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<Cow<'a, str>>,
+        V: IntoFluentValue<'a>;
+}
+
+impl<'a> FluentArgsExt<'a> for FluentArgs<'a> {
+    fn set_value<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<Cow<'a, str>>,
+        V: IntoFluentValue<'a>,
+    {
+        self.set(key, value.into_fluent_value());
+    }
+}
+
+/// The custom Fluent value that carries a `LocalizedValue`.
+#[derive(Clone, Debug)]
+struct Localized(Arc<dyn LocalizedValue>);
+
+impl PartialEq for Localized {
+    fn eq(&self, other: &Localized) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Fluent only tells a custom value which language it is being formatted in through the bundle's
+/// memoizer, which constructs its cached objects from the language.
+struct BundleLanguage(LanguageIdentifier);
+
+impl Memoizable for BundleLanguage {
+    type Args = ();
+    type Error = ();
+
+    fn construct(lang: LanguageIdentifier, _args: ()) -> Result<BundleLanguage, ()> {
+        Ok(BundleLanguage(lang))
+    }
+}
+
+impl FluentType for Localized {
+    fn duplicate(&self) -> Box<dyn FluentType + Send> {
+        Box::new(self.clone())
+    }
+
+    fn as_string(&self, intls: &intl_memoizer::IntlLangMemoizer) -> Cow<'static, str> {
+        intls
+            .with_try_get::<BundleLanguage, _, _>((), |lang| self.0.format(&lang.0))
+            .map_or(Cow::Borrowed(""), Cow::Owned)
+    }
+
+    fn as_string_threadsafe(
+        &self,
+        intls: &intl_memoizer::concurrent::IntlLangMemoizer,
+    ) -> Cow<'static, str> {
+        intls
+            .with_try_get::<BundleLanguage, _, _>((), |lang| self.0.format(&lang.0))
+            .map_or(Cow::Borrowed(""), Cow::Owned)
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{canonicalize_language, BundleKind, FluentErgo, PoisonExt};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::SystemTime;
+use unic_langid::LanguageIdentifier;
+
+/// Identifies exactly what has been loaded for a language, from `FluentErgo::catalog_version`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CatalogVersion {
+    /// A hash of the text of every resource, which changes whenever any of them does
+    pub hash: u64,
+    /// How many resources have been loaded
+    pub resources: usize,
+    /// The newest modification time of the files that were loaded. This is `None` if nothing was
+    /// loaded from a file.
+    pub modified: Option<SystemTime>,
+}
+
+/// A language whose files are older than those of the reference language, reported to the
+/// handler given to `FluentErgo::set_staleness_handler`. This usually means that a deployment
+/// updated the reference language but not the translations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaleCatalog {
+    pub lang: LanguageIdentifier,
+    pub modified: SystemTime,
+    pub reference: LanguageIdentifier,
+    pub reference_modified: SystemTime,
+}
+
+pub(crate) struct StalenessCheck {
+    reference: LanguageIdentifier,
+    handler: Box<dyn Fn(&StaleCatalog) + Send + Sync>,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// The version of what has been loaded for `lang`, or `None` if nothing has. Lazy resources
+    /// only count once they have been loaded.
+    pub fn catalog_version(&self, lang: &LanguageIdentifier) -> Option<CatalogVersion> {
+        let bundles = self.bundles.read().recovered();
+        let entry = bundles
+            .get(&canonicalize_language(lang))?
+            .read()
+            .recovered();
+        let mut hasher = DefaultHasher::new();
+        for (hash, _) in &entry.resources {
+            hash.hash(&mut hasher);
+        }
+        Some(CatalogVersion {
+            hash: hasher.finish(),
+            resources: entry.resources.len(),
+            modified: entry.modified,
+        })
+    }
+
+    /// Call `handler` whenever files are loaded for a language which are older than the newest
+    /// file of `reference`, usually the language the application is written in. Languages that
+    /// were not loaded from files are never reported. The handler is shared by every clone of
+    /// this translator which is created after this call.
+    pub fn set_staleness_handler<F>(&mut self, reference: LanguageIdentifier, handler: F)
+    where
+        F: Fn(&StaleCatalog) + Send + Sync + 'static,
+    {
+        self.staleness = Some(Arc::new(StalenessCheck {
+            reference: canonicalize_language(&reference),
+            handler: Box::new(handler),
+        }));
+    }
+
+    /// Report any of the `touched` languages which are now stale. If the reference language was
+    /// touched, every language is checked.
+    pub(crate) fn check_staleness(&self, touched: &[LanguageIdentifier]) {
+        let check = match &self.staleness {
+            Some(check) => check,
+            None => return,
+        };
+        let mut stale = Vec::new();
+        {
+            let bundles = self.bundles.read().recovered();
+            let modified =
+                |lang: &LanguageIdentifier| bundles.get(lang)?.read().recovered().modified;
+            let reference_modified = match modified(&check.reference) {
+                Some(reference_modified) => reference_modified,
+                None => return,
+            };
+            let candidates: Vec<&LanguageIdentifier> = if touched.contains(&check.reference) {
+                bundles.keys().collect()
+            } else {
+                touched.iter().collect()
+            };
+            for lang in candidates {
+                match modified(lang) {
+                    Some(modified) if modified < reference_modified => stale.push(StaleCatalog {
+                        lang: lang.clone(),
+                        modified,
+                        reference: check.reference.clone(),
+                        reference_modified,
+                    }),
+                    _ => (),
+                }
+            }
+        }
+        for event in &stale {
+            (check.handler)(event);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::fingerprint::Fnv;
+use crate::{BundleKind, Error, FluentErgo};
+use fluent::FluentArgs;
+
+/// The argument that `FluentErgo::tr_weighted` gives the chosen variant in.
+const VARIANT_ARG: &str = "variant";
+
+/// A translation from `FluentErgo::tr_weighted`, and the variant it was given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedTranslation {
+    /// The variant chosen for this seed, or `None` if every weight was zero
+    pub variant: Option<String>,
+    pub text: String,
+}
+
+impl<K: BundleKind> FluentErgo<K> {
+    /// Translate a message which has several phrasings for a copy test, choosing one by weight.
+    /// The message selects on `$variant`, and each key in `weights` is one of its variants:
+    ///
+    /// ```
+    /// let en_id = "en-US".parse::<unic_langid::LanguageIdentifier>().unwrap();
+    /// let mut fluent = fluent_ergonomics::FluentErgo::new(&[en_id.clone()]);
+    /// fluent
+    ///     .add_from_text(
+    ///         en_id,
+    ///         String::from(
+    ///             "
+    /// sign-up = { $variant ->
+    ///     [free] Create your free account
+    ///     [join] Join now
+    ///    *[plain] Sign up
+    /// }
+    /// ",
+    ///         ),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let weights = [("plain", 50), ("free", 25), ("join", 25)];
+    /// let shown = fluent.tr_weighted("sign-up", &weights, "user-1234", None).unwrap();
+    /// assert_eq!(
+    ///     fluent.tr_weighted("sign-up", &weights, "user-1234", None).unwrap(),
+    ///     shown
+    /// );
+    /// assert!(["plain", "free", "join"].contains(&shown.variant.as_deref().unwrap()));
+    /// ```
+    ///
+    /// The choice depends only on `msgid`, `seed` and `weights`, so a user ID as the seed puts
+    /// each user in the same bucket on every call, in every build, and on every platform. The
+    /// chosen variant is returned along with the text, to record which copy the user saw. A
+    /// language which does not have the variant shows its default one. Changing the weights moves
+    /// some users to other buckets.
+    ///
+    /// # Errors
+    ///
+    /// * All of the errors that `tr` can return
+    ///
+    pub fn tr_weighted(
+        &self,
+        msgid: &str,
+        weights: &[(&str, u32)],
+        seed: &str,
+        args: Option<&FluentArgs>,
+    ) -> Result<WeightedTranslation, Error> {
+        let variant = choose(msgid, weights, seed);
+        let mut weighted = FluentArgs::new();
+        for (name, value) in args.into_iter().flat_map(|args| args.iter()) {
+            if name != VARIANT_ARG {
+                weighted.set(name, value.clone());
+            }
+        }
+        if let Some(variant) = variant {
+            weighted.set(VARIANT_ARG, variant);
+        }
+        Ok(WeightedTranslation {
+            variant: variant.map(String::from),
+            text: self.tr(msgid, Some(&weighted))?,
+        })
+    }
+}
+
+/// The key of `weights` that `seed` falls into for `msgid`.
+fn choose<'w>(msgid: &str, weights: &[(&'w str, u32)], seed: &str) -> Option<&'w str> {
+    let total: u64 = weights.iter().map(|(_, weight)| u64::from(*weight)).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut hash = Fnv::new();
+    hash.write(msgid);
+    hash.write(seed);
+    let mut bucket = hash.0 % total;
+    for (variant, weight) in weights {
+        let weight = u64::from(*weight);
+        if bucket < weight {
+            return Some(variant);
+        }
+        bucket -= weight;
+    }
+    None
+}